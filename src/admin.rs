@@ -0,0 +1,354 @@
+use anyhow::{anyhow, Result};
+use postgres_protocol::escape::{escape_identifier, escape_literal};
+use serde::Serialize;
+use tokio_postgres::{Client, NoTls, Row};
+
+use crate::incremental::parse_table_name;
+use crate::lsn::Lsn;
+use crate::snapshot::{published_tables, PublishedTable};
+
+/// Operations a publication can publish
+const PUBLISH_OPERATIONS: [&str; 4] = ["insert", "update", "delete", "truncate"];
+
+/// Open a regular connection for managing slots and publications
+pub async fn connect(connection_string: &str) -> Result<Client> {
+    let (client, connection) = tokio_postgres::connect(connection_string, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection error: {}", e);
+        }
+    });
+    Ok(client)
+}
+
+/// A logical replication slot and how much WAL it holds back
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotStatus {
+    pub slot_name: String,
+    pub plugin: String,
+    pub database: String,
+    pub confirmed_flush_lsn: Lsn,
+    /// Oldest WAL the slot still needs; `None` once the slot has been invalidated
+    pub restart_lsn: Option<Lsn>,
+    pub active: bool,
+    /// Backend process reading from the slot
+    pub active_pid: Option<i32>,
+    /// WAL between `restart_lsn` and the server's current position, kept for the slot
+    pub retained_wal_bytes: Option<i64>,
+    /// `reserved`, `extended`, `unreserved` or `lost` (PostgreSQL 13+)
+    pub wal_status: Option<String>,
+    /// WAL that can still be written before the slot risks invalidation
+    /// (PostgreSQL 13+, unset without `max_slot_wal_keep_size`)
+    pub safe_wal_size: Option<i64>,
+    pub two_phase: bool,
+}
+
+impl SlotStatus {
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(SlotStatus {
+            slot_name: row.get(0),
+            plugin: row.get(1),
+            database: row.get(2),
+            confirmed_flush_lsn: row
+                .get::<_, Option<&str>>(3)
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or(Lsn::ZERO),
+            restart_lsn: row.get::<_, Option<&str>>(4).map(str::parse).transpose()?,
+            active: row.get(5),
+            active_pid: row.get(6),
+            retained_wal_bytes: row.get(7),
+            wal_status: row.get(8),
+            safe_wal_size: row.get(9),
+            two_phase: row.get(10),
+        })
+    }
+}
+
+/// Columns of `pg_replication_slots` read into a `SlotStatus`. Columns newer servers
+/// added are read through `to_jsonb` so the query also runs on older ones.
+const SLOT_STATUS_QUERY: &str = "\
+    SELECT s.slot_name::text, s.plugin::text, s.database::text, \
+           s.confirmed_flush_lsn::text, s.restart_lsn::text, s.active, s.active_pid, \
+           pg_wal_lsn_diff( \
+               CASE WHEN pg_is_in_recovery() THEN pg_last_wal_receive_lsn() ELSE pg_current_wal_lsn() END, \
+               s.restart_lsn)::int8, \
+           to_jsonb(s) ->> 'wal_status', (to_jsonb(s) ->> 'safe_wal_size')::int8, \
+           coalesce((to_jsonb(s) ->> 'two_phase')::bool, false) \
+    FROM pg_replication_slots s \
+    WHERE s.slot_type = 'logical'";
+
+/// Status of one logical replication slot
+pub async fn slot_status(client: &Client, slot_name: &str) -> Result<SlotStatus> {
+    let query = format!("{} AND s.slot_name = $1", SLOT_STATUS_QUERY);
+    let row = client
+        .query_opt(&query, &[&slot_name])
+        .await?
+        .ok_or_else(|| anyhow!("Replication slot '{}' not found", slot_name))?;
+    SlotStatus::from_row(&row)
+}
+
+/// Every logical replication slot of the server
+pub async fn list_slots(client: &Client) -> Result<Vec<SlotStatus>> {
+    let query = format!("{} ORDER BY s.slot_name", SLOT_STATUS_QUERY);
+    client.query(&query, &[]).await?.iter().map(SlotStatus::from_row).collect()
+}
+
+/// Create a pgoutput slot; returns its consistent point. Two-phase decoding can
+/// only be enabled when the slot is created.
+pub async fn create_slot(client: &Client, slot_name: &str, two_phase: bool) -> Result<Lsn> {
+    let row = if two_phase {
+        // The two_phase argument of pg_create_logical_replication_slot was added in PostgreSQL 14
+        let version_num: i32 = client
+            .query_one("SELECT current_setting('server_version_num')::int", &[])
+            .await?
+            .get(0);
+        if version_num < 140000 {
+            return Err(anyhow!(
+                "Creating a two-phase slot needs PostgreSQL 14+, the server is version {}",
+                version_num / 10000
+            ));
+        }
+        client
+            .query_one(
+                "SELECT lsn::text FROM pg_create_logical_replication_slot($1, 'pgoutput', false, true)",
+                &[&slot_name],
+            )
+            .await?
+    } else {
+        client
+            .query_one("SELECT lsn::text FROM pg_create_logical_replication_slot($1, 'pgoutput')", &[&slot_name])
+            .await?
+    };
+    row.get::<_, &str>(0).parse()
+}
+
+/// Drop a slot, releasing the WAL it retains
+pub async fn drop_slot(client: &Client, slot_name: &str) -> Result<()> {
+    client
+        .execute("SELECT pg_drop_replication_slot($1)", &[&slot_name])
+        .await
+        .map_err(|e| anyhow!("Failed to drop replication slot '{}': {}", slot_name, e))?;
+    Ok(())
+}
+
+/// Move a slot forward without decoding the changes in between; returns its new position
+pub async fn advance_slot(client: &Client, slot_name: &str, lsn: Lsn) -> Result<Lsn> {
+    let row = client
+        .query_one(
+            "SELECT end_lsn::text FROM pg_replication_slot_advance($1, $2::text::pg_lsn)",
+            &[&slot_name, &lsn.to_string()],
+        )
+        .await?;
+    row.get::<_, &str>(0).parse()
+}
+
+/// A publication and the tables it publishes
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicationInfo {
+    pub name: String,
+    pub owner: String,
+    /// `FOR ALL TABLES`
+    pub all_tables: bool,
+    /// Operations published: insert, update, delete, truncate
+    pub publish: Vec<String>,
+    /// Changes of partitions are published as changes of their root table
+    pub via_partition_root: bool,
+    /// Published tables with their column lists and row filters
+    pub tables: Vec<PublishedTable>,
+}
+
+/// Every publication of the current database
+pub async fn list_publications(client: &Client) -> Result<Vec<PublicationInfo>> {
+    query_publications(client, None).await
+}
+
+/// One publication with its tables
+pub async fn show_publication(client: &Client, publication_name: &str) -> Result<PublicationInfo> {
+    query_publications(client, Some(publication_name))
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("Publication '{}' not found", publication_name))
+}
+
+async fn query_publications(client: &Client, publication_name: Option<&str>) -> Result<Vec<PublicationInfo>> {
+    let rows = client
+        .query(
+            "SELECT p.pubname::text, pg_get_userbyid(p.pubowner)::text, p.puballtables, \
+                    p.pubinsert, p.pubupdate, p.pubdelete, p.pubtruncate, \
+                    coalesce((to_jsonb(p) ->> 'pubviaroot')::bool, false) \
+             FROM pg_publication p \
+             WHERE $1::text IS NULL OR p.pubname = $1 \
+             ORDER BY p.pubname",
+            &[&publication_name],
+        )
+        .await?;
+
+    let mut publications = Vec::with_capacity(rows.len());
+    for row in rows {
+        let name: String = row.get(0);
+        let publish = PUBLISH_OPERATIONS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| row.get::<_, bool>(3 + i))
+            .map(|(_, op)| op.to_string())
+            .collect();
+        publications.push(PublicationInfo {
+            tables: published_tables(client, &name).await?,
+            owner: row.get(1),
+            all_tables: row.get(2),
+            publish,
+            via_partition_root: row.get(7),
+            name,
+        });
+    }
+    Ok(publications)
+}
+
+/// A table of a publication as given on the command line: `schema.table`, optionally
+/// followed by a column list `(a, b)` and a row filter `WHERE <expression>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSpec {
+    pub schema: String,
+    pub table: String,
+    /// Columns to publish (PostgreSQL 15+)
+    pub columns: Option<Vec<String>>,
+    /// Rows to publish, as SQL (PostgreSQL 15+)
+    pub row_filter: Option<String>,
+}
+
+impl TableSpec {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        let (name, row_filter) = match s.to_ascii_lowercase().find(" where ") {
+            Some(at) => (&s[..at], Some(s[at + 7..].trim().to_string())),
+            None => (s, None),
+        };
+        if row_filter.as_deref() == Some("") {
+            return Err(anyhow!("Empty row filter in '{}'", s));
+        }
+
+        let (name, columns) = match name.split_once('(') {
+            Some((name, rest)) => {
+                let list = rest
+                    .trim_end()
+                    .strip_suffix(')')
+                    .ok_or_else(|| anyhow!("Unclosed column list in '{}'", s))?;
+                let columns: Vec<String> = list
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+                if columns.is_empty() {
+                    return Err(anyhow!("Empty column list in '{}'", s));
+                }
+                (name, Some(columns))
+            }
+            None => (name, None),
+        };
+
+        let (schema, table) = parse_table_name(name.trim())?;
+        Ok(TableSpec { schema, table, columns, row_filter })
+    }
+
+    /// The table as written in CREATE/ALTER PUBLICATION
+    pub fn to_sql(&self) -> String {
+        let mut sql = format!("{}.{}", escape_identifier(&self.schema), escape_identifier(&self.table));
+        if let Some(columns) = &self.columns {
+            let columns: Vec<String> = columns.iter().map(|c| escape_identifier(c)).collect();
+            sql.push_str(&format!(" ({})", columns.join(", ")));
+        }
+        if let Some(filter) = &self.row_filter {
+            sql.push_str(&format!(" WHERE ({})", filter));
+        }
+        sql
+    }
+}
+
+/// `publish` option from a comma-separated list of operations
+fn publish_option(publish: &str) -> Result<String> {
+    let operations: Vec<String> = publish
+        .split(',')
+        .map(|op| op.trim().to_lowercase())
+        .filter(|op| !op.is_empty())
+        .collect();
+    if let Some(op) = operations.iter().find(|op| !PUBLISH_OPERATIONS.contains(&op.as_str())) {
+        return Err(anyhow!("Unknown operation: {}. Valid options: {}", op, PUBLISH_OPERATIONS.join(", ")));
+    }
+    Ok(format!("publish = {}", escape_literal(&operations.join(", "))))
+}
+
+fn table_list(tables: &[TableSpec]) -> String {
+    tables.iter().map(TableSpec::to_sql).collect::<Vec<_>>().join(", ")
+}
+
+/// CREATE PUBLICATION for the given tables, or for all tables
+pub fn build_create_publication(
+    publication_name: &str,
+    tables: &[TableSpec],
+    all_tables: bool,
+    publish: Option<&str>,
+) -> Result<String> {
+    let mut sql = format!("CREATE PUBLICATION {}", escape_identifier(publication_name));
+    match (all_tables, tables.is_empty()) {
+        (true, false) => return Err(anyhow!("A publication for all tables cannot also list tables")),
+        (true, true) => sql.push_str(" FOR ALL TABLES"),
+        (false, false) => sql.push_str(&format!(" FOR TABLE {}", table_list(tables))),
+        (false, true) => {}
+    }
+    if let Some(publish) = publish {
+        sql.push_str(&format!(" WITH ({})", publish_option(publish)?));
+    }
+    Ok(sql)
+}
+
+/// ALTER PUBLICATION statements adding, dropping or replacing tables and changing
+/// the published operations, to be run together
+pub fn build_alter_publication(
+    publication_name: &str,
+    add: &[TableSpec],
+    drop: &[TableSpec],
+    set: &[TableSpec],
+    publish: Option<&str>,
+) -> Result<Vec<String>> {
+    if !set.is_empty() && (!add.is_empty() || !drop.is_empty()) {
+        return Err(anyhow!("Setting the table list cannot be combined with adding or dropping tables"));
+    }
+    if drop.iter().any(|t| t.columns.is_some() || t.row_filter.is_some()) {
+        return Err(anyhow!("Tables are dropped by name only, without a column list or row filter"));
+    }
+
+    let alter = format!("ALTER PUBLICATION {}", escape_identifier(publication_name));
+    let mut statements = Vec::new();
+    if !set.is_empty() {
+        statements.push(format!("{} SET TABLE {}", alter, table_list(set)));
+    }
+    if !add.is_empty() {
+        statements.push(format!("{} ADD TABLE {}", alter, table_list(add)));
+    }
+    if !drop.is_empty() {
+        statements.push(format!("{} DROP TABLE {}", alter, table_list(drop)));
+    }
+    if let Some(publish) = publish {
+        statements.push(format!("{} SET ({})", alter, publish_option(publish)?));
+    }
+    if statements.is_empty() {
+        return Err(anyhow!("Nothing to change for publication '{}'", publication_name));
+    }
+    Ok(statements)
+}
+
+/// Byte count with a binary unit, like `pg_size_pretty` (e.g. `12.5 MiB`)
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes.abs() < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
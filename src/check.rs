@@ -0,0 +1,455 @@
+use anyhow::Result;
+use postgres_protocol::escape::escape_identifier;
+use serde::Serialize;
+use std::fmt;
+use tokio_postgres::Client;
+
+use crate::admin;
+use crate::decoder::ReplicaIdentity;
+use crate::incremental::parse_table_name;
+use crate::protocol::ReplicationConnection;
+use crate::replication::{ReplicationMode, ReplicationOptions};
+
+/// Outcome of one pre-flight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Streaming works, but something is likely to cause trouble
+    Warn,
+    /// Streaming will fail
+    Fail,
+}
+
+/// A replication prerequisite and whether it is met
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix a failed or doubtful check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl CheckResult {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Pass, detail: detail.into(), fix: None }
+    }
+
+    pub fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.status {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        write!(f, "[{}] {}: {}", label, self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n       Fix: {}", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether any check failed
+pub fn has_failures(results: &[CheckResult]) -> bool {
+    results.iter().any(|r| r.status == CheckStatus::Fail)
+}
+
+/// Check everything streaming from `slot_name` and `publication_name` with these options
+/// needs. A check that cannot be carried out is reported as a warning; an unreachable
+/// server is the only failure that ends the checks early.
+pub async fn run_checks(
+    connection_string: &str,
+    slot_name: &str,
+    publication_name: &str,
+    options: &ReplicationOptions,
+) -> Vec<CheckResult> {
+    let client = match admin::connect(connection_string).await {
+        Ok(client) => client,
+        Err(e) => {
+            return vec![CheckResult::fail(
+                "connection",
+                format!("cannot connect: {}", e),
+                "check the connection string, that the server is running and that pg_hba.conf allows this host and user",
+            )]
+        }
+    };
+
+    let mut results = Vec::new();
+    let checks = [
+        server_checks(&client, options).await,
+        slot_checks(&client, slot_name, options).await,
+        publication_checks(&client, publication_name, options).await,
+    ];
+    for (name, checks) in ["server", "slot", "publication"].into_iter().zip(checks) {
+        match checks {
+            Ok(checks) => results.extend(checks),
+            Err(e) => results.push(CheckResult::warn(name, format!("could not be checked: {}", e), "run the check as a user that can read the system catalogs")),
+        }
+    }
+
+    if options.mode == ReplicationMode::Streaming {
+        results.push(replication_connection_check(connection_string).await);
+    }
+    results
+}
+
+async fn server_checks(client: &Client, options: &ReplicationOptions) -> Result<Vec<CheckResult>> {
+    let row = client
+        .query_one(
+            "SELECT current_setting('server_version_num')::int, current_setting('server_version'), \
+                    current_setting('wal_level'), current_user::text, \
+                    coalesce((SELECT rolreplication OR rolsuper FROM pg_roles WHERE rolname = current_user), false), \
+                    current_setting('max_wal_senders')::int, \
+                    (SELECT count(*)::int FROM pg_stat_replication)",
+            &[],
+        )
+        .await?;
+    let version_num: i32 = row.get(0);
+    let version: &str = row.get(1);
+    let wal_level: &str = row.get(2);
+    let user: &str = row.get(3);
+    let can_replicate: bool = row.get(4);
+    let max_wal_senders: i32 = row.get(5);
+    let wal_senders: i32 = row.get(6);
+
+    let mut results = vec![
+        CheckResult::pass("connection", format!("connected to PostgreSQL {} as {}", version, user)),
+        check_server_version(version_num, options),
+        check_wal_level(wal_level),
+    ];
+    results.push(if can_replicate {
+        CheckResult::pass("replication privilege", format!("{} has the REPLICATION attribute", user))
+    } else {
+        CheckResult::fail(
+            "replication privilege",
+            format!("{} lacks the REPLICATION attribute", user),
+            format!("ALTER ROLE {} WITH REPLICATION;", escape_identifier(user)),
+        )
+    });
+    if options.mode == ReplicationMode::Streaming {
+        results.push(if wal_senders < max_wal_senders {
+            CheckResult::pass("max_wal_senders", format!("{} of {} WAL senders in use", wal_senders, max_wal_senders))
+        } else {
+            CheckResult::fail(
+                "max_wal_senders",
+                format!("all {} WAL senders are in use", max_wal_senders),
+                "raise max_wal_senders in postgresql.conf and restart PostgreSQL, or stop an unused replication connection",
+            )
+        });
+    }
+    Ok(results)
+}
+
+/// The server must be new enough for the requested pgoutput protocol and options
+pub fn check_server_version(version_num: i32, options: &ReplicationOptions) -> CheckResult {
+    let mut needed = vec![(100000, "pgoutput".to_string())];
+    let protocol_version = match options.protocol_version {
+        2 => Some(140000),
+        3 => Some(150000),
+        4 => Some(160000),
+        _ => None,
+    };
+    if let Some(version) = protocol_version {
+        needed.push((version, format!("protocol version {}", options.protocol_version)));
+    }
+    if options.binary {
+        needed.push((140000, "--binary".to_string()));
+    }
+    if options.messages {
+        needed.push((140000, "--messages".to_string()));
+    }
+    if options.two_phase {
+        needed.push((150000, "--two-phase".to_string()));
+    }
+
+    let major = |num: i32| num / 10000;
+    match needed.iter().filter(|(version, _)| version_num < *version).max_by_key(|(version, _)| *version) {
+        Some((version, feature)) => CheckResult::fail(
+            "server version",
+            format!("{} needs PostgreSQL {}+, the server is version {}", feature, major(*version), major(version_num)),
+            format!("upgrade to PostgreSQL {} or leave out {}", major(*version), feature),
+        ),
+        None => CheckResult::pass("server version", format!("PostgreSQL {} supports the requested options", major(version_num))),
+    }
+}
+
+/// Logical decoding needs `wal_level = logical`
+pub fn check_wal_level(wal_level: &str) -> CheckResult {
+    if wal_level == "logical" {
+        CheckResult::pass("wal_level", "logical")
+    } else {
+        CheckResult::fail(
+            "wal_level",
+            format!("is '{}', logical replication needs 'logical'", wal_level),
+            "ALTER SYSTEM SET wal_level = logical; then restart PostgreSQL",
+        )
+    }
+}
+
+async fn slot_checks(client: &Client, slot_name: &str, options: &ReplicationOptions) -> Result<Vec<CheckResult>> {
+    let creates_slot = options.create_slot || options.snapshot.is_some();
+    let existing = admin::list_slots(client).await?;
+    let Some(slot) = existing.iter().find(|s| s.slot_name == slot_name) else {
+        let row = client
+            .query_one(
+                "SELECT current_setting('max_replication_slots')::int, (SELECT count(*)::int FROM pg_replication_slots)",
+                &[],
+            )
+            .await?;
+        let (max_slots, used): (i32, i32) = (row.get(0), row.get(1));
+        if !creates_slot {
+            return Ok(vec![CheckResult::fail(
+                "replication slot",
+                format!("slot '{}' does not exist", slot_name),
+                format!("pass --create-slot or run: pgoutput-stream slot create {}", slot_name),
+            )]);
+        }
+        return Ok(vec![if used < max_slots {
+            CheckResult::pass("replication slot", format!("slot '{}' will be created ({} of {} slots in use)", slot_name, used, max_slots))
+        } else {
+            CheckResult::fail(
+                "replication slot",
+                format!("slot '{}' cannot be created: all {} replication slots are in use", slot_name, max_slots),
+                "drop an unused slot (pgoutput-stream slot list / slot drop) or raise max_replication_slots and restart PostgreSQL",
+            )
+        }]);
+    };
+
+    let mut results = Vec::new();
+    if slot.plugin != "pgoutput" {
+        results.push(CheckResult::fail(
+            "replication slot",
+            format!("slot '{}' uses the {} plugin, not pgoutput", slot_name, slot.plugin),
+            format!("use another slot name, or drop the slot: pgoutput-stream slot drop {}", slot_name),
+        ));
+    } else if slot.wal_status.as_deref() == Some("lost") {
+        results.push(CheckResult::fail(
+            "replication slot",
+            format!("slot '{}' was invalidated and the WAL it needs is gone", slot_name),
+            format!("drop and recreate the slot, then resync the targets: pgoutput-stream slot drop {}", slot_name),
+        ));
+    } else if slot.active {
+        results.push(CheckResult::fail(
+            "replication slot",
+            format!("slot '{}' is in use by process {}", slot_name, slot.active_pid.unwrap_or_default()),
+            "stop the other consumer or use another slot",
+        ));
+    } else {
+        results.push(CheckResult::pass(
+            "replication slot",
+            format!("slot '{}' exists at {}", slot_name, slot.confirmed_flush_lsn),
+        ));
+    }
+    if options.two_phase && !slot.two_phase {
+        results.push(CheckResult::warn(
+            "replication slot",
+            format!("slot '{}' was created without two-phase decoding", slot_name),
+            format!("recreate the slot with: pgoutput-stream slot create {} --two-phase", slot_name),
+        ));
+    }
+    if let Some(bytes) = slot.retained_wal_bytes.filter(|_| matches!(slot.wal_status.as_deref(), Some("extended" | "unreserved"))) {
+        results.push(CheckResult::warn(
+            "replication slot",
+            format!("slot '{}' retains {} of WAL (wal_status {})", slot_name, admin::format_bytes(bytes), slot.wal_status.as_deref().unwrap_or_default()),
+            "consume the slot soon or it may be invalidated; check disk space",
+        ));
+    }
+    Ok(results)
+}
+
+/// A published table's catalog facts relevant to replication
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableFacts {
+    pub schema: String,
+    pub table: String,
+    pub replica_identity: ReplicaIdentity,
+    pub has_primary_key: bool,
+    pub can_select: bool,
+}
+
+/// UPDATE and DELETE on a published table fail on the server unless the table's
+/// replica identity can identify the old row
+pub fn check_replica_identity(table: &TableFacts, publishes_update_or_delete: bool) -> CheckResult {
+    let name = format!("{}.{}", table.schema, table.table);
+    let qualified = format!("{}.{}", escape_identifier(&table.schema), escape_identifier(&table.table));
+    let fix = format!("ALTER TABLE {} REPLICA IDENTITY FULL; (or add a primary key)", qualified);
+    match table.replica_identity {
+        ReplicaIdentity::Default if !table.has_primary_key && publishes_update_or_delete => CheckResult::fail(
+            "replica identity",
+            format!("{} has no primary key, so UPDATE and DELETE on it fail", name),
+            fix,
+        ),
+        ReplicaIdentity::Nothing if publishes_update_or_delete => CheckResult::fail(
+            "replica identity",
+            format!("{} has REPLICA IDENTITY NOTHING, so UPDATE and DELETE on it fail", name),
+            fix,
+        ),
+        ReplicaIdentity::Default if !table.has_primary_key => CheckResult::warn(
+            "replica identity",
+            format!("{} has no primary key; targets cannot key its rows", name),
+            fix,
+        ),
+        identity => CheckResult::pass(
+            "replica identity",
+            format!("{} uses replica identity {}", name, format!("{:?}", identity).to_lowercase()),
+        ),
+    }
+}
+
+async fn publication_checks(
+    client: &Client,
+    publication_name: &str,
+    options: &ReplicationOptions,
+) -> Result<Vec<CheckResult>> {
+    let publication = match admin::show_publication(client, publication_name).await {
+        Ok(publication) => publication,
+        Err(_) => {
+            return Ok(vec![CheckResult::fail(
+                "publication",
+                format!("publication '{}' does not exist", publication_name),
+                format!(
+                    "CREATE PUBLICATION {} FOR TABLE ...; or run: pgoutput-stream publication create {} --table <schema.table>",
+                    escape_identifier(publication_name),
+                    publication_name
+                ),
+            )])
+        }
+    };
+
+    let mut results = Vec::new();
+    if publication.tables.is_empty() {
+        results.push(CheckResult::warn(
+            "publication",
+            format!("publication '{}' has no tables", publication_name),
+            format!("pgoutput-stream publication alter {} --add-table <schema.table>", publication_name),
+        ));
+    } else {
+        results.push(CheckResult::pass(
+            "publication",
+            format!("publication '{}' publishes {} table(s): {}", publication_name, publication.tables.len(), publication.publish.join(", ")),
+        ));
+    }
+
+    let rows = client
+        .query(
+            "SELECT n.nspname::text, c.relname::text, c.relreplident::text, \
+                    EXISTS (SELECT 1 FROM pg_index i WHERE i.indrelid = c.oid AND i.indisprimary), \
+                    has_table_privilege(c.oid, 'SELECT') \
+             FROM pg_publication_tables pt \
+             JOIN pg_namespace n ON n.nspname = pt.schemaname \
+             JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = pt.tablename \
+             WHERE pt.pubname = $1 \
+             ORDER BY 1, 2",
+            &[&publication_name],
+        )
+        .await?;
+    let publishes_update_or_delete = publication.publish.iter().any(|op| op == "update" || op == "delete");
+    let reads_tables = options.snapshot.is_some() || options.incremental_snapshot.is_some();
+
+    let mut usable = 0;
+    for row in &rows {
+        let table = TableFacts {
+            schema: row.get(0),
+            table: row.get(1),
+            replica_identity: ReplicaIdentity::from_byte(row.get::<_, &str>(2).as_bytes()[0])?,
+            has_primary_key: row.get(3),
+            can_select: row.get(4),
+        };
+        let check = check_replica_identity(&table, publishes_update_or_delete);
+        if check.status == CheckStatus::Pass {
+            usable += 1;
+        } else {
+            results.push(check);
+        }
+        if reads_tables && !table.can_select {
+            results.push(CheckResult::fail(
+                "table privilege",
+                format!("{}.{} cannot be read for the snapshot", table.schema, table.table),
+                format!(
+                    "GRANT SELECT ON {}.{} TO <user>;",
+                    escape_identifier(&table.schema),
+                    escape_identifier(&table.table)
+                ),
+            ));
+        }
+    }
+    if usable > 0 {
+        results.push(CheckResult::pass("replica identity", format!("{} table(s) can be replicated", usable)));
+    }
+
+    if let Some(incremental) = &options.incremental_snapshot {
+        results.push(signal_table_check(client, &incremental.signal_table, &publication.tables).await?);
+    }
+    Ok(results)
+}
+
+/// The signal table must be published and writable, so watermarks can be written and read back
+async fn signal_table_check(
+    client: &Client,
+    signal_table: &str,
+    published: &[crate::snapshot::PublishedTable],
+) -> Result<CheckResult> {
+    let (schema, table) = parse_table_name(signal_table)?;
+    let qualified = format!("{}.{}", escape_identifier(&schema), escape_identifier(&table));
+    let row = client
+        .query_one(
+            "SELECT to_regclass($1) IS NOT NULL, \
+                    CASE WHEN to_regclass($1) IS NOT NULL THEN has_table_privilege(to_regclass($1), 'INSERT') END",
+            &[&qualified],
+        )
+        .await?;
+    let (exists, can_insert): (bool, Option<bool>) = (row.get(0), row.get(1));
+    let name = format!("{}.{}", schema, table);
+
+    Ok(if !exists {
+        CheckResult::fail(
+            "signal table",
+            format!("{} does not exist", name),
+            format!("CREATE TABLE {} (id varchar(64) PRIMARY KEY, type varchar(32) NOT NULL, data text);", qualified),
+        )
+    } else if !published.iter().any(|t| t.schema == schema && t.table == table) {
+        CheckResult::fail(
+            "signal table",
+            format!("{} is not part of the publication, so signals are never seen", name),
+            format!("ALTER PUBLICATION ... ADD TABLE {};", qualified),
+        )
+    } else if can_insert != Some(true) {
+        CheckResult::fail(
+            "signal table",
+            format!("{} is not writable, so snapshot watermarks cannot be written", name),
+            format!("GRANT INSERT ON {} TO <user>;", qualified),
+        )
+    } else {
+        CheckResult::pass("signal table", format!("{} is published and writable", name))
+    })
+}
+
+/// Streaming needs a `replication=database` connection, which pg_hba.conf may refuse
+async fn replication_connection_check(connection_string: &str) -> CheckResult {
+    let result = async {
+        let config = connection_string.parse::<tokio_postgres::Config>()?;
+        let mut conn = ReplicationConnection::connect(&config).await?;
+        conn.simple_query("IDENTIFY_SYSTEM").await
+    }
+    .await;
+    match result {
+        Ok(_) => CheckResult::pass("replication connection", "the server accepts replication connections"),
+        Err(e) => CheckResult::fail(
+            "replication connection",
+            format!("cannot open a replication connection: {}", e),
+            "allow it in pg_hba.conf (e.g. `host replication <user> <address> scram-sha-256`) and reload, or use --replication-mode polling",
+        ),
+    }
+}
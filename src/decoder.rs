@@ -1,337 +1,1136 @@
 use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
-use once_cell::sync::Lazy;
+use std::fmt;
+use crate::lsn::Lsn;
+use crate::value::{builtin_type_name, decode_binary, decode_text, PgValue, TypeModifier};
+
+/// Column values of a decoded row, keyed by column name in the relation's column order
+pub type Tuple = IndexMap<String, Option<PgValue>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Change {
     Begin {
-        lsn: String,
+        /// LSN of the transaction's commit record
+        #[serde(alias = "lsn")]
+        final_lsn: Lsn,
+        /// Commit timestamp (microseconds since 2000-01-01)
         timestamp: i64,
         xid: u32,
+        /// Replication origin the transaction was replayed from, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
     },
     Commit {
-        lsn: String,
+        /// Commit flags (currently unused by PostgreSQL, always 0)
+        #[serde(default)]
+        flags: u8,
+        /// LSN of the commit record
+        lsn: Lsn,
+        /// End of the commit record; streaming resumes from here
+        #[serde(default)]
+        end_lsn: Lsn,
+        /// Commit timestamp (microseconds since 2000-01-01)
         timestamp: i64,
+        /// Id of the committed transaction, from its BEGIN
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
     },
     Insert {
         relation_id: u32,
         schema: String,
         table: String,
-        new_tuple: HashMap<String, Option<String>>,
+        new_tuple: Tuple,
+        /// Replica identity key columns of the relation
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        key_columns: Vec<String>,
+        /// WAL position the change was read from; set by the replication stream
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lsn: Option<Lsn>,
+        /// Transaction the change belongs to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transaction: Option<TransactionInfo>,
+        /// (Sub)transaction id, only sent for changes inside streamed blocks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
     },
     Update {
         relation_id: u32,
         schema: String,
         table: String,
-        old_tuple: Option<HashMap<String, Option<String>>>,
-        new_tuple: HashMap<String, Option<String>>,
+        old_tuple: Option<Tuple>,
+        /// The old tuple is a key-only ('K') image: only the replica identity
+        /// columns carry values, every other column is NULL
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        old_key_only: bool,
+        /// New row; columns listed in `unchanged_columns` are absent
+        new_tuple: Tuple,
+        /// TOASTed columns the UPDATE did not modify, whose values were not sent
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        unchanged_columns: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        key_columns: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lsn: Option<Lsn>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transaction: Option<TransactionInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
     },
     Delete {
         relation_id: u32,
         schema: String,
         table: String,
-        old_tuple: HashMap<String, Option<String>>,
+        old_tuple: Tuple,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        key_columns: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lsn: Option<Lsn>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transaction: Option<TransactionInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
+    },
+    /// A row copied by the initial snapshot, or by an incremental snapshot while
+    /// streaming; consumers apply it like an INSERT (an upsert when incremental)
+    Snapshot {
+        relation_id: u32,
+        schema: String,
+        table: String,
+        new_tuple: Tuple,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        key_columns: Vec<String>,
+        /// Slot position the snapshot is consistent with; streaming starts here.
+        /// For an incremental snapshot, the position of the chunk's high watermark
+        lsn: Lsn,
+        /// Read by an incremental snapshot requested through the signal table
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        incremental: bool,
     },
     Relation {
         relation_id: u32,
         schema: String,
         table: String,
+        #[serde(default)]
+        replica_identity: ReplicaIdentity,
         columns: Vec<ColumnInfo>,
     },
+    /// A relation was described again with different columns (e.g. after ALTER TABLE);
+    /// follows the Relation event that carries the new definition
+    SchemaChanged {
+        relation_id: u32,
+        schema: String,
+        table: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        added: Vec<ColumnInfo>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        dropped: Vec<ColumnInfo>,
+        /// A dropped and an added column at the same position with the same type
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        renamed: Vec<ColumnChange>,
+        /// Columns whose type or type modifier changed
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        retyped: Vec<ColumnChange>,
+    },
+    /// Replication origin of the current transaction; sent right after its BEGIN
+    Origin {
+        lsn: Lsn,
+        name: String,
+    },
+    /// Logical decoding message emitted with pg_logical_emit_message()
+    Message {
+        /// Whether the message is part of a transaction (decoded at its commit)
+        transactional: bool,
+        prefix: String,
+        /// Message content, decoded as UTF-8
+        content: String,
+        lsn: Lsn,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
+    },
+    /// TRUNCATE of one or more tables
+    Truncate {
+        relations: Vec<TruncatedRelation>,
+        cascade: bool,
+        restart_identity: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lsn: Option<Lsn>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transaction: Option<TransactionInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        xid: Option<u32>,
+    },
+    /// Start of a block of changes from an in-progress transaction (protocol v2)
+    StreamStart {
+        xid: u32,
+        first_segment: bool,
+    },
+    /// End of the current streamed block
+    StreamStop,
+    /// A streamed transaction committed
+    StreamCommit {
+        xid: u32,
+        lsn: Lsn,
+        #[serde(default)]
+        end_lsn: Lsn,
+        timestamp: i64,
+    },
+    /// A streamed transaction, or one of its subtransactions, rolled back
+    StreamAbort {
+        xid: u32,
+        subxid: u32,
+    },
+    /// Start of a transaction prepared for two-phase commit (protocol v3)
+    BeginPrepare {
+        lsn: Lsn,
+        timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// End of a prepared transaction's changes (PREPARE TRANSACTION)
+    Prepare {
+        lsn: Lsn,
+        timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// COMMIT PREPARED of an earlier prepared transaction
+    CommitPrepared {
+        lsn: Lsn,
+        timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// ROLLBACK PREPARED of an earlier prepared transaction
+    RollbackPrepared {
+        lsn: Lsn,
+        timestamp: i64,
+        prepare_end_lsn: Lsn,
+        prepare_timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// PREPARE TRANSACTION of a streamed transaction
+    StreamPrepare {
+        lsn: Lsn,
+        timestamp: i64,
+        xid: u32,
+        gid: String,
+    },
+    /// A whole committed transaction, written by the transaction-grouping output
+    /// in place of its Begin, changes and Commit
+    Transaction {
+        xid: u32,
+        /// LSN of the commit record
+        commit_lsn: Lsn,
+        /// End of the commit record
+        end_lsn: Lsn,
+        /// Commit timestamp (microseconds since 2000-01-01)
+        commit_ts: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
+        /// Everything between Begin and Commit, in order
+        changes: Vec<Change>,
+    },
 }
 
 impl Change {
     /// Extract LSN from Change event if available
-    pub fn get_lsn(&self) -> Option<&str> {
-        match self {
-            Change::Begin { lsn, .. } => Some(lsn),
+    pub fn get_lsn(&self) -> Option<Lsn> {
+        match *self {
+            Change::Begin { final_lsn, .. } => Some(final_lsn),
+            Change::Insert { lsn: Some(lsn), .. }
+            | Change::Update { lsn: Some(lsn), .. }
+            | Change::Delete { lsn: Some(lsn), .. }
+            | Change::Truncate { lsn: Some(lsn), .. } => Some(lsn),
+            Change::Snapshot { lsn, .. } => Some(lsn),
             Change::Commit { lsn, .. } => Some(lsn),
+            Change::StreamCommit { lsn, .. } => Some(lsn),
+            Change::BeginPrepare { lsn, .. } => Some(lsn),
+            Change::Prepare { lsn, .. } => Some(lsn),
+            Change::CommitPrepared { lsn, .. } => Some(lsn),
+            Change::RollbackPrepared { lsn, .. } => Some(lsn),
+            Change::StreamPrepare { lsn, .. } => Some(lsn),
+            Change::Transaction { commit_lsn, .. } => Some(commit_lsn),
+            _ => None,
+        }
+    }
+
+    /// Record the WAL position a data change was read from
+    pub fn set_lsn(&mut self, position: Lsn) {
+        if let Change::Insert { lsn, .. }
+        | Change::Update { lsn, .. }
+        | Change::Delete { lsn, .. }
+        | Change::Truncate { lsn, .. } = self
+        {
+            *lsn = Some(position);
+        }
+    }
+
+    /// Transaction a data change belongs to, if known
+    pub fn transaction(&self) -> Option<&TransactionInfo> {
+        match self {
+            Change::Insert { transaction, .. }
+            | Change::Update { transaction, .. }
+            | Change::Delete { transaction, .. }
+            | Change::Truncate { transaction, .. } => transaction.as_ref(),
             _ => None,
         }
     }
+
+    /// Whether this change opens a transaction (BEGIN or BEGIN PREPARE)
+    pub fn starts_transaction(&self) -> bool {
+        matches!(self, Change::Begin { .. } | Change::BeginPrepare { .. })
+    }
+
+    /// The Begin, changes and Commit a grouped transaction stands for, for targets
+    /// that write changes one at a time; `None` for other changes
+    pub fn expand_transaction(&self) -> Option<Vec<Change>> {
+        let Change::Transaction { xid, commit_lsn, end_lsn, commit_ts, origin, changes } = self else {
+            return None;
+        };
+
+        let mut expanded = Vec::with_capacity(changes.len() + 2);
+        expanded.push(Change::Begin {
+            final_lsn: *commit_lsn,
+            timestamp: *commit_ts,
+            xid: *xid,
+            origin: origin.clone(),
+        });
+        expanded.extend(changes.iter().cloned());
+        expanded.push(Change::Commit {
+            flags: 0,
+            lsn: *commit_lsn,
+            end_lsn: *end_lsn,
+            timestamp: *commit_ts,
+            xid: Some(*xid),
+        });
+        Some(expanded)
+    }
+
+    /// Whether this change completes a unit of work whose position can be
+    /// confirmed to PostgreSQL once it has been delivered
+    pub fn ends_transaction(&self) -> bool {
+        matches!(
+            self,
+            Change::Commit { .. }
+                | Change::StreamCommit { .. }
+                | Change::Prepare { .. }
+                | Change::StreamPrepare { .. }
+                | Change::CommitPrepared { .. }
+                | Change::RollbackPrepared { .. }
+        )
+    }
+
+    /// Values of the replica identity key of the row a data change is about:
+    /// the new row for INSERT, UPDATE and snapshot rows, the removed row for DELETE.
+    /// `None` for other changes and for relations without a key.
+    pub fn key(&self) -> Option<Tuple> {
+        let (tuple, key_columns) = match self {
+            Change::Insert { new_tuple, key_columns, .. }
+            | Change::Update { new_tuple, key_columns, .. }
+            | Change::Snapshot { new_tuple, key_columns, .. } => (new_tuple, key_columns),
+            Change::Delete { old_tuple, key_columns, .. } => (old_tuple, key_columns),
+            _ => return None,
+        };
+        if key_columns.is_empty() {
+            return None;
+        }
+        Some(
+            key_columns
+                .iter()
+                .filter_map(|name| tuple.get_key_value(name))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        )
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A relation's REPLICA IDENTITY, which decides what UPDATE and DELETE send of the old row
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicaIdentity {
+    /// Primary key columns
+    #[default]
+    Default,
+    /// No old row
+    Nothing,
+    /// All columns
+    Full,
+    /// Columns of a designated unique index
+    Index,
+}
+
+impl ReplicaIdentity {
+    /// Parse the setting from pgoutput's Relation message or `pg_class.relreplident`
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            b'd' => Ok(ReplicaIdentity::Default),
+            b'n' => Ok(ReplicaIdentity::Nothing),
+            b'f' => Ok(ReplicaIdentity::Full),
+            b'i' => Ok(ReplicaIdentity::Index),
+            _ => Err(anyhow!("Unknown replica identity setting: {}", byte as char)),
+        }
+    }
+}
+
+/// Transaction a data change belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransactionInfo {
+    /// Top-level transaction id
+    pub xid: u32,
+    /// Commit timestamp (microseconds since 2000-01-01); unknown for changes of
+    /// prepared transactions and changes streamed before their transaction committed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_timestamp: Option<i64>,
+}
+
+/// A table affected by a TRUNCATE
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruncatedRelation {
+    pub relation_id: u32,
+    pub schema: String,
+    pub table: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColumnInfo {
     pub name: String,
     pub type_id: u32,
     pub flags: u8,
+    /// Type modifier (`atttypmod`), -1 when the type has none
+    #[serde(default = "no_type_modifier")]
+    pub type_modifier: i32,
+    /// Human-readable name of a built-in type, or the schema-qualified name of a
+    /// custom type (enum, domain, composite, ...) announced by a TYPE message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    /// Declared length of character and bit string columns, e.g. 64 for `varchar(64)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<i32>,
+    /// Precision of numeric columns, or fractional second digits of time columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<i32>,
+    /// Scale of numeric columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<i32>,
 }
 
-// Thread-safe relation cache
-static RELATION_CACHE: Lazy<Mutex<HashMap<u32, (String, String, Vec<ColumnInfo>)>>> = 
-    Lazy::new(|| Mutex::new(HashMap::new()));
+fn no_type_modifier() -> i32 {
+    -1
+}
 
-/// Get column metadata for a relation from the cache
-pub fn get_relation_columns(relation_id: u32) -> Option<Vec<ColumnInfo>> {
-    let cache = RELATION_CACHE.lock().unwrap();
-    cache.get(&relation_id).map(|(_, _, cols)| cols.clone())
+/// A column's definition before and after a schema change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnChange {
+    pub before: ColumnInfo,
+    pub after: ColumnInfo,
 }
 
-pub fn decode_pgoutput_message(data: &[u8]) -> Result<Option<Change>> {
-    if data.is_empty() {
-        return Ok(None);
+impl ColumnInfo {
+    /// Whether the column is part of the relation's replica identity key (flag bit 1)
+    pub fn is_key(&self) -> bool {
+        self.flags & 1 != 0
     }
 
-    let msg_type = data[0] as char;
-    let rest = &data[1..];
+    /// Type name including its modifier, e.g. `numeric(12,2)` or `character varying(64)`
+    pub fn formatted_type(&self) -> Option<String> {
+        let type_name = self.type_name.as_ref()?;
+        let (base, array) = match type_name.strip_suffix("[]") {
+            Some(base) => (base, "[]"),
+            None => (type_name.as_str(), ""),
+        };
+        let modifier = match (self.length, self.precision, self.scale) {
+            (Some(length), _, _) => format!("({})", length),
+            (None, Some(precision), Some(scale)) => format!("({},{})", precision, scale),
+            (None, Some(precision), None) => format!("({})", precision),
+            (None, None, _) => String::new(),
+        };
+        // Time types put the precision before "with/without time zone"
+        Some(match base.split_once(" with") {
+            Some((head, zone)) if !modifier.is_empty() => format!("{}{} with{}{}", head, modifier, zone, array),
+            _ => format!("{}{}{}", base, modifier, array),
+        })
+    }
+}
 
-    match msg_type {
-        'B' => decode_begin(rest),
-        'C' => decode_commit(rest),
-        'R' => decode_relation(rest),
-        'I' => decode_insert(rest),
-        'U' => decode_update(rest),
-        'D' => decode_delete(rest),
-        'O' | 'T' | 'Y' => {
-            // Origin, Type, Truncate - not implemented yet
-            Ok(None)
-        }
-        _ => {
-            eprintln!("Unknown message type: {}", msg_type);
-            Ok(None)
+/// Cached relation metadata: (schema, table, columns)
+type RelationEntry = (String, String, Vec<ColumnInfo>);
+
+
+/// Compare two definitions of a relation; `None` when no column was added, dropped,
+/// renamed or retyped (PostgreSQL also resends unchanged Relation messages)
+fn schema_change(
+    relation_id: u32,
+    schema: &str,
+    table: &str,
+    old: &[ColumnInfo],
+    new: &[ColumnInfo],
+) -> Option<Change> {
+    let same_type = |a: &ColumnInfo, b: &ColumnInfo| a.type_id == b.type_id && a.type_modifier == b.type_modifier;
+
+    let mut added: Vec<(usize, &ColumnInfo)> = new
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| !old.iter().any(|o| o.name == column.name))
+        .collect();
+    let mut dropped = Vec::new();
+    let mut renamed = Vec::new();
+    let mut retyped = Vec::new();
+
+    for (position, column) in old.iter().enumerate() {
+        match new.iter().find(|c| c.name == column.name) {
+            Some(current) if !same_type(column, current) => retyped.push(ColumnChange {
+                before: column.clone(),
+                after: current.clone(),
+            }),
+            Some(_) => {}
+            None => match added.iter().position(|(p, c)| *p == position && same_type(column, c)) {
+                Some(index) => renamed.push(ColumnChange {
+                    before: column.clone(),
+                    after: added.remove(index).1.clone(),
+                }),
+                None => dropped.push(column.clone()),
+            },
         }
     }
+
+    if added.is_empty() && dropped.is_empty() && renamed.is_empty() && retyped.is_empty() {
+        return None;
+    }
+    Some(Change::SchemaChanged {
+        relation_id,
+        schema: schema.to_string(),
+        table: table.to_string(),
+        added: added.into_iter().map(|(_, column)| column.clone()).collect(),
+        dropped,
+        renamed,
+        retyped,
+    })
 }
 
-fn decode_begin(data: &[u8]) -> Result<Option<Change>> {
-    if data.len() < 20 {
-        return Err(anyhow!("Invalid BEGIN message length"));
+/// A pgoutput message that ends before a field it declares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Type byte of the message being decoded
+    pub message_type: char,
+    /// Field that could not be read
+    pub field: &'static str,
+    /// Byte offset of the field within the message (the type byte is offset 0)
+    pub offset: usize,
+    /// Bytes the field needs
+    pub expected: usize,
+    /// Bytes left in the message at `offset`
+    pub available: usize,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Truncated '{}' message: {} at offset {} needs {} bytes, {} available",
+            self.message_type, self.field, self.offset, self.expected, self.available
+        )
     }
+}
 
-    let lsn = u64::from_be_bytes(data[0..8].try_into()?);
-    let timestamp = i64::from_be_bytes(data[8..16].try_into()?);
-    let xid = u32::from_be_bytes(data[16..20].try_into()?);
+impl std::error::Error for DecodeError {}
 
-    Ok(Some(Change::Begin {
-        lsn: format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFFFFFF),
-        timestamp,
-        xid,
-    }))
+/// Bounds-checked reader over a single pgoutput message.
+///
+/// Every read either consumes the bytes it needs or fails with a `DecodeError`
+/// describing the short read, so malformed input cannot index out of bounds.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+    message_type: char,
 }
 
-fn decode_commit(data: &[u8]) -> Result<Option<Change>> {
-    if data.len() < 17 {
-        return Err(anyhow!("Invalid COMMIT message length"));
+impl<'a> Cursor<'a> {
+    /// Start reading a message right after its type byte
+    fn new(data: &'a [u8]) -> Self {
+        let message_type = data.first().map_or('\0', |b| *b as char);
+        Self {
+            data,
+            pos: data.len().min(1),
+            message_type,
+        }
     }
 
-    let _flags = data[0];
-    let lsn = u64::from_be_bytes(data[1..9].try_into()?);
-    let _end_lsn = u64::from_be_bytes(data[9..17].try_into()?);
-    let timestamp = i64::from_be_bytes(data[17..25].try_into()?);
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
 
-    Ok(Some(Change::Commit {
-        lsn: format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFFFFFF),
-        timestamp,
-    }))
+    fn short_read(&self, field: &'static str, expected: usize) -> DecodeError {
+        DecodeError {
+            message_type: self.message_type,
+            field,
+            offset: self.pos,
+            expected,
+            available: self.remaining(),
+        }
+    }
+
+    fn take(&mut self, len: usize, field: &'static str) -> Result<&'a [u8], DecodeError> {
+        if len > self.remaining() {
+            return Err(self.short_read(field, len));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self, field: &'static str) -> Result<[u8; N], DecodeError> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.take(N, field)?);
+        Ok(bytes)
+    }
+
+    fn u8(&mut self, field: &'static str) -> Result<u8, DecodeError> {
+        Ok(self.array::<1>(field)?[0])
+    }
+
+    fn u16(&mut self, field: &'static str) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.array(field)?))
+    }
+
+    fn u32(&mut self, field: &'static str) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.array(field)?))
+    }
+
+    fn i32(&mut self, field: &'static str) -> Result<i32, DecodeError> {
+        Ok(i32::from_be_bytes(self.array(field)?))
+    }
+
+    fn u64(&mut self, field: &'static str) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.array(field)?))
+    }
+
+    fn i64(&mut self, field: &'static str) -> Result<i64, DecodeError> {
+        Ok(i64::from_be_bytes(self.array(field)?))
+    }
+
+    fn lsn(&mut self, field: &'static str) -> Result<Lsn, DecodeError> {
+        self.u64(field).map(Lsn)
+    }
+
+    /// Read a null-terminated string
+    fn cstring(&mut self, field: &'static str) -> Result<String, DecodeError> {
+        let rest = &self.data[self.pos..];
+        let end = rest
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| self.short_read(field, rest.len() + 1))?;
+        let s = String::from_utf8_lossy(&rest[..end]).to_string();
+        self.pos += end + 1;
+        Ok(s)
+    }
 }
 
-fn decode_relation(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+/// Decoding state of one replication stream.
+///
+/// pgoutput describes a relation (and each non-built-in type) once and refers to it
+/// by OID afterwards, so every stream keeps its own record of what it has been sent.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    relations: HashMap<u32, RelationEntry>,
+    /// Non-built-in type names from Type messages: OID -> (namespace, name)
+    types: HashMap<u32, (String, String)>,
+    /// SchemaChanged event for the Relation message decoded last, if its columns changed
+    schema_change: Option<Change>,
+    /// Transaction opened by the last BEGIN (or BEGIN PREPARE) and not yet committed
+    transaction: Option<TransactionInfo>,
+    /// Transaction of the streamed block being decoded
+    stream_xid: Option<u32>,
+}
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    let schema = read_string(data, &mut pos)?;
-    let table = read_string(data, &mut pos)?;
+    /// Get column metadata for a relation announced on this stream
+    pub fn get_relation_columns(&self, relation_id: u32) -> Option<Vec<ColumnInfo>> {
+        self.relations.get(&relation_id).map(|(_, _, cols)| cols.clone())
+    }
 
-    let _replica_identity = data[pos];
-    pos += 1;
+    /// Get the schema-qualified name of a type announced by a Type message,
+    /// or the name of a built-in type
+    pub fn get_type_name(&self, type_id: u32) -> Option<String> {
+        match self.types.get(&type_id) {
+            Some((namespace, name)) => Some(format!("{}.{}", namespace, name)),
+            None => builtin_type_name(type_id).map(str::to_string),
+        }
+    }
 
-    let column_count = u16::from_be_bytes(data[pos..pos + 2].try_into()?) as usize;
-    pos += 2;
+    /// Take the SchemaChanged event produced by the last Relation message, if any.
+    /// Call after a Relation event to emit the change right behind it.
+    pub fn take_schema_change(&mut self) -> Option<Change> {
+        self.schema_change.take()
+    }
 
-    let mut columns = Vec::new();
-    for _ in 0..column_count {
-        let _flags = data[pos];
-        pos += 1;
+    /// Decode one pgoutput message. Truncated messages fail with a `DecodeError`.
+    pub fn decode_pgoutput_message(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        self.decode_message(data, false)
+    }
 
-        let name = read_string(data, &mut pos)?;
+    /// Decode a message received between Stream Start and Stream Stop. Inside a
+    /// streamed block every data message is prefixed with the xid of the
+    /// (sub)transaction it belongs to.
+    pub fn decode_streamed_pgoutput_message(&mut self, data: &[u8]) -> Result<Option<Change>> {
+        self.decode_message(data, true)
+    }
 
-        let type_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-        pos += 4;
+    fn relation(&self, relation_id: u32) -> Result<&RelationEntry> {
+        self.relations
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))
+    }
 
-        let _type_modifier = i32::from_be_bytes(data[pos..pos + 4].try_into()?);
-        pos += 4;
+    /// Schema, table and key column names of a cached relation, for data events
+    fn relation_names(&self, relation_id: u32) -> Result<(String, String, Vec<String>)> {
+        let (schema, table, columns) = self.relation(relation_id)?;
+        let key_columns = columns
+            .iter()
+            .filter(|column| column.is_key())
+            .map(|column| column.name.clone())
+            .collect();
+        Ok((schema.clone(), table.clone(), key_columns))
+    }
 
-        columns.push(ColumnInfo {
-            name,
-            type_id,
-            flags: _flags,
-        });
+    fn decode_message(&mut self, data: &[u8], streamed: bool) -> Result<Option<Change>> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        let mut cur = Cursor::new(data);
+        let msg_type = cur.message_type;
+
+        let xid = if streamed && matches!(msg_type, 'R' | 'I' | 'U' | 'D' | 'T' | 'Y' | 'M') {
+            Some(cur.u32("xid")?)
+        } else {
+            None
+        };
+
+        let mut change = match msg_type {
+            'B' => decode_begin(&mut cur),
+            'C' => decode_commit(&mut cur),
+            'R' => self.decode_relation(&mut cur),
+            'I' => self.decode_insert(&mut cur, xid),
+            'U' => self.decode_update(&mut cur, xid),
+            'D' => self.decode_delete(&mut cur, xid),
+            'S' => decode_stream_start(&mut cur),
+            'E' => Ok(Some(Change::StreamStop)),
+            'c' => decode_stream_commit(&mut cur),
+            'A' => decode_stream_abort(&mut cur),
+            'b' => decode_begin_prepare(&mut cur),
+            'P' => decode_prepare(&mut cur, false),
+            'K' => decode_commit_prepared(&mut cur),
+            'r' => decode_rollback_prepared(&mut cur),
+            'p' => decode_prepare(&mut cur, true),
+            'T' => self.decode_truncate(&mut cur, xid),
+            'O' => decode_origin(&mut cur),
+            'Y' => self.decode_type(&mut cur),
+            'M' => decode_message_event(&mut cur, xid),
+            _ => {
+                eprintln!("Unknown message type: {}", msg_type);
+                Ok(None)
+            }
+        }?;
+
+        if let Some(change) = change.as_mut() {
+            self.track_transaction(change, streamed);
+        }
+        Ok(change)
     }
 
-    // Cache the relation info
-    let mut cache = RELATION_CACHE.lock().unwrap();
-    cache.insert(relation_id, (schema.clone(), table.clone(), columns.clone()));
-    drop(cache);
+    /// Follow transaction boundaries and tag data changes with their transaction
+    fn track_transaction(&mut self, change: &mut Change, streamed: bool) {
+        match change {
+            Change::Begin { xid, timestamp, .. } => {
+                self.transaction = Some(TransactionInfo { xid: *xid, commit_timestamp: Some(*timestamp) });
+            }
+            Change::BeginPrepare { xid, .. } => {
+                self.transaction = Some(TransactionInfo { xid: *xid, commit_timestamp: None });
+            }
+            Change::Commit { xid, .. } => *xid = self.transaction.take().map(|t| t.xid),
+            Change::Prepare { .. } => self.transaction = None,
+            Change::StreamStart { xid, .. } => self.stream_xid = Some(*xid),
+            Change::StreamStop => self.stream_xid = None,
+            Change::Insert { transaction, .. }
+            | Change::Update { transaction, .. }
+            | Change::Delete { transaction, .. }
+            | Change::Truncate { transaction, .. } => {
+                *transaction = if streamed {
+                    self.stream_xid.map(|xid| TransactionInfo { xid, commit_timestamp: None })
+                } else {
+                    self.transaction
+                };
+            }
+            _ => {}
+        }
+    }
 
-    Ok(Some(Change::Relation {
-        relation_id,
-        schema,
-        table,
-        columns,
-    }))
-}
+    fn decode_relation(&mut self, cur: &mut Cursor) -> Result<Option<Change>> {
+        let relation_id = cur.u32("relation id")?;
+        let schema = cur.cstring("namespace")?;
+        let table = cur.cstring("relation name")?;
+        let replica_identity = ReplicaIdentity::from_byte(cur.u8("replica identity")?)?;
+        let column_count = cur.u16("column count")?;
 
-fn decode_insert(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+        let mut columns = Vec::new();
+        for _ in 0..column_count {
+            let flags = cur.u8("column flags")?;
+            let name = cur.cstring("column name")?;
+            let type_id = cur.u32("column type")?;
+            let type_modifier = cur.i32("column type modifier")?;
+            let modifier = TypeModifier::decode(type_id, type_modifier);
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+            columns.push(ColumnInfo {
+                name,
+                type_id,
+                flags,
+                type_modifier,
+                type_name: self.get_type_name(type_id),
+                length: modifier.length,
+                precision: modifier.precision,
+                scale: modifier.scale,
+            });
+        }
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+        // Cache the relation info, comparing it with the previous definition
+        let previous = self.relations.insert(relation_id, (schema.clone(), table.clone(), columns.clone()));
+        self.schema_change = previous
+            .and_then(|(_, _, old_columns)| schema_change(relation_id, &schema, &table, &old_columns, &columns));
 
-    if tuple_type != 'N' {
-        return Err(anyhow!("Expected 'N' (new tuple) in INSERT"));
+        Ok(Some(Change::Relation {
+            relation_id,
+            schema,
+            table,
+            replica_identity,
+            columns,
+        }))
     }
 
-    let new_tuple = decode_tuple(data, &mut pos, relation_id)?;
+    fn decode_insert(&self, cur: &mut Cursor, xid: Option<u32>) -> Result<Option<Change>> {
+        let relation_id = cur.u32("relation id")?;
 
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
+        let tuple_type = cur.u8("tuple type")? as char;
+        if tuple_type != 'N' {
+            return Err(anyhow!("Expected 'N' (new tuple) in INSERT"));
+        }
 
-    Ok(Some(Change::Insert {
-        relation_id,
-        schema,
-        table,
-        new_tuple,
-    }))
-}
+        let (new_tuple, _) = self.decode_tuple(cur, relation_id)?;
 
-fn decode_update(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+        let (schema, table, key_columns) = self.relation_names(relation_id)?;
+
+        Ok(Some(Change::Insert {
+            relation_id,
+            schema,
+            table,
+            new_tuple,
+            key_columns,
+            // Position and transaction are filled in by the replication stream and the Decoder
+            lsn: None,
+            transaction: None,
+            xid,
+        }))
+    }
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+    fn decode_update(&self, cur: &mut Cursor, xid: Option<u32>) -> Result<Option<Change>> {
+        let relation_id = cur.u32("relation id")?;
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+        let tuple_type = cur.u8("tuple type")? as char;
+        let old_key_only = tuple_type == 'K';
+        let old_tuple = if tuple_type == 'K' || tuple_type == 'O' {
+            let (tuple, _) = self.decode_tuple(cur, relation_id)?;
+            if cur.u8("tuple type")? != b'N' {
+                return Err(anyhow!("Expected 'N' after old tuple in UPDATE"));
+            }
+            Some(tuple)
+        } else if tuple_type == 'N' {
+            None
+        } else {
+            return Err(anyhow!("Unexpected tuple type in UPDATE: {}", tuple_type));
+        };
+
+        let (new_tuple, unchanged_columns) = self.decode_tuple(cur, relation_id)?;
+
+        let (schema, table, key_columns) = self.relation_names(relation_id)?;
+
+        Ok(Some(Change::Update {
+            relation_id,
+            schema,
+            table,
+            old_tuple,
+            old_key_only,
+            new_tuple,
+            unchanged_columns,
+            key_columns,
+            lsn: None,
+            transaction: None,
+            xid,
+        }))
+    }
 
-    let old_tuple = if tuple_type == 'K' || tuple_type == 'O' {
-        let tuple = decode_tuple(data, &mut pos, relation_id)?;
-        let next_type = data[pos] as char;
-        pos += 1;
-        if next_type != 'N' {
-            return Err(anyhow!("Expected 'N' after old tuple in UPDATE"));
+    fn decode_delete(&self, cur: &mut Cursor, xid: Option<u32>) -> Result<Option<Change>> {
+        let relation_id = cur.u32("relation id")?;
+
+        let tuple_type = cur.u8("tuple type")? as char;
+        if tuple_type != 'K' && tuple_type != 'O' {
+            return Err(anyhow!("Expected 'K' or 'O' (old tuple) in DELETE"));
         }
-        Some(tuple)
-    } else if tuple_type == 'N' {
-        None
-    } else {
-        return Err(anyhow!("Unexpected tuple type in UPDATE: {}", tuple_type));
-    };
 
-    let new_tuple = decode_tuple(data, &mut pos, relation_id)?;
+        let (old_tuple, _) = self.decode_tuple(cur, relation_id)?;
 
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
+        let (schema, table, key_columns) = self.relation_names(relation_id)?;
 
-    Ok(Some(Change::Update {
-        relation_id,
-        schema,
-        table,
-        old_tuple,
-        new_tuple,
+        Ok(Some(Change::Delete {
+            relation_id,
+            schema,
+            table,
+            old_tuple,
+            key_columns,
+            lsn: None,
+            transaction: None,
+            xid,
+        }))
+    }
+
+    fn decode_truncate(&self, cur: &mut Cursor, xid: Option<u32>) -> Result<Option<Change>> {
+        let relation_count = cur.u32("relation count")? as usize;
+        // Option bits: 1 = CASCADE, 2 = RESTART IDENTITY
+        let options = cur.u8("options")?;
+
+        // The count comes off the wire, so don't trust it for the allocation
+        let mut relations = Vec::with_capacity(relation_count.min(cur.remaining() / 4));
+        for _ in 0..relation_count {
+            let relation_id = cur.u32("relation id")?;
+            let (schema, table, _) = self.relation(relation_id)?;
+            relations.push(TruncatedRelation {
+                relation_id,
+                schema: schema.clone(),
+                table: table.clone(),
+            });
+        }
+
+        Ok(Some(Change::Truncate {
+            relations,
+            cascade: options & 1 != 0,
+            restart_identity: options & 2 != 0,
+            lsn: None,
+            transaction: None,
+            xid,
+        }))
+    }
+
+    /// Type messages only feed the type name cache; the names show up on Relation columns
+    fn decode_type(&mut self, cur: &mut Cursor) -> Result<Option<Change>> {
+        let type_id = cur.u32("type id")?;
+        let namespace = cur.cstring("namespace")?;
+        let name = cur.cstring("type name")?;
+
+        self.types.insert(type_id, (namespace, name));
+
+        Ok(None)
+    }
+
+    /// Decode TupleData into the row's values and the names of its unchanged TOAST columns.
+    /// Unchanged columns are left out of the row rather than reported as NULL.
+    fn decode_tuple(&self, cur: &mut Cursor, relation_id: u32) -> Result<(Tuple, Vec<String>)> {
+        let column_count = cur.u16("column count")? as usize;
+
+        let (_, _, columns) = self.relation(relation_id)?;
+
+        let mut tuple = Tuple::new();
+        let mut unchanged = Vec::new();
+
+        for i in 0..column_count {
+            let (column_name, type_id) = match columns.get(i) {
+                Some(column) => (column.name.clone(), column.type_id),
+                None => (format!("column_{}", i), 0),
+            };
+
+            let tuple_type = cur.u8("column kind")? as char;
+
+            let value = match tuple_type {
+                'n' => None, // NULL
+                'u' => {
+                    // UNCHANGED TOAST datum (for UPDATE); the value was not sent
+                    unchanged.push(column_name);
+                    continue;
+                }
+                't' => {
+                    // Text value, parsed by the column's type so every format sees typed values
+                    let length = cur.u32("column length")? as usize;
+                    let bytes = cur.take(length, "column value")?;
+                    Some(decode_text(type_id, &String::from_utf8_lossy(bytes)))
+                }
+                'b' => {
+                    // Binary value (requested with the `binary` plugin option)
+                    let length = cur.u32("column length")? as usize;
+                    let bytes = cur.take(length, "column value")?;
+                    Some(decode_binary(type_id, bytes)?)
+                }
+                _ => {
+                    return Err(anyhow!("Unknown tuple data type: {}", tuple_type));
+                }
+            };
+
+            tuple.insert(column_name, value);
+        }
+
+        Ok((tuple, unchanged))
+    }
+}
+
+fn decode_begin(cur: &mut Cursor) -> Result<Option<Change>> {
+    let lsn = cur.lsn("final lsn")?;
+    let timestamp = cur.i64("commit timestamp")?;
+    let xid = cur.u32("xid")?;
+
+    Ok(Some(Change::Begin {
+        final_lsn: lsn,
+        timestamp,
+        xid,
+        origin: None,
     }))
 }
 
-fn decode_delete(data: &[u8]) -> Result<Option<Change>> {
-    let mut pos = 0;
+fn decode_origin(cur: &mut Cursor) -> Result<Option<Change>> {
+    let lsn = cur.lsn("origin lsn")?;
+    let name = cur.cstring("origin name")?;
 
-    let relation_id = u32::from_be_bytes(data[pos..pos + 4].try_into()?);
-    pos += 4;
+    Ok(Some(Change::Origin {
+        lsn,
+        name,
+    }))
+}
 
-    let tuple_type = data[pos] as char;
-    pos += 1;
+fn decode_commit(cur: &mut Cursor) -> Result<Option<Change>> {
+    let flags = cur.u8("flags")?;
+    let lsn = cur.lsn("commit lsn")?;
+    let end_lsn = cur.lsn("end lsn")?;
+    let timestamp = cur.i64("commit timestamp")?;
 
-    if tuple_type != 'K' && tuple_type != 'O' {
-        return Err(anyhow!("Expected 'K' or 'O' (old tuple) in DELETE"));
-    }
+    // The transaction id is filled in from the BEGIN by the Decoder
+    Ok(Some(Change::Commit {
+        flags,
+        lsn,
+        end_lsn,
+        timestamp,
+        xid: None,
+    }))
+}
 
-    let old_tuple = decode_tuple(data, &mut pos, relation_id)?;
+fn decode_stream_start(cur: &mut Cursor) -> Result<Option<Change>> {
+    let xid = cur.u32("xid")?;
+    let first_segment = cur.u8("first segment")? == 1;
 
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (schema, table, _) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?
-        .clone();
-    drop(cache);
+    Ok(Some(Change::StreamStart { xid, first_segment }))
+}
 
-    Ok(Some(Change::Delete {
-        relation_id,
-        schema,
-        table,
-        old_tuple,
+fn decode_stream_commit(cur: &mut Cursor) -> Result<Option<Change>> {
+    let xid = cur.u32("xid")?;
+    let _flags = cur.u8("flags")?;
+    let lsn = cur.lsn("commit lsn")?;
+    let end_lsn = cur.lsn("end lsn")?;
+    let timestamp = cur.i64("commit timestamp")?;
+
+    Ok(Some(Change::StreamCommit {
+        xid,
+        lsn,
+        end_lsn,
+        timestamp,
     }))
 }
 
-fn decode_tuple(
-    data: &[u8],
-    pos: &mut usize,
-    relation_id: u32,
-) -> Result<HashMap<String, Option<String>>> {
-    let column_count = u16::from_be_bytes(data[*pos..*pos + 2].try_into()?) as usize;
-    *pos += 2;
-
-    let cache = RELATION_CACHE.lock().unwrap();
-    let (_, _, columns) = cache
-        .get(&relation_id)
-        .ok_or_else(|| anyhow!("Relation {} not found in cache", relation_id))?;
-    let columns = columns.clone();
-    drop(cache);
-
-    let mut tuple = HashMap::new();
-
-    for i in 0..column_count {
-        let column_name = if i < columns.len() {
-            columns[i].name.clone()
-        } else {
-            format!("column_{}", i)
-        };
+fn decode_stream_abort(cur: &mut Cursor) -> Result<Option<Change>> {
+    let xid = cur.u32("xid")?;
+    let subxid = cur.u32("subtransaction xid")?;
 
-        let tuple_type = data[*pos] as char;
-        *pos += 1;
-
-        let value = match tuple_type {
-            'n' => None, // NULL
-            'u' => None, // UNCHANGED (for UPDATE)
-            't' => {
-                // Text value
-                let length = u32::from_be_bytes(data[*pos..*pos + 4].try_into()?) as usize;
-                *pos += 4;
-                let value = String::from_utf8_lossy(&data[*pos..*pos + length]).to_string();
-                *pos += length;
-                Some(value)
-            }
-            _ => {
-                return Err(anyhow!("Unknown tuple data type: {}", tuple_type));
-            }
-        };
+    Ok(Some(Change::StreamAbort { xid, subxid }))
+}
 
-        tuple.insert(column_name, value);
-    }
+fn decode_begin_prepare(cur: &mut Cursor) -> Result<Option<Change>> {
+    let lsn = cur.lsn("prepare lsn")?;
+    let _end_lsn = cur.lsn("end lsn")?;
+    let timestamp = cur.i64("prepare timestamp")?;
+    let xid = cur.u32("xid")?;
+    let gid = cur.cstring("gid")?;
 
-    Ok(tuple)
+    Ok(Some(Change::BeginPrepare {
+        lsn,
+        timestamp,
+        xid,
+        gid,
+    }))
 }
 
-fn read_string(data: &[u8], pos: &mut usize) -> Result<String> {
-    let start = *pos;
-    while *pos < data.len() && data[*pos] != 0 {
-        *pos += 1;
+/// PREPARE ('P') and STREAM PREPARE ('p') share the same layout
+fn decode_prepare(cur: &mut Cursor, streamed: bool) -> Result<Option<Change>> {
+    let _flags = cur.u8("flags")?;
+    let lsn = cur.lsn("prepare lsn")?;
+    let _end_lsn = cur.lsn("end lsn")?;
+    let timestamp = cur.i64("prepare timestamp")?;
+    let xid = cur.u32("xid")?;
+    let gid = cur.cstring("gid")?;
+
+    if streamed {
+        Ok(Some(Change::StreamPrepare { lsn, timestamp, xid, gid }))
+    } else {
+        Ok(Some(Change::Prepare { lsn, timestamp, xid, gid }))
     }
-    let s = String::from_utf8_lossy(&data[start..*pos]).to_string();
-    *pos += 1; // Skip null terminator
-    Ok(s)
+}
+
+fn decode_commit_prepared(cur: &mut Cursor) -> Result<Option<Change>> {
+    let _flags = cur.u8("flags")?;
+    let lsn = cur.lsn("commit lsn")?;
+    let _end_lsn = cur.lsn("end lsn")?;
+    let timestamp = cur.i64("commit timestamp")?;
+    let xid = cur.u32("xid")?;
+    let gid = cur.cstring("gid")?;
+
+    Ok(Some(Change::CommitPrepared {
+        lsn,
+        timestamp,
+        xid,
+        gid,
+    }))
+}
+
+fn decode_rollback_prepared(cur: &mut Cursor) -> Result<Option<Change>> {
+    let _flags = cur.u8("flags")?;
+    let prepare_end_lsn = cur.lsn("prepare end lsn")?;
+    let lsn = cur.lsn("rollback end lsn")?;
+    let prepare_timestamp = cur.i64("prepare timestamp")?;
+    let timestamp = cur.i64("rollback timestamp")?;
+    let xid = cur.u32("xid")?;
+    let gid = cur.cstring("gid")?;
+
+    Ok(Some(Change::RollbackPrepared {
+        lsn,
+        timestamp,
+        prepare_end_lsn,
+        prepare_timestamp,
+        xid,
+        gid,
+    }))
+}
+
+fn decode_message_event(cur: &mut Cursor, xid: Option<u32>) -> Result<Option<Change>> {
+    let transactional = cur.u8("flags")? & 1 != 0;
+    let lsn = cur.lsn("message lsn")?;
+    let prefix = cur.cstring("prefix")?;
+    let length = cur.u32("content length")? as usize;
+    let content = cur.take(length, "content")?;
+
+    Ok(Some(Change::Message {
+        transactional,
+        prefix,
+        content: String::from_utf8_lossy(content).to_string(),
+        lsn,
+        xid,
+    }))
 }
@@ -0,0 +1,682 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use postgres_protocol::escape::{escape_identifier, escape_literal};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_postgres::{Client, SimpleQueryMessage};
+
+use crate::decoder::{Change, ColumnInfo, ReplicaIdentity, Tuple};
+use crate::lsn::Lsn;
+use crate::snapshot::{published_tables, table_columns, table_reference, TablePlan};
+use crate::value::{decode_text, PgValue};
+
+/// Signal asking for tables to be snapshotted
+const EXECUTE_SNAPSHOT: &str = "execute-snapshot";
+/// Watermark written before a chunk is read
+const WINDOW_OPEN: &str = "snapshot-window-open";
+/// Watermark written after a chunk is read
+const WINDOW_CLOSE: &str = "snapshot-window-close";
+
+/// Where incremental snapshots are requested and how large their chunks are
+#[derive(Debug, Clone)]
+pub struct IncrementalSnapshotOptions {
+    /// Signal table as `schema.table` (or `table` in `public`); it must be part of
+    /// the publication and writable by the connecting user
+    pub signal_table: String,
+    /// Rows read per chunk
+    pub chunk_size: usize,
+}
+
+/// A row of the signal table, which has Debezium's `id`, `type` and `data` columns
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    /// Backfill the listed tables, in order
+    ExecuteSnapshot { tables: Vec<(String, String)> },
+    /// Low watermark of a chunk
+    WindowOpen { id: String },
+    /// High watermark of a chunk
+    WindowClose { id: String },
+    /// A signal type this stream doesn't act on
+    Other { kind: String },
+}
+
+impl Signal {
+    /// Read a signal from an inserted signal table row.
+    ///
+    /// `execute-snapshot` takes `{"data-collections": ["schema.table", ...]}`, optionally
+    /// with `"type": "incremental"`, the only kind supported.
+    pub fn from_tuple(tuple: &Tuple) -> Result<Self> {
+        let field = |name: &str| tuple.get(name).cloned().flatten();
+        let id = field("id").map(|v| v.to_string()).unwrap_or_default();
+        let kind = field("type")
+            .map(|v| v.to_string())
+            .ok_or_else(|| anyhow!("Signal '{}' has no type", id))?;
+
+        match kind.as_str() {
+            WINDOW_OPEN => Ok(Signal::WindowOpen { id }),
+            WINDOW_CLOSE => Ok(Signal::WindowClose { id }),
+            EXECUTE_SNAPSHOT => {
+                let data = match field("data") {
+                    Some(PgValue::Json(data)) => data,
+                    Some(value) => serde_json::from_str(&value.to_string())
+                        .map_err(|e| anyhow!("Signal '{}' has invalid data: {}", id, e))?,
+                    None => return Err(anyhow!("Signal '{}' has no data", id)),
+                };
+                if let Some(snapshot_type) = data.get("type").and_then(|t| t.as_str()) {
+                    if !snapshot_type.eq_ignore_ascii_case("incremental") {
+                        return Err(anyhow!(
+                            "Signal '{}' asks for a {} snapshot; only incremental snapshots are supported",
+                            id,
+                            snapshot_type
+                        ));
+                    }
+                }
+                let tables = data
+                    .get("data-collections")
+                    .and_then(|c| c.as_array())
+                    .ok_or_else(|| anyhow!("Signal '{}' has no data-collections", id))?
+                    .iter()
+                    .map(|name| {
+                        name.as_str()
+                            .ok_or_else(|| anyhow!("Signal '{}' has a non-string data collection", id))
+                            .and_then(parse_table_name)
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Signal::ExecuteSnapshot { tables })
+            }
+            _ => Ok(Signal::Other { kind }),
+        }
+    }
+}
+
+/// Split `schema.table` into its parts; a bare table name is in `public`
+pub fn parse_table_name(name: &str) -> Result<(String, String)> {
+    match name.split_once('.') {
+        Some((schema, table)) if !schema.is_empty() && !table.is_empty() => {
+            Ok((schema.to_string(), table.to_string()))
+        }
+        None if !name.is_empty() => Ok(("public".to_string(), name.to_string())),
+        _ => Err(anyhow!("Invalid table name: '{}'. Expected schema.table", name)),
+    }
+}
+
+/// Rows of one chunk, held between its low and high watermarks.
+///
+/// Once the low watermark shows up in the stream, every change to a row of the chunk
+/// supersedes the row that was read, so it is dropped; what is left when the high
+/// watermark arrives has not changed since and is emitted at that position.
+pub struct ChunkWindow {
+    relation_id: u32,
+    primary_key: Vec<ColumnInfo>,
+    rows: IndexMap<String, Change>,
+    open: bool,
+}
+
+impl ChunkWindow {
+    pub fn new(relation_id: u32, primary_key: Vec<ColumnInfo>) -> Self {
+        Self {
+            relation_id,
+            primary_key,
+            rows: IndexMap::new(),
+            open: false,
+        }
+    }
+
+    /// Add a Snapshot row read for the chunk
+    pub fn insert(&mut self, row: Change) -> Result<()> {
+        let Change::Snapshot { new_tuple, .. } = &row else {
+            return Err(anyhow!("Only snapshot rows belong in a chunk window"));
+        };
+        let key = self
+            .key_of(new_tuple)
+            .ok_or_else(|| anyhow!("Chunk row is missing its primary key"))?;
+        self.rows.insert(key, row);
+        Ok(())
+    }
+
+    /// The low watermark was reached
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Drop the rows a streamed change supersedes; changes before the low watermark
+    /// are already reflected in the chunk
+    pub fn apply(&mut self, change: &Change) {
+        if !self.open {
+            return;
+        }
+        if let Change::Truncate { relations, .. } = change {
+            if relations.iter().any(|r| r.relation_id == self.relation_id) {
+                self.rows.clear();
+            }
+            return;
+        }
+        let keys = self.change_keys(change);
+        self.forget(&keys);
+    }
+
+    /// Drop the rows with these keys
+    pub fn forget(&mut self, keys: &[String]) {
+        if !self.open {
+            return;
+        }
+        for key in keys {
+            self.rows.shift_remove(key);
+        }
+    }
+
+    /// Keys of the chunk's table a data change touches: the new row's and, when the
+    /// key was changed, the old row's
+    pub fn change_keys(&self, change: &Change) -> Vec<String> {
+        match change_rows(change) {
+            Some((relation_id, _, tuples)) => self.row_keys(relation_id, tuples),
+            None => Vec::new(),
+        }
+    }
+
+    /// Keys of rows of a relation, if it is the chunk's table
+    pub fn row_keys<'a>(&self, relation_id: u32, tuples: impl IntoIterator<Item = &'a Tuple>) -> Vec<String> {
+        if relation_id != self.relation_id {
+            return Vec::new();
+        }
+        tuples.into_iter().filter_map(|tuple| self.key_of(tuple)).collect()
+    }
+
+    /// Remaining rows, positioned at the high watermark
+    pub fn close(&mut self, lsn: Lsn) -> Vec<Change> {
+        self.open = false;
+        self.rows
+            .drain(..)
+            .map(|(_, mut row)| {
+                if let Change::Snapshot { lsn: position, .. } = &mut row {
+                    *position = lsn;
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// Primary key of a row, comparable whether its values are text or binary
+    fn key_of(&self, tuple: &Tuple) -> Option<String> {
+        let values = self
+            .primary_key
+            .iter()
+            .map(|column| {
+                Some(match tuple.get(&column.name)? {
+                    Some(PgValue::Text(text)) => decode_text(column.type_id, text).to_json(),
+                    Some(value) => value.to_json(),
+                    None => serde_json::Value::Null,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(serde_json::Value::Array(values).to_string())
+    }
+}
+
+/// Relation, replica identity columns and rows of a data change: the new row and,
+/// for updates and deletes, the old one
+fn change_rows(change: &Change) -> Option<(u32, &[String], Vec<&Tuple>)> {
+    match change {
+        Change::Insert { relation_id, key_columns, new_tuple, .. } => Some((*relation_id, key_columns, vec![new_tuple])),
+        Change::Update { relation_id, key_columns, new_tuple, old_tuple, .. } => Some((
+            *relation_id,
+            key_columns,
+            std::iter::once(new_tuple).chain(old_tuple.as_ref()).collect(),
+        )),
+        Change::Delete { relation_id, key_columns, old_tuple, .. } => Some((*relation_id, key_columns, vec![old_tuple])),
+        _ => None,
+    }
+}
+
+/// A row written by a streamed transaction, reduced to its replica identity columns
+struct StreamedRow {
+    subxid: u32,
+    relation_id: u32,
+    tuples: Vec<Tuple>,
+}
+
+/// Rows written by streamed transactions that haven't committed yet.
+///
+/// They are kept for every streamed transaction, whichever table is being backfilled:
+/// changes sent before a chunk's low watermark still supersede its rows if the
+/// transaction commits inside the window. Only replica identity columns are kept, which
+/// include the primary key of every table an incremental snapshot accepts.
+#[derive(Default)]
+pub struct StreamedRows {
+    /// Top-level transaction of the current streamed block
+    current_xid: Option<u32>,
+    rows: HashMap<u32, Vec<StreamedRow>>,
+}
+
+impl StreamedRows {
+    /// A streamed block of the transaction starts
+    pub fn start(&mut self, xid: u32) {
+        self.current_xid = Some(xid);
+    }
+
+    /// The streamed block ended
+    pub fn stop(&mut self) {
+        self.current_xid = None;
+    }
+
+    /// Remember the rows a data change of (sub)transaction `subxid` inside the current
+    /// streamed block touches
+    pub fn record(&mut self, subxid: u32, change: &Change) {
+        let Some(top) = self.current_xid else {
+            return;
+        };
+        let Some((relation_id, key_columns, tuples)) = change_rows(change) else {
+            return;
+        };
+        let tuples = tuples
+            .into_iter()
+            .map(|tuple| {
+                tuple
+                    .iter()
+                    .filter(|(name, _)| key_columns.contains(name))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect()
+            })
+            .collect();
+        self.rows.entry(top).or_default().push(StreamedRow { subxid, relation_id, tuples });
+    }
+
+    /// Forget the rows of an aborted transaction or subtransaction
+    pub fn abort(&mut self, xid: u32, subxid: u32) {
+        if xid == subxid {
+            self.rows.remove(&xid);
+        } else if let Some(rows) = self.rows.get_mut(&xid) {
+            rows.retain(|row| row.subxid != subxid);
+        }
+    }
+
+    /// The transaction committed: its changes supersede the rows of an open chunk window
+    pub fn commit(&mut self, xid: u32, window: Option<&mut ChunkWindow>) {
+        let Some(rows) = self.rows.remove(&xid) else {
+            return;
+        };
+        if let Some(window) = window {
+            for row in rows {
+                let keys = window.row_keys(row.relation_id, &row.tuples);
+                window.forget(&keys);
+            }
+        }
+    }
+
+    /// Number of uncommitted streamed transactions with rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// A table being backfilled chunk by chunk in primary key order
+struct TableBackfill {
+    plan: TablePlan,
+    primary_key: Vec<ColumnInfo>,
+    /// Primary key of the last row read, as text; the next chunk starts after it
+    last_key: Option<Vec<String>>,
+    window: ChunkWindow,
+    /// Watermark id of the chunk in the window
+    chunk_id: Option<String>,
+    /// The last chunk came back short, so the table is done once its window closes
+    exhausted: bool,
+    rows: u64,
+}
+
+impl TableBackfill {
+    /// Whether a watermark row belongs to the chunk in the window
+    fn is_watermark(&self, id: &str, kind: &str) -> bool {
+        self.chunk_id.as_ref().is_some_and(|chunk| watermark_id(chunk, kind) == id)
+    }
+}
+
+/// Snapshots of single tables requested through a signal table while streaming, using
+/// the watermark algorithm of DBLog.
+///
+/// Each chunk is read between a low and a high watermark row written to the signal table.
+/// The stream delivers both watermarks in commit order with the live changes; rows of the
+/// chunk changed in between are dropped, as the stream already carries their newer
+/// version, and the rest are emitted as incremental Snapshot events at the high watermark.
+///
+/// Progress is kept in memory only: a snapshot interrupted by a restart must be
+/// signalled again.
+pub struct IncrementalSnapshot {
+    publication: String,
+    signal_schema: String,
+    signal_table: String,
+    chunk_size: usize,
+    /// Tables signalled for a snapshot and not yet started
+    pending: VecDeque<(String, String)>,
+    current: Option<TableBackfill>,
+    /// Changes to hand out before reading more from the stream
+    ready: VecDeque<Change>,
+    /// Rows written by streamed transactions that haven't committed
+    streamed: StreamedRows,
+    /// Prefix of this run's watermark ids, so watermarks of an earlier run are not matched
+    run_id: String,
+    chunks_read: u64,
+}
+
+impl IncrementalSnapshot {
+    pub fn new(options: &IncrementalSnapshotOptions, publication_name: &str) -> Result<Self> {
+        let (signal_schema, signal_table) = parse_table_name(&options.signal_table)?;
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(Self {
+            publication: publication_name.to_string(),
+            signal_schema,
+            signal_table,
+            chunk_size: options.chunk_size.max(1),
+            pending: VecDeque::new(),
+            current: None,
+            ready: VecDeque::new(),
+            streamed: StreamedRows::default(),
+            run_id: format!("{:x}", started.as_millis()),
+            chunks_read: 0,
+        })
+    }
+
+    /// Next change ready to be handed out
+    pub fn pop_ready(&mut self) -> Option<Change> {
+        self.ready.pop_front()
+    }
+
+    fn is_signal_table(&self, schema: &str, table: &str) -> bool {
+        schema == self.signal_schema && table == self.signal_table
+    }
+
+    /// Take a change from the stream: act on signals, deduplicate the open chunk and
+    /// queue what is to be handed out. Changes to the signal table are not passed on.
+    pub async fn handle(&mut self, client: &Client, change: Change) -> Result<()> {
+        match &change {
+            Change::Insert { schema, table, new_tuple, lsn, .. } if self.is_signal_table(schema, table) => {
+                match Signal::from_tuple(new_tuple) {
+                    Ok(signal) => self.on_signal(client, signal, lsn.unwrap_or(Lsn::ZERO)).await?,
+                    Err(e) => eprintln!("Ignoring signal: {}", e),
+                }
+                return Ok(());
+            }
+            Change::Relation { schema, table, .. }
+            | Change::SchemaChanged { schema, table, .. }
+            | Change::Update { schema, table, .. }
+            | Change::Delete { schema, table, .. }
+            | Change::Snapshot { schema, table, .. }
+                if self.is_signal_table(schema, table) =>
+            {
+                return Ok(());
+            }
+            Change::StreamStart { xid, .. } => self.streamed.start(*xid),
+            Change::StreamStop => self.streamed.stop(),
+            // A streamed transaction's changes supersede chunk rows once it commits
+            Change::StreamCommit { xid, .. } => {
+                self.streamed.commit(*xid, self.current.as_mut().map(|current| &mut current.window));
+            }
+            Change::StreamAbort { xid, subxid } => self.streamed.abort(*xid, *subxid),
+            Change::Insert { xid: Some(subxid), .. }
+            | Change::Update { xid: Some(subxid), .. }
+            | Change::Delete { xid: Some(subxid), .. } => self.streamed.record(*subxid, &change),
+            _ => {
+                if let Some(current) = self.current.as_mut() {
+                    current.window.apply(&change);
+                }
+            }
+        }
+        self.ready.push_back(change);
+        Ok(())
+    }
+
+    async fn on_signal(&mut self, client: &Client, signal: Signal, lsn: Lsn) -> Result<()> {
+        match signal {
+            Signal::ExecuteSnapshot { tables } => {
+                let names: Vec<String> = tables.iter().map(|(s, t)| format!("{}.{}", s, t)).collect();
+                eprintln!("Incremental snapshot requested for {}", names.join(", "));
+                self.pending.extend(tables);
+                if self.current.is_none() {
+                    self.advance(client).await?;
+                }
+            }
+            Signal::WindowOpen { id } => {
+                if let Some(current) = self.current.as_mut() {
+                    if current.is_watermark(&id, WINDOW_OPEN) {
+                        current.window.open();
+                    }
+                }
+            }
+            Signal::WindowClose { id } => {
+                let Some(current) = self.current.as_mut().filter(|c| c.is_watermark(&id, WINDOW_CLOSE)) else {
+                    return Ok(());
+                };
+                let rows = current.window.close(lsn);
+                current.rows += rows.len() as u64;
+                current.chunk_id = None;
+                self.ready.extend(rows);
+                self.advance(client).await?;
+            }
+            Signal::Other { kind } => eprintln!("Ignoring unsupported signal '{}'", kind),
+        }
+        Ok(())
+    }
+
+    /// Read chunks until one has rows waiting for its high watermark or every
+    /// signalled table is done
+    async fn advance(&mut self, client: &Client) -> Result<()> {
+        loop {
+            let current = match self.current.as_mut() {
+                Some(current) => current,
+                None => {
+                    let Some((schema, table)) = self.pending.pop_front() else {
+                        return Ok(());
+                    };
+                    match load_table(client, &self.publication, &schema, &table).await {
+                        Ok(backfill) => {
+                            eprintln!("Starting incremental snapshot of {}.{}", schema, table);
+                            self.ready.push_back(backfill.plan.relation());
+                            self.current.insert(backfill)
+                        }
+                        Err(e) => {
+                            eprintln!("Skipping incremental snapshot of {}.{}: {}", schema, table, e);
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if current.exhausted {
+                eprintln!(
+                    "Incremental snapshot of {}.{} complete: {} rows",
+                    current.plan.table.schema, current.plan.table.table, current.rows
+                );
+                self.current = None;
+                continue;
+            }
+
+            self.chunks_read += 1;
+            let chunk_id = format!("{}-{}", self.run_id, self.chunks_read);
+            let signal = (self.signal_schema.as_str(), self.signal_table.as_str());
+            if read_chunk(client, signal, current, &chunk_id, self.chunk_size).await? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Id of a chunk's watermark row
+fn watermark_id(chunk_id: &str, kind: &str) -> String {
+    match kind {
+        WINDOW_OPEN => format!("{}-open", chunk_id),
+        _ => format!("{}-close", chunk_id),
+    }
+}
+
+async fn write_watermark(client: &Client, signal: (&str, &str), chunk_id: &str, kind: &str) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "INSERT INTO {}.{} (id, type) VALUES ({}, {})",
+            escape_identifier(signal.0),
+            escape_identifier(signal.1),
+            escape_literal(&watermark_id(chunk_id, kind)),
+            escape_literal(kind)
+        ))
+        .await
+        .map_err(|e| anyhow!("Failed to write snapshot watermark to {}.{}: {}", signal.0, signal.1, e))
+}
+
+/// Read the table's next chunk between a low and a high watermark. Returns false
+/// if there were no rows left, in which case no high watermark is written.
+async fn read_chunk(
+    client: &Client,
+    signal: (&str, &str),
+    current: &mut TableBackfill,
+    chunk_id: &str,
+    chunk_size: usize,
+) -> Result<bool> {
+    write_watermark(client, signal, chunk_id, WINDOW_OPEN).await?;
+
+    let query = build_chunk_query(&current.plan, &current.primary_key, current.last_key.as_deref(), chunk_size);
+    let rows: Vec<_> = client
+        .simple_query(&query)
+        .await?
+        .into_iter()
+        .filter_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(row),
+            _ => None,
+        })
+        .collect();
+
+    current.exhausted = rows.len() < chunk_size;
+    if rows.is_empty() {
+        return Ok(false);
+    }
+
+    let key_columns = current.plan.key_columns();
+    for row in &rows {
+        let new_tuple: Tuple = current
+            .plan
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| (column.name.clone(), row.get(i).map(|v| decode_text(column.type_id, v))))
+            .collect();
+        current.window.insert(Change::Snapshot {
+            relation_id: current.plan.table.relation_id,
+            schema: current.plan.table.schema.clone(),
+            table: current.plan.table.table.clone(),
+            new_tuple,
+            key_columns: key_columns.clone(),
+            lsn: Lsn::ZERO,
+            incremental: true,
+        })?;
+    }
+
+    let last = &rows[rows.len() - 1];
+    current.last_key = current
+        .primary_key
+        .iter()
+        .map(|key| {
+            let index = current.plan.columns.iter().position(|c| c.name == key.name)?;
+            last.get(index).map(str::to_string)
+        })
+        .collect();
+    current.chunk_id = Some(chunk_id.to_string());
+
+    write_watermark(client, signal, chunk_id, WINDOW_CLOSE).await?;
+    Ok(true)
+}
+
+/// Look up a table of the publication and the primary key its chunks are ordered by
+async fn load_table(client: &Client, publication_name: &str, schema: &str, table: &str) -> Result<TableBackfill> {
+    let published = published_tables(client, publication_name)
+        .await?
+        .into_iter()
+        .find(|t| t.schema == schema && t.table == table)
+        .ok_or_else(|| anyhow!("it is not part of publication '{}'", publication_name))?;
+    if !matches!(published.replica_identity, ReplicaIdentity::Default | ReplicaIdentity::Full) {
+        return Err(anyhow!("its replica identity must be DEFAULT or FULL so deletes carry the primary key"));
+    }
+
+    let columns = table_columns(client, &published).await?;
+    let rows = client
+        .query(
+            "SELECT a.attname \
+             FROM pg_index i \
+             CROSS JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, position) \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum \
+             WHERE i.indrelid = $1 AND i.indisprimary \
+             ORDER BY k.position",
+            &[&published.relation_id],
+        )
+        .await?;
+    if rows.is_empty() {
+        return Err(anyhow!("it has no primary key"));
+    }
+    let primary_key = rows
+        .iter()
+        .map(|row| {
+            let name: &str = row.get(0);
+            columns
+                .iter()
+                .find(|c| c.name == name)
+                .cloned()
+                .ok_or_else(|| anyhow!("primary key column '{}' is not published", name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TableBackfill {
+        window: ChunkWindow::new(published.relation_id, primary_key.clone()),
+        plan: TablePlan {
+            table: published,
+            columns,
+            chunk_key: None,
+            chunks: Vec::new(),
+        },
+        primary_key,
+        last_key: None,
+        chunk_id: None,
+        exhausted: false,
+        rows: 0,
+    })
+}
+
+/// SELECT reading the next `limit` rows of a table in primary key order, starting
+/// after `after` (the text values of the last key read)
+pub fn build_chunk_query(
+    plan: &TablePlan,
+    primary_key: &[ColumnInfo],
+    after: Option<&[String]>,
+    limit: usize,
+) -> String {
+    let column_names: Vec<String> = plan.columns.iter().map(|c| escape_identifier(&c.name)).collect();
+    let key_names: Vec<String> = primary_key.iter().map(|c| escape_identifier(&c.name)).collect();
+
+    let mut conditions = Vec::new();
+    if let Some(filter) = &plan.table.row_filter {
+        conditions.push(format!("({})", filter));
+    }
+    if let Some(after) = after {
+        // Untyped literals take the type of the key column they are compared with
+        let values: Vec<String> = after.iter().map(|v| escape_literal(v)).collect();
+        conditions.push(format!("({}) > ({})", key_names.join(", "), values.join(", ")));
+    }
+
+    let mut query = format!("SELECT {} FROM {}", column_names.join(", "), table_reference(&plan.table));
+    if !conditions.is_empty() {
+        query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    query.push_str(&format!(" ORDER BY {} LIMIT {}", key_names.join(", "), limit));
+    query
+}
@@ -1,5 +1,12 @@
 // Library exports for testing and external use
 
+pub mod admin;
+pub mod check;
 pub mod decoder;
+pub mod incremental;
+pub mod lsn;
 pub mod output;
+pub mod protocol;
 pub mod replication;
+pub mod snapshot;
+pub mod value;
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+/// A position in the write-ahead log (PostgreSQL's `pg_lsn`).
+///
+/// Displayed and serialized in PostgreSQL's `X/Y` notation (e.g. `0/16B2D50`); positions
+/// compare in WAL order and subtracting two of them gives the distance in bytes, which
+/// is zero rather than a panic when positions arrive out of order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lsn(pub u64);
+
+impl Lsn {
+    /// The invalid position `0/0`, used where PostgreSQL expects "no position"
+    pub const ZERO: Lsn = Lsn(0);
+
+    /// Bytes of WAL from `earlier` up to this position; zero if `earlier` is not behind
+    pub fn bytes_since(self, earlier: Lsn) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Bytes of WAL from `earlier` up to this position, or `None` if `earlier` is ahead
+    pub fn checked_sub(self, earlier: Lsn) -> Option<u64> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl fmt::Display for Lsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 & 0xFFFFFFFF)
+    }
+}
+
+impl FromStr for Lsn {
+    type Err = Error;
+
+    /// Parse a position written in PostgreSQL's `X/Y` notation
+    fn from_str(s: &str) -> Result<Self> {
+        let (hi, lo) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Invalid LSN '{}': expected format X/Y (e.g. 0/16B2D50)", s))?;
+        let hi = u32::from_str_radix(hi, 16).map_err(|_| anyhow!("Invalid LSN '{}': bad upper half", s))?;
+        let lo = u32::from_str_radix(lo, 16).map_err(|_| anyhow!("Invalid LSN '{}': bad lower half", s))?;
+        Ok(Lsn(((hi as u64) << 32) | lo as u64))
+    }
+}
+
+impl From<u64> for Lsn {
+    fn from(lsn: u64) -> Self {
+        Lsn(lsn)
+    }
+}
+
+impl From<Lsn> for u64 {
+    fn from(lsn: Lsn) -> Self {
+        lsn.0
+    }
+}
+
+/// Distance in bytes between two positions; zero if `rhs` is ahead of `self`, so a
+/// stale server position cannot underflow a lag computation
+impl Sub for Lsn {
+    type Output = u64;
+
+    fn sub(self, rhs: Lsn) -> u64 {
+        self.bytes_since(rhs)
+    }
+}
+
+/// Move a position forward by a number of bytes
+impl Add<u64> for Lsn {
+    type Output = Lsn;
+
+    fn add(self, bytes: u64) -> Lsn {
+        Lsn(self.0 + bytes)
+    }
+}
+
+/// Move a position back by a number of bytes, stopping at `0/0`
+impl Sub<u64> for Lsn {
+    type Output = Lsn;
+
+    fn sub(self, bytes: u64) -> Lsn {
+        Lsn(self.0.saturating_sub(bytes))
+    }
+}
+
+impl Serialize for Lsn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Lsn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
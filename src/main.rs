@@ -1,16 +1,150 @@
-mod replication;
-mod decoder;
-mod output;
-
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use anyhow::Result;
 use std::sync::Arc;
+use pgoutput_stream::admin;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::{check, incremental, output, replication, snapshot};
 use output::OutputTarget;
 
 #[derive(Parser, Debug)]
 #[command(name = "pgoutput-stream")]
 #[command(about = "Stream PostgreSQL logical replication changes to stdout", long_about = None)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Without a subcommand, the options of `stream`
+    #[command(flatten)]
+    stream: Option<StreamArgs>,
+}
+
+#[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    /// Stream changes from a replication slot (the default)
+    Stream(StreamArgs),
+    /// Check the replication prerequisites for streaming with these options, without streaming
+    Check(StreamArgs),
+    /// Create, drop, list, advance or inspect replication slots
+    Slot {
+        #[command(subcommand)]
+        command: SlotCommand,
+    },
+    /// Create, alter, list or show publications
+    Publication {
+        #[command(subcommand)]
+        command: PublicationCommand,
+    },
+}
+
+#[derive(Args, Debug)]
+struct ConnectionArgs {
+    /// PostgreSQL connection string (e.g., "host=localhost user=postgres dbname=mydb")
+    #[arg(short, long)]
+    connection: String,
+}
+
+#[derive(Subcommand, Debug)]
+enum SlotCommand {
+    /// Create a pgoutput replication slot
+    Create {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        slot: String,
+        /// Enable decoding of prepared transactions (PostgreSQL 14+)
+        #[arg(long)]
+        two_phase: bool,
+    },
+    /// Drop a replication slot, releasing the WAL it retains
+    Drop {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        slot: String,
+    },
+    /// List the logical replication slots of the server
+    List {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move a slot forward to an LSN, skipping the changes before it
+    Advance {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        slot: String,
+        lsn: Lsn,
+    },
+    /// Show a slot's positions, retained WAL and whether it is in use
+    Status {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        slot: String,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PublicationCommand {
+    /// Create a publication
+    Create {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        publication: String,
+        /// Table to publish: schema.table, optionally with a column list and a row filter,
+        /// e.g. "public.orders (id, status) WHERE status <> 'draft'" (repeatable)
+        #[arg(long = "table", value_name = "SPEC")]
+        tables: Vec<String>,
+        /// Publish every table of the database
+        #[arg(long, conflicts_with = "tables")]
+        all_tables: bool,
+        /// Operations to publish (comma-separated: insert, update, delete, truncate)
+        #[arg(long)]
+        publish: Option<String>,
+    },
+    /// Add, drop or replace tables of a publication, or change its operations
+    Alter {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        publication: String,
+        /// Table to add, in the same form as for `create` (repeatable)
+        #[arg(long = "add-table", value_name = "SPEC")]
+        add: Vec<String>,
+        /// Table to drop, as schema.table (repeatable)
+        #[arg(long = "drop-table", value_name = "TABLE")]
+        drop: Vec<String>,
+        /// Replace the publication's tables with these (repeatable)
+        #[arg(long = "set-table", value_name = "SPEC")]
+        set: Vec<String>,
+        /// Operations to publish (comma-separated: insert, update, delete, truncate)
+        #[arg(long)]
+        publish: Option<String>,
+    },
+    /// List the publications of the database
+    List {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show a publication's tables with their column lists and row filters
+    Show {
+        #[command(flatten)]
+        connection: ConnectionArgs,
+        publication: String,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct StreamArgs {
     /// PostgreSQL connection string (e.g., "host=localhost user=postgres dbname=mydb")
     #[arg(short, long)]
     connection: String,
@@ -27,13 +161,104 @@ struct Args {
     #[arg(short, long, default_value = "json")]
     format: String,
 
+    /// Wrap Debezium events as {"schema", "payload"} with a Kafka Connect schema
+    /// of the table's columns, like Debezium with `schemas.enable=true`
+    #[arg(long)]
+    debezium_schema: bool,
+
     /// Create replication slot if it doesn't exist
     #[arg(long)]
     create_slot: bool,
 
+    /// Create the slot and copy the existing rows of every published table before streaming
+    #[arg(long, conflicts_with = "start_lsn")]
+    snapshot: bool,
+
+    /// Connections copying snapshot chunks in parallel
+    #[arg(long, default_value_t = 4)]
+    snapshot_workers: usize,
+
+    /// Rows per snapshot chunk (tables with a single integer primary key)
+    #[arg(long, default_value_t = 100_000)]
+    snapshot_chunk_size: i64,
+
+    /// File recording completed snapshot chunks, so an interrupted snapshot resumes
+    /// [default: <slot>.snapshot.json]
+    #[arg(long)]
+    snapshot_state: Option<std::path::PathBuf>,
+
+    /// Table (schema.table) watched for execute-snapshot signals, which backfill
+    /// single tables while streaming; it must be part of the publication
+    #[arg(long)]
+    signal_table: Option<String>,
+
+    /// Rows per incremental snapshot chunk
+    #[arg(long, default_value_t = 1024)]
+    incremental_snapshot_chunk_size: usize,
+
     /// Starting LSN (Log Sequence Number) to stream from
     #[arg(long)]
-    start_lsn: Option<String>,
+    start_lsn: Option<Lsn>,
+
+    /// Replication mode: streaming (START_REPLICATION over the replication protocol)
+    /// or polling (pg_logical_slot_get_binary_changes)
+    #[arg(long, default_value = "streaming")]
+    replication_mode: String,
+
+    /// Delivery guarantee: at-least-once (confirm a transaction to PostgreSQL only after
+    /// every target accepted it) or at-most-once (confirm on receipt)
+    #[arg(long, default_value = "at-least-once")]
+    delivery: String,
+
+    /// Seconds between standby status updates sent to PostgreSQL in streaming mode
+    #[arg(long, default_value_t = 10)]
+    status_interval: u64,
+
+    /// pgoutput protocol version (2 adds streaming of in-progress transactions,
+    /// 3 adds two-phase commit)
+    #[arg(long, default_value_t = 1)]
+    protocol_version: u32,
+
+    /// Stream large in-progress transactions instead of waiting for them to commit
+    /// (requires --protocol-version 2 or later)
+    #[arg(long)]
+    streaming: bool,
+
+    /// Decode prepared transactions at PREPARE TRANSACTION time
+    /// (requires --protocol-version 3 or later)
+    #[arg(long)]
+    two_phase: bool,
+
+    /// Request column values in binary format and decode them into typed values
+    #[arg(long)]
+    binary: bool,
+
+    /// Request logical decoding messages emitted with pg_logical_emit_message()
+    #[arg(long)]
+    messages: bool,
+
+    /// Only pass on messages with these prefixes (comma-separated; implies --messages)
+    #[arg(long)]
+    message_prefixes: Option<String>,
+
+    /// How streamed transactions reach the targets: reassemble (buffer until
+    /// STREAM COMMIT) or passthrough (forward chunks as they arrive)
+    #[arg(long, default_value = "reassemble")]
+    streamed_transactions: String,
+
+    /// Write each transaction as a single Transaction record holding all of its changes
+    /// instead of separate Begin, change and Commit events
+    #[arg(long)]
+    group_transactions: bool,
+
+    /// Largest transaction (in changes) held in memory for --group-transactions;
+    /// bigger ones are written change by change
+    #[arg(long, default_value_t = 10_000)]
+    max_transaction_changes: usize,
+
+    /// Start streaming even if the pre-flight checks of the replication prerequisites fail
+    #[arg(long)]
+    skip_checks: bool,
 
     /// Output target(s): stdout, nats, feldera (comma-separated for multiple)
     #[arg(short, long, default_value = "stdout")]
@@ -64,6 +289,15 @@ struct Args {
     #[arg(long)]
     feldera_tables: Option<String>,
 
+    /// How the Feldera target handles TRUNCATE: error (stop the stream) or skip (warn and continue)
+    #[arg(long, default_value = "error")]
+    feldera_truncate: String,
+
+    /// How the Feldera target handles column changes of a table: warn (log and continue)
+    /// or error (stop the stream before rows with the new shape are pushed)
+    #[arg(long, default_value = "warn")]
+    feldera_schema_change: String,
+
     /// Feldera API key for authentication (optional)
     #[arg(long)]
     feldera_api_key: Option<String>,
@@ -71,20 +305,46 @@ struct Args {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match (cli.command, cli.stream) {
+        (Some(Command::Stream(args)), _) | (None, Some(args)) => stream(args).await,
+        (Some(Command::Check(args)), _) => check_prerequisites(args).await,
+        (Some(Command::Slot { command }), _) => slot(command).await,
+        (Some(Command::Publication { command }), _) => publication(command).await,
+        (None, None) => {
+            use clap::CommandFactory;
+            Cli::command().print_help()?;
+            Ok(())
+        }
+    }
+}
 
+async fn stream(args: StreamArgs) -> Result<()> {
     eprintln!("Connecting to PostgreSQL...");
     eprintln!("Slot: {}", args.slot);
     eprintln!("Publication: {}", args.publication);
     eprintln!("Output format: {}", args.format);
+    eprintln!("Replication mode: {}", args.replication_mode);
+    eprintln!("Delivery: {}", args.delivery);
+
+    let options = replication_options(&args)?;
+    let results = check::run_checks(&args.connection, &args.slot, &args.publication, &options).await;
+    for result in results.iter().filter(|r| r.status != check::CheckStatus::Pass) {
+        eprintln!("{}", result);
+    }
+    if check::has_failures(&results) {
+        if !args.skip_checks {
+            anyhow::bail!("Replication prerequisites are not met (see above); pass --skip-checks to start anyway");
+        }
+        eprintln!("Replication prerequisites are not met; starting anyway because of --skip-checks");
+    }
 
     // Initialize replication stream
     let mut stream = replication::ReplicationStream::new(
         &args.connection,
         &args.slot,
         &args.publication,
-        args.create_slot,
-        args.start_lsn,
+        options,
     )
     .await?;
 
@@ -99,7 +359,8 @@ async fn main() -> Result<()> {
     for target in target_list {
         match target {
             "stdout" => {
-                let stdout_output = output::StdoutOutput::new(output::OutputFormat::from_str(&args.format)?);
+                let stdout_output = output::StdoutOutput::new(output::OutputFormat::from_str(&args.format)?)
+                    .with_debezium_schema(args.debezium_schema);
                 targets.push(Arc::new(stdout_output));
                 eprintln!("  - stdout (format: {})", args.format);
             }
@@ -142,6 +403,8 @@ async fn main() -> Result<()> {
                 } else {
                     eprintln!("      Tables: all (dynamic routing)");
                 }
+                eprintln!("      On TRUNCATE: {}", args.feldera_truncate);
+                eprintln!("      On schema change: {}", args.feldera_schema_change);
                 if args.feldera_api_key.is_some() {
                     eprintln!("      API Key: [configured]");
                 }
@@ -151,7 +414,9 @@ async fn main() -> Result<()> {
                     feldera_pipeline,
                     allowed_tables,
                     args.feldera_api_key.as_deref(),
-                ).await?;
+                ).await?
+                .with_truncate_policy(output::FelderaTruncatePolicy::from_str(&args.feldera_truncate)?)
+                .with_schema_change_policy(output::FelderaSchemaChangePolicy::from_str(&args.feldera_schema_change)?);
                 targets.push(Arc::new(feldera_output));
             }
             _ => {
@@ -167,7 +432,18 @@ async fn main() -> Result<()> {
     }
     
     // Create composite output
-    let output_handler = output::CompositeOutput::new(targets);
+    let mut composite: Arc<dyn OutputTarget> = Arc::new(output::CompositeOutput::new(targets));
+    if args.group_transactions {
+        eprintln!("Grouping transactions (up to {} changes)", args.max_transaction_changes);
+        composite = Arc::new(output::TransactionGroupingOutput::new(composite, args.max_transaction_changes));
+    }
+    let output_handler: Arc<dyn OutputTarget> =
+        match output::StreamedTransactionMode::from_str(&args.streamed_transactions)? {
+            output::StreamedTransactionMode::Reassemble if args.streaming => {
+                Arc::new(output::ReassemblingOutput::new(composite))
+            }
+            _ => composite,
+        };
 
     // Set up graceful shutdown
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
@@ -187,18 +463,34 @@ async fn main() -> Result<()> {
                         // Write change to output targets
                         output_handler.write_change(&change).await?;
                         
+                        // Once every target holds the whole snapshot chunk, record it as done;
+                        // once they hold the whole transaction, let the slot advance
+                        let ends_transaction = change.ends_transaction();
+                        if ends_transaction || stream.snapshot_checkpoint_due() {
+                            output_handler.flush().await?;
+                            stream.save_snapshot_checkpoint()?;
+                            if ends_transaction {
+                                stream.confirm_commit().await?;
+                                continue;
+                            }
+                        }
+                        
                         // Mark LSN as processed for monitoring
-                        // Note: pg_logical_slot_get_binary_changes already auto-confirms,
-                        // this is for tracking/debugging purposes
                         if let Some(lsn) = change.get_lsn() {
                             stream.mark_processed(lsn);
-                        } else if let Some(lsn) = stream.last_received_lsn().map(|s| s.to_string()) {
-                            // For data events without LSN, use the last received LSN
-                            stream.mark_processed(&lsn);
                         }
                     }
                     Ok(None) => {
-                        // Keep-alive or no data
+                        // Keep-alive or no data; the snapshot may just have finished, and
+                        // messages sent outside a transaction are confirmed once flushed
+                        let confirm_due = stream.confirm_due();
+                        if confirm_due || stream.snapshot_checkpoint_due() {
+                            output_handler.flush().await?;
+                            stream.save_snapshot_checkpoint()?;
+                            if confirm_due {
+                                stream.confirm_commit().await?;
+                            }
+                        }
                         continue;
                     }
                     Err(e) => {
@@ -209,15 +501,26 @@ async fn main() -> Result<()> {
             }
             _ = shutdown_rx.recv() => {
                 eprintln!("Shutting down gracefully...");
-                
+
+                // Record the snapshot chunks the targets already hold
+                if stream.snapshot_checkpoint_due() {
+                    output_handler.flush().await?;
+                    stream.save_snapshot_checkpoint()?;
+                }
+
                 // Print final status
                 if let Some(lsn) = stream.last_processed_lsn() {
                     eprintln!("Last processed LSN: {}", lsn);
                 }
+                if let Some(lsn) = stream.last_flushed_lsn() {
+                    eprintln!("Last confirmed LSN: {}", lsn);
+                }
                 if let Ok(status) = stream.get_slot_status().await {
                     eprintln!("Replication slot status:");
                     eprintln!("  Confirmed flush LSN: {}", status.confirmed_flush_lsn);
-                    eprintln!("  Restart LSN: {}", status.restart_lsn);
+                    if let Some(lsn) = status.restart_lsn {
+                        eprintln!("  Restart LSN: {}", lsn);
+                    }
                     eprintln!("  Active: {}", status.active);
                 }
                 break;
@@ -227,3 +530,232 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Replication options from the `stream` command line
+fn replication_options(args: &StreamArgs) -> Result<replication::ReplicationOptions> {
+    Ok(replication::ReplicationOptions {
+        create_slot: args.create_slot,
+        snapshot: args.snapshot.then(|| snapshot::SnapshotOptions {
+            workers: args.snapshot_workers,
+            chunk_size: args.snapshot_chunk_size,
+            state_path: Some(
+                args.snapshot_state
+                    .clone()
+                    .unwrap_or_else(|| format!("{}.snapshot.json", args.slot).into()),
+            ),
+        }),
+        incremental_snapshot: args.signal_table.clone().map(|signal_table| {
+            incremental::IncrementalSnapshotOptions {
+                signal_table,
+                chunk_size: args.incremental_snapshot_chunk_size,
+            }
+        }),
+        start_lsn: args.start_lsn,
+        mode: replication::ReplicationMode::from_str(&args.replication_mode)?,
+        delivery: replication::DeliveryMode::from_str(&args.delivery)?,
+        status_interval: std::time::Duration::from_secs(args.status_interval),
+        protocol_version: args.protocol_version,
+        streaming: args.streaming,
+        two_phase: args.two_phase,
+        binary: args.binary,
+        messages: args.messages || args.message_prefixes.is_some(),
+        message_prefixes: args
+            .message_prefixes
+            .as_deref()
+            .map(|prefixes| {
+                prefixes
+                    .split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+async fn check_prerequisites(args: StreamArgs) -> Result<()> {
+    let options = replication_options(&args)?;
+    let results = check::run_checks(&args.connection, &args.slot, &args.publication, &options).await;
+    for result in &results {
+        println!("{}", result);
+    }
+    let failed = results.iter().filter(|r| r.status == check::CheckStatus::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, results.len());
+    }
+    println!("\nAll replication prerequisites are met");
+    Ok(())
+}
+
+async fn slot(command: SlotCommand) -> Result<()> {
+    match command {
+        SlotCommand::Create { connection, slot, two_phase } => {
+            let client = admin::connect(&connection.connection).await?;
+            let lsn = admin::create_slot(&client, &slot, two_phase).await?;
+            println!("Created replication slot '{}' at {}", slot, lsn);
+        }
+        SlotCommand::Drop { connection, slot } => {
+            let client = admin::connect(&connection.connection).await?;
+            admin::drop_slot(&client, &slot).await?;
+            println!("Dropped replication slot '{}'", slot);
+        }
+        SlotCommand::List { connection, json } => {
+            let client = admin::connect(&connection.connection).await?;
+            let slots = admin::list_slots(&client).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&slots)?);
+            } else {
+                let rows: Vec<Vec<String>> = slots
+                    .iter()
+                    .map(|s| {
+                        vec![
+                            s.slot_name.clone(),
+                            s.plugin.clone(),
+                            s.database.clone(),
+                            if s.active { "yes" } else { "no" }.to_string(),
+                            s.restart_lsn.map(|lsn| lsn.to_string()).unwrap_or_default(),
+                            s.confirmed_flush_lsn.to_string(),
+                            s.retained_wal_bytes.map(admin::format_bytes).unwrap_or_default(),
+                            s.wal_status.clone().unwrap_or_default(),
+                        ]
+                    })
+                    .collect();
+                print_table(
+                    &["SLOT", "PLUGIN", "DATABASE", "ACTIVE", "RESTART LSN", "CONFIRMED LSN", "RETAINED WAL", "WAL STATUS"],
+                    &rows,
+                );
+            }
+        }
+        SlotCommand::Advance { connection, slot, lsn } => {
+            let client = admin::connect(&connection.connection).await?;
+            let position = admin::advance_slot(&client, &slot, lsn).await?;
+            println!("Replication slot '{}' is now at {}", slot, position);
+        }
+        SlotCommand::Status { connection, slot, json } => {
+            let client = admin::connect(&connection.connection).await?;
+            let status = admin::slot_status(&client, &slot).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+                return Ok(());
+            }
+            println!("Slot:                {}", status.slot_name);
+            println!("Plugin:              {}", status.plugin);
+            println!("Database:            {}", status.database);
+            match status.active_pid {
+                Some(pid) if status.active => println!("Active:              yes (pid {})", pid),
+                _ => println!("Active:              {}", if status.active { "yes" } else { "no" }),
+            }
+            match status.restart_lsn {
+                Some(lsn) => println!("Restart LSN:         {}", lsn),
+                None => println!("Restart LSN:         none (slot invalidated)"),
+            }
+            println!("Confirmed flush LSN: {}", status.confirmed_flush_lsn);
+            if let Some(bytes) = status.retained_wal_bytes {
+                println!("Retained WAL:        {} ({} bytes)", admin::format_bytes(bytes), bytes);
+            }
+            if let Some(wal_status) = &status.wal_status {
+                println!("WAL status:          {}", wal_status);
+            }
+            if let Some(bytes) = status.safe_wal_size {
+                println!("Safe WAL size:       {}", admin::format_bytes(bytes));
+            }
+            println!("Two-phase:           {}", if status.two_phase { "yes" } else { "no" });
+        }
+    }
+    Ok(())
+}
+
+async fn publication(command: PublicationCommand) -> Result<()> {
+    let parse_tables = |specs: &[String]| -> Result<Vec<admin::TableSpec>> {
+        specs.iter().map(|spec| admin::TableSpec::from_str(spec)).collect()
+    };
+
+    match command {
+        PublicationCommand::Create { connection, publication, tables, all_tables, publish } => {
+            let sql = admin::build_create_publication(&publication, &parse_tables(&tables)?, all_tables, publish.as_deref())?;
+            let client = admin::connect(&connection.connection).await?;
+            client.batch_execute(&sql).await?;
+            println!("Created publication '{}'", publication);
+        }
+        PublicationCommand::Alter { connection, publication, add, drop, set, publish } => {
+            let statements = admin::build_alter_publication(
+                &publication,
+                &parse_tables(&add)?,
+                &parse_tables(&drop)?,
+                &parse_tables(&set)?,
+                publish.as_deref(),
+            )?;
+            let client = admin::connect(&connection.connection).await?;
+            // Several statements in one query run as a single transaction
+            client.batch_execute(&statements.join("; ")).await?;
+            println!("Altered publication '{}'", publication);
+        }
+        PublicationCommand::List { connection, json } => {
+            let client = admin::connect(&connection.connection).await?;
+            let publications = admin::list_publications(&client).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&publications)?);
+            } else {
+                let rows: Vec<Vec<String>> = publications
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            p.name.clone(),
+                            p.owner.clone(),
+                            if p.all_tables { "all".to_string() } else { p.tables.len().to_string() },
+                            p.publish.join(", "),
+                        ]
+                    })
+                    .collect();
+                print_table(&["PUBLICATION", "OWNER", "TABLES", "PUBLISH"], &rows);
+            }
+        }
+        PublicationCommand::Show { connection, publication, json } => {
+            let client = admin::connect(&connection.connection).await?;
+            let info = admin::show_publication(&client, &publication).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+                return Ok(());
+            }
+            println!("Publication: {}", info.name);
+            println!("Owner:       {}", info.owner);
+            println!("Publish:     {}", info.publish.join(", "));
+            if info.via_partition_root {
+                println!("Partitions are published through their root table");
+            }
+            println!("Tables:{}", if info.all_tables { " all tables" } else { "" });
+            for table in &info.tables {
+                let mut line = format!("  {}.{}", table.schema, table.table);
+                if let Some(columns) = &table.column_list {
+                    line.push_str(&format!(" ({})", columns.join(", ")));
+                }
+                if let Some(filter) = &table.row_filter {
+                    line.push_str(&format!(" WHERE {}", filter));
+                }
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print rows as columns aligned under their headers
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| rows.iter().map(|row| row[i].len()).chain([header.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<&str>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    };
+    line(headers.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
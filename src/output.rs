@@ -1,14 +1,17 @@
 use anyhow::{anyhow, Result};
-use crate::decoder::{Change, ColumnInfo};
+use crate::decoder::{Change, ColumnInfo, TruncatedRelation, Tuple};
+use crate::lsn::Lsn;
+use crate::value::{decode_text, PgValue};
 use serde_json;
 use async_nats::jetstream;
 use std::sync::Arc;
 use std::collections::{HashMap, HashSet};
 use reqwest::{Client, header};
 
-/// Convert a tuple (HashMap of string values) to proper JSON types based on column metadata
+/// Convert a tuple to proper JSON types based on column metadata.
+/// Binary-decoded values already carry their type; text values are parsed by type OID.
 fn tuple_to_json_with_types(
-    tuple: &HashMap<String, Option<String>>,
+    tuple: &Tuple,
     columns: &[ColumnInfo],
 ) -> serde_json::Value {
     let mut map = serde_json::Map::new();
@@ -17,53 +20,8 @@ fn tuple_to_json_with_types(
         if let Some(value_opt) = tuple.get(&col.name) {
             let json_value = match value_opt {
                 None => serde_json::Value::Null,
-                Some(string_val) => {
-                    // Convert based on PostgreSQL type OID
-                    // Common PostgreSQL type OIDs:
-                    // 16 = bool, 20 = int8, 21 = int2, 23 = int4
-                    // 700 = float4, 701 = float8
-                    // 1700 = numeric, 25 = text, 1043 = varchar
-                    // 1082 = date, 1083 = time, 1114 = timestamp, 1184 = timestamptz
-                    match col.type_id {
-                        // Boolean types
-                        16 => {
-                            match string_val.as_str() {
-                                "t" | "true" | "1" => serde_json::Value::Bool(true),
-                                "f" | "false" | "0" => serde_json::Value::Bool(false),
-                                _ => serde_json::Value::String(string_val.clone()),
-                            }
-                        }
-                        // Integer types: int2 (smallint), int4 (integer), int8 (bigint)
-                        20 | 21 | 23 => {
-                            string_val.parse::<i64>()
-                                .map(|n| serde_json::Value::Number(n.into()))
-                                .unwrap_or_else(|_| serde_json::Value::String(string_val.clone()))
-                        }
-                        // Float types: float4, float8
-                        700 | 701 => {
-                            string_val.parse::<f64>()
-                                .ok()
-                                .and_then(|f| serde_json::Number::from_f64(f))
-                                .map(serde_json::Value::Number)
-                                .unwrap_or_else(|| serde_json::Value::String(string_val.clone()))
-                        }
-                        // Numeric/decimal type
-                        1700 => {
-                            // Try to parse as integer first, then as float
-                            if let Ok(n) = string_val.parse::<i64>() {
-                                serde_json::Value::Number(n.into())
-                            } else if let Ok(f) = string_val.parse::<f64>() {
-                                serde_json::Number::from_f64(f)
-                                    .map(serde_json::Value::Number)
-                                    .unwrap_or_else(|| serde_json::Value::String(string_val.clone()))
-                            } else {
-                                serde_json::Value::String(string_val.clone())
-                            }
-                        }
-                        // All other types (text, varchar, timestamp, etc.) remain as strings
-                        _ => serde_json::Value::String(string_val.clone()),
-                    }
-                }
+                Some(PgValue::Text(text)) => decode_text(col.type_id, text).to_json(),
+                Some(typed) => typed.to_json(),
             };
             map.insert(col.name.clone(), json_value);
         }
@@ -76,6 +34,12 @@ fn tuple_to_json_with_types(
 #[async_trait::async_trait]
 pub trait OutputTarget: Send + Sync {
     async fn write_change(&self, change: &Change) -> Result<()>;
+
+    /// Ensure every change written so far has been durably accepted by the target.
+    /// Called before a transaction's position is confirmed to PostgreSQL.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -88,6 +52,7 @@ pub enum OutputFormat {
 }
 
 impl OutputFormat {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "json" => Ok(OutputFormat::Json),
@@ -122,7 +87,18 @@ pub struct DebeziumSource {
     pub db: String,
     pub schema: String,
     pub table: String,
+    /// WAL position of the change
     pub lsn: String,
+    /// Id of the transaction the change belongs to
+    #[serde(rename = "txId", default, skip_serializing_if = "Option::is_none")]
+    pub tx_id: Option<u32>,
+    /// Replication origin of the transaction, if it was replayed from another node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// `"true"` for rows read by the initial snapshot, `"incremental"` for rows of an
+    /// incremental snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
 }
 
 /// Debezium transaction metadata
@@ -133,14 +109,65 @@ pub struct DebeziumTransaction {
     pub data_collection_order: i64,
 }
 
+/// Debezium transaction metadata event for two-phase commit boundaries
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct DebeziumTransactionEvent {
+    pub status: String,
+    pub id: String,
+    pub gid: String,
+    pub ts_ms: i64,
+}
+
+/// Convert a protocol timestamp (microseconds since 2000-01-01) to Unix milliseconds
+fn pg_timestamp_to_millis(timestamp: i64) -> i64 {
+    timestamp / 1000 + 946_684_800_000
+}
+
+/// Convert a two-phase commit event to a Debezium transaction metadata event
+fn convert_to_debezium_transaction(change: &Change) -> Option<DebeziumTransactionEvent> {
+    let (status, lsn, timestamp, xid, gid) = match change {
+        Change::BeginPrepare { lsn, timestamp, xid, gid } => ("BEGIN", lsn, timestamp, xid, gid),
+        Change::Prepare { lsn, timestamp, xid, gid }
+        | Change::StreamPrepare { lsn, timestamp, xid, gid } => ("PREPARE", lsn, timestamp, xid, gid),
+        Change::CommitPrepared { lsn, timestamp, xid, gid } => ("COMMIT", lsn, timestamp, xid, gid),
+        Change::RollbackPrepared { lsn, timestamp, xid, gid, .. } => ("ROLLBACK", lsn, timestamp, xid, gid),
+        _ => return None,
+    };
+
+    Some(DebeziumTransactionEvent {
+        status: status.to_string(),
+        id: format!("{}:{}", xid, lsn),
+        gid: gid.clone(),
+        ts_ms: pg_timestamp_to_millis(*timestamp),
+    })
+}
+
+/// Placeholder Debezium uses for unchanged TOASTed values that were not sent
+pub const DEBEZIUM_UNAVAILABLE_VALUE: &str = "__debezium_unavailable_value";
+
+/// Debezium `before`/`after` row; typed by column OID when the relation's columns are given
+fn debezium_row(tuple: &Tuple, columns: Option<&[ColumnInfo]>) -> Option<serde_json::Value> {
+    match columns {
+        Some(columns) => Some(tuple_to_json_with_types(tuple, columns)),
+        None => serde_json::to_value(tuple).ok(),
+    }
+}
+
 /// Convert a Change event to Debezium format
-fn convert_to_debezium(change: &Change) -> Option<DebeziumEnvelope> {
+fn convert_to_debezium(change: &Change, columns: Option<&[ColumnInfo]>) -> Option<DebeziumEnvelope> {
     use chrono::Utc;
     let ts_ms = Utc::now().timestamp_millis();
-    
+    let lsn = change.get_lsn().map(|lsn| lsn.to_string()).unwrap_or_default();
+    let transaction = change.transaction();
+    // Debezium reports when the change was committed in source.ts_ms
+    let source_ts_ms = transaction
+        .and_then(|t| t.commit_timestamp)
+        .map(pg_timestamp_to_millis)
+        .unwrap_or(ts_ms);
+
     match change {
-        Change::Insert { schema, table, new_tuple, relation_id } => {
-            let after = serde_json::to_value(new_tuple).ok()?;
+        Change::Insert { schema, table, new_tuple, .. } => {
+            let after = debezium_row(new_tuple, columns)?;
             Some(DebeziumEnvelope {
                 before: None,
                 after: Some(after),
@@ -148,20 +175,28 @@ fn convert_to_debezium(change: &Change) -> Option<DebeziumEnvelope> {
                     version: "pgoutput-cmdline-0.1.0".to_string(),
                     connector: "postgresql".to_string(),
                     name: "pgoutput-cmdline".to_string(),
-                    ts_ms,
+                    ts_ms: source_ts_ms,
                     db: "postgres".to_string(),
                     schema: schema.clone(),
                     table: table.clone(),
-                    lsn: relation_id.to_string(),
+                    lsn: lsn.clone(),
+                    tx_id: transaction.map(|t| t.xid),
+                    origin: None,
+                    snapshot: None,
                 },
                 op: "c".to_string(), // c = create/insert
                 ts_ms,
                 transaction: None,
             })
         }
-        Change::Update { schema, table, old_tuple, new_tuple, relation_id } => {
-            let before = old_tuple.as_ref().and_then(|t| serde_json::to_value(t).ok());
-            let after = serde_json::to_value(new_tuple).ok()?;
+        Change::Update { schema, table, old_tuple, new_tuple, unchanged_columns, .. } => {
+            let before = old_tuple.as_ref().and_then(|t| debezium_row(t, columns));
+            let mut after = debezium_row(new_tuple, columns)?;
+            if let Some(after) = after.as_object_mut() {
+                for column in unchanged_columns {
+                    after.insert(column.clone(), serde_json::Value::from(DEBEZIUM_UNAVAILABLE_VALUE));
+                }
+            }
             Some(DebeziumEnvelope {
                 before,
                 after: Some(after),
@@ -169,19 +204,22 @@ fn convert_to_debezium(change: &Change) -> Option<DebeziumEnvelope> {
                     version: "pgoutput-stream-0.1.0".to_string(),
                     connector: "postgresql".to_string(),
                     name: "pgoutput-stream".to_string(),
-                    ts_ms,
+                    ts_ms: source_ts_ms,
                     db: "postgres".to_string(),
                     schema: schema.clone(),
                     table: table.clone(),
-                    lsn: relation_id.to_string(),
+                    lsn: lsn.clone(),
+                    tx_id: transaction.map(|t| t.xid),
+                    origin: None,
+                    snapshot: None,
                 },
                 op: "u".to_string(), // u = update
                 ts_ms,
                 transaction: None,
             })
         }
-        Change::Delete { schema, table, old_tuple, relation_id } => {
-            let before = serde_json::to_value(old_tuple).ok()?;
+        Change::Delete { schema, table, old_tuple, .. } => {
+            let before = debezium_row(old_tuple, columns)?;
             Some(DebeziumEnvelope {
                 before: Some(before),
                 after: None,
@@ -189,22 +227,231 @@ fn convert_to_debezium(change: &Change) -> Option<DebeziumEnvelope> {
                     version: "pgoutput-cmdline-0.1.0".to_string(),
                     connector: "postgresql".to_string(),
                     name: "pgoutput-cmdline".to_string(),
-                    ts_ms,
+                    ts_ms: source_ts_ms,
                     db: "postgres".to_string(),
                     schema: schema.clone(),
                     table: table.clone(),
-                    lsn: relation_id.to_string(),
+                    lsn: lsn.clone(),
+                    tx_id: transaction.map(|t| t.xid),
+                    origin: None,
+                    snapshot: None,
                 },
                 op: "d".to_string(), // d = delete
                 ts_ms,
                 transaction: None,
             })
         }
+        Change::Snapshot { schema, table, new_tuple, incremental, .. } => {
+            let after = debezium_row(new_tuple, columns)?;
+            Some(DebeziumEnvelope {
+                before: None,
+                after: Some(after),
+                source: DebeziumSource {
+                    version: "pgoutput-stream-0.1.0".to_string(),
+                    connector: "postgresql".to_string(),
+                    name: "pgoutput-stream".to_string(),
+                    ts_ms,
+                    db: "postgres".to_string(),
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    lsn: lsn.clone(),
+                    tx_id: None,
+                    origin: None,
+                    snapshot: Some(if *incremental { "incremental" } else { "true" }.to_string()),
+                },
+                op: "r".to_string(), // r = read (snapshot)
+                ts_ms,
+                transaction: None,
+            })
+        }
         // Begin, Commit, and Relation events are not converted to Debezium format
         _ => None,
     }
 }
 
+/// Debezium envelope with its Kafka Connect schema, as written with `schemas.enable=true`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct DebeziumRecord {
+    pub schema: serde_json::Value,
+    pub payload: DebeziumEnvelope,
+}
+
+/// Kafka Connect schema of one column. Values are typed the way
+/// `tuple_to_json_with_types` renders them; the source type, length and scale
+/// are carried in Debezium's `__debezium.source.column.*` parameters.
+fn debezium_field_schema(column: &ColumnInfo) -> serde_json::Value {
+    let connect_type = match column.type_id {
+        16 => "boolean",
+        21 => "int16",
+        23 => "int32",
+        20 | 26 => "int64",
+        700 => "float",
+        701 => "double",
+        17 => "bytes",
+        _ => "string",
+    };
+
+    let mut parameters = serde_json::Map::new();
+    if let Some(type_name) = &column.type_name {
+        parameters.insert("__debezium.source.column.type".to_string(), type_name.to_uppercase().into());
+    }
+    if let Some(length) = column.length.or(column.precision) {
+        parameters.insert("__debezium.source.column.length".to_string(), length.to_string().into());
+    }
+    if let Some(scale) = column.scale {
+        parameters.insert("__debezium.source.column.scale".to_string(), scale.to_string().into());
+    }
+
+    let mut field = serde_json::json!({
+        "type": connect_type,
+        "optional": !column.is_key(),
+        "field": column.name,
+    });
+    if !parameters.is_empty() {
+        field["parameters"] = serde_json::Value::Object(parameters);
+    }
+    field
+}
+
+/// Kafka Connect schema of the Debezium envelope for a table
+pub fn debezium_schema(schema: &str, table: &str, columns: &[ColumnInfo]) -> serde_json::Value {
+    use serde_json::json;
+
+    let prefix = format!("pgoutput-stream.{}.{}", schema, table);
+    let fields: Vec<_> = columns.iter().map(debezium_field_schema).collect();
+    let row = |field: &str| {
+        json!({
+            "type": "struct",
+            "optional": true,
+            "name": format!("{}.Value", prefix),
+            "field": field,
+            "fields": fields,
+        })
+    };
+    let string = |field: &str, optional: bool| json!({ "type": "string", "optional": optional, "field": field });
+
+    json!({
+        "type": "struct",
+        "optional": false,
+        "name": format!("{}.Envelope", prefix),
+        "fields": [
+            row("before"),
+            row("after"),
+            {
+                "type": "struct",
+                "optional": false,
+                "name": "io.debezium.connector.postgresql.Source",
+                "field": "source",
+                "fields": [
+                    string("version", false),
+                    string("connector", false),
+                    string("name", false),
+                    { "type": "int64", "optional": false, "field": "ts_ms" },
+                    string("db", false),
+                    string("schema", false),
+                    string("table", false),
+                    string("lsn", false),
+                    string("origin", true),
+                    string("snapshot", true),
+                ],
+            },
+            string("op", false),
+            { "type": "int64", "optional": true, "field": "ts_ms" },
+        ],
+    })
+}
+
+/// Debezium logical decoding message event (`op: "m"`)
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct DebeziumMessageEvent {
+    pub op: String,
+    pub ts_ms: i64,
+    pub source: DebeziumMessageSource,
+    pub message: DebeziumMessage,
+}
+
+/// Source metadata of a Debezium message event
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct DebeziumMessageSource {
+    pub version: String,
+    pub connector: String,
+    pub name: String,
+    pub ts_ms: i64,
+    pub db: String,
+    pub lsn: Lsn,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<u32>,
+}
+
+/// Prefix and content of a logical decoding message
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct DebeziumMessage {
+    pub prefix: String,
+    pub content: String,
+}
+
+/// Convert a logical decoding message to a Debezium message event
+fn convert_to_debezium_message(change: &Change) -> Option<DebeziumMessageEvent> {
+    let Change::Message { prefix, content, lsn, xid, .. } = change else {
+        return None;
+    };
+    let ts_ms = chrono::Utc::now().timestamp_millis();
+
+    Some(DebeziumMessageEvent {
+        op: "m".to_string(), // m = message
+        ts_ms,
+        source: DebeziumMessageSource {
+            version: "pgoutput-stream-0.1.0".to_string(),
+            connector: "postgresql".to_string(),
+            name: "pgoutput-stream".to_string(),
+            ts_ms,
+            db: "postgres".to_string(),
+            lsn: *lsn,
+            txid: *xid,
+        },
+        message: DebeziumMessage {
+            prefix: prefix.clone(),
+            content: content.clone(),
+        },
+    })
+}
+
+/// Convert a TRUNCATE to one Debezium `op: "t"` event per affected table
+fn convert_to_debezium_truncate(change: &Change) -> Vec<DebeziumEnvelope> {
+    let Change::Truncate { relations, lsn, transaction, .. } = change else {
+        return Vec::new();
+    };
+    let ts_ms = chrono::Utc::now().timestamp_millis();
+    let source_ts_ms = transaction
+        .and_then(|t| t.commit_timestamp)
+        .map(pg_timestamp_to_millis)
+        .unwrap_or(ts_ms);
+
+    relations
+        .iter()
+        .map(|relation| DebeziumEnvelope {
+            before: None,
+            after: None,
+            source: DebeziumSource {
+                version: "pgoutput-stream-0.1.0".to_string(),
+                connector: "postgresql".to_string(),
+                name: "pgoutput-stream".to_string(),
+                ts_ms: source_ts_ms,
+                db: "postgres".to_string(),
+                schema: relation.schema.clone(),
+                table: relation.table.clone(),
+                lsn: lsn.map(|lsn| lsn.to_string()).unwrap_or_default(),
+                tx_id: transaction.map(|t| t.xid),
+                origin: None,
+                snapshot: None,
+            },
+            op: "t".to_string(), // t = truncate
+            ts_ms,
+            transaction: None,
+        })
+        .collect()
+}
+
 /// Feldera InsertDelete format event
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
@@ -217,12 +464,37 @@ pub struct FelderaUpdate {
     pub update: Option<serde_json::Value>,
 }
 
+/// Column metadata of the relations announced on the stream a target is fed from.
+///
+/// Targets see the same Relation events as the stream's decoder, so each keeps
+/// its own copy to type the values of later data events.
+#[derive(Default)]
+struct RelationColumns(std::sync::Mutex<HashMap<u32, Vec<ColumnInfo>>>);
+
+impl RelationColumns {
+    /// Record the columns of a Relation event; other changes are ignored
+    fn observe(&self, change: &Change) {
+        if let Change::Relation { relation_id, columns, .. } = change {
+            self.0.lock().unwrap().insert(*relation_id, columns.clone());
+        }
+    }
+
+    fn get(&self, relation_id: u32) -> Option<Vec<ColumnInfo>> {
+        self.0.lock().unwrap().get(&relation_id).cloned()
+    }
+}
+
 /// Convert a Change event to Feldera InsertDelete format
 /// Updates are represented as delete (old) + insert (new) pairs
-fn convert_to_feldera(change: &Change) -> Vec<FelderaUpdate> {
-    match change {
-        Change::Insert { relation_id, new_tuple, .. } => {
-            if let Some(columns) = crate::decoder::get_relation_columns(*relation_id) {
+///
+/// Unchanged TOAST columns of an UPDATE are filled in from the full old tuple
+/// (sent with REPLICA IDENTITY FULL); without it, or with only a key image, the
+/// new row cannot be rebuilt and the update is rejected rather than inserted
+/// with NULLs.
+fn convert_to_feldera(change: &Change, relations: &RelationColumns) -> Result<Vec<FelderaUpdate>> {
+    let events = match change {
+        Change::Insert { relation_id, new_tuple, .. } | Change::Snapshot { relation_id, new_tuple, .. } => {
+            if let Some(columns) = relations.get(*relation_id) {
                 let insert_data = tuple_to_json_with_types(new_tuple, &columns);
                 vec![FelderaUpdate {
                     insert: Some(insert_data),
@@ -242,9 +514,30 @@ fn convert_to_feldera(change: &Change) -> Vec<FelderaUpdate> {
                 }
             }
         }
-        Change::Update { relation_id, old_tuple, new_tuple, .. } => {
-            let columns = crate::decoder::get_relation_columns(*relation_id);
+        Change::Update { relation_id, schema, table, old_tuple, old_key_only, new_tuple, unchanged_columns, .. } => {
+            let columns = relations.get(*relation_id);
             let mut events = Vec::new();
+
+            let mut repaired;
+            let new_tuple = if unchanged_columns.is_empty() {
+                new_tuple
+            } else {
+                repaired = new_tuple.clone();
+                for column in unchanged_columns {
+                    // A key-only image has NULL in place of every non-key column
+                    let value = old_tuple
+                        .as_ref()
+                        .filter(|_| !old_key_only)
+                        .and_then(|old| old.get(column))
+                        .ok_or_else(|| anyhow!(
+                            "UPDATE on {}.{} did not send unchanged TOAST column '{}' and no before-image is available; \
+                             set REPLICA IDENTITY FULL on the table",
+                            schema, table, column
+                        ))?;
+                    repaired.insert(column.clone(), value.clone());
+                }
+                &repaired
+            };
             
             // First, delete the old state
             if let Some(old) = old_tuple {
@@ -275,7 +568,7 @@ fn convert_to_feldera(change: &Change) -> Vec<FelderaUpdate> {
             events
         }
         Change::Delete { relation_id, old_tuple, .. } => {
-            if let Some(columns) = crate::decoder::get_relation_columns(*relation_id) {
+            if let Some(columns) = relations.get(*relation_id) {
                 let delete_data = tuple_to_json_with_types(old_tuple, &columns);
                 vec![FelderaUpdate {
                     insert: None,
@@ -297,23 +590,74 @@ fn convert_to_feldera(change: &Change) -> Vec<FelderaUpdate> {
         }
         // Begin, Commit, and Relation events are not converted to Feldera format
         _ => vec![],
-    }
+    };
+    Ok(events)
 }
 
 /// Stdout output target
 pub struct StdoutOutput {
     format: OutputFormat,
+    /// Replication origin of the current transaction, reported in Debezium `source`
+    origin: std::sync::Mutex<Option<String>>,
+    relations: RelationColumns,
+    /// Wrap Debezium events with the table's Kafka Connect schema
+    debezium_schema: bool,
 }
 
 impl StdoutOutput {
     pub fn new(format: OutputFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            origin: std::sync::Mutex::new(None),
+            relations: RelationColumns::default(),
+            debezium_schema: false,
+        }
+    }
+
+    /// Write Debezium events as `{"schema", "payload"}` records with typed row values
+    pub fn with_debezium_schema(mut self, enabled: bool) -> Self {
+        self.debezium_schema = enabled;
+        self
+    }
+
+    /// Columns of a relation when Debezium events are written with their schema
+    fn debezium_columns(&self, relation_id: u32) -> Option<Vec<ColumnInfo>> {
+        if self.debezium_schema {
+            self.relations.get(relation_id)
+        } else {
+            None
+        }
+    }
+
+    /// Print a Debezium envelope, wrapped with its table schema when columns are given
+    fn print_debezium(&self, envelope: DebeziumEnvelope, columns: Option<&[ColumnInfo]>) -> Result<()> {
+        match columns {
+            Some(columns) => {
+                let record = DebeziumRecord {
+                    schema: debezium_schema(&envelope.source.schema, &envelope.source.table, columns),
+                    payload: envelope,
+                };
+                println!("{}", serde_json::to_string(&record)?);
+            }
+            None => println!("{}", serde_json::to_string(&envelope)?),
+        }
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl OutputTarget for StdoutOutput {
     async fn write_change(&self, change: &Change) -> Result<()> {
+        // Debezium and Feldera events describe single rows, so grouped transactions are unpacked
+        if matches!(self.format, OutputFormat::Debezium | OutputFormat::Feldera) {
+            if let Some(changes) = change.expand_transaction() {
+                for change in &changes {
+                    self.write_change(change).await?;
+                }
+                return Ok(());
+            }
+        }
+
         match self.format {
             OutputFormat::Json => {
                 println!("{}", serde_json::to_string(change)?);
@@ -325,21 +669,51 @@ impl OutputTarget for StdoutOutput {
                 print_text_format(change);
             }
             OutputFormat::Debezium => {
+                if let Change::Begin { origin, .. } = change {
+                    *self.origin.lock().unwrap() = origin.clone();
+                }
+                self.relations.observe(change);
+                let columns = match change {
+                    Change::Insert { relation_id, .. }
+                    | Change::Update { relation_id, .. }
+                    | Change::Delete { relation_id, .. }
+                    | Change::Snapshot { relation_id, .. } => self.debezium_columns(*relation_id),
+                    _ => None,
+                };
                 // Convert to Debezium format and print only data events (not Begin/Commit/Relation)
-                if let Some(debezium_event) = convert_to_debezium(change) {
-                    println!("{}", serde_json::to_string(&debezium_event)?);
+                if let Some(mut debezium_event) = convert_to_debezium(change, columns.as_deref()) {
+                    debezium_event.source.origin = self.origin.lock().unwrap().clone();
+                    self.print_debezium(debezium_event, columns.as_deref())?;
+                } else if let Some(transaction_event) = convert_to_debezium_transaction(change) {
+                    println!("{}", serde_json::to_string(&transaction_event)?);
+                }
+                if let Change::Truncate { relations, .. } = change {
+                    for (relation, truncate_event) in relations.iter().zip(convert_to_debezium_truncate(change)) {
+                        let columns = self.debezium_columns(relation.relation_id);
+                        self.print_debezium(truncate_event, columns.as_deref())?;
+                    }
+                }
+                if let Some(message_event) = convert_to_debezium_message(change) {
+                    println!("{}", serde_json::to_string(&message_event)?);
                 }
             }
             OutputFormat::Feldera => {
                 // Convert to Feldera InsertDelete format
                 // Updates produce two events: delete + insert
-                for feldera_event in convert_to_feldera(change) {
+                self.relations.observe(change);
+                for feldera_event in convert_to_feldera(change, &self.relations)? {
                     println!("{}", serde_json::to_string(&feldera_event)?);
                 }
             }
         }
         Ok(())
     }
+
+    async fn flush(&self) -> Result<()> {
+        use std::io::Write;
+        std::io::stdout().flush()?;
+        Ok(())
+    }
 }
 
 /// NATS JetStream output target
@@ -358,7 +732,10 @@ impl NatsOutput {
         let jetstream = jetstream::new(client);
         
         // Create or get the stream
-        let stream_subjects = format!("{}.*.*.*", subject_prefix);
+        let stream_subjects = vec![
+            format!("{}.*.*.*", subject_prefix),
+            format!("{}.messages.>", subject_prefix),
+        ];
         match jetstream.get_stream(stream_name).await {
             Ok(_stream) => {
                 eprintln!("Using existing NATS stream: {}", stream_name);
@@ -372,7 +749,7 @@ impl NatsOutput {
                 eprintln!("Creating NATS stream: {}", stream_name);
                 jetstream.create_stream(jetstream::stream::Config {
                     name: stream_name.to_string(),
-                    subjects: vec![stream_subjects],
+                    subjects: stream_subjects,
                     max_messages: 1_000_000,
                     max_bytes: 1_000_000_000, // 1GB
                     ..Default::default()
@@ -387,22 +764,48 @@ impl NatsOutput {
         }
     }
 
-    fn get_subject(&self, change: &Change) -> String {
+    /// Subjects a change is published to (a TRUNCATE goes to every affected table)
+    fn get_subjects(&self, change: &Change) -> Vec<String> {
         match change {
-            Change::Begin { .. } => format!("{}.transactions.begin.event", self.subject_prefix),
-            Change::Commit { .. } => format!("{}.transactions.commit.event", self.subject_prefix),
+            Change::Begin { .. } => vec![format!("{}.transactions.begin.event", self.subject_prefix)],
+            Change::Commit { .. } => vec![format!("{}.transactions.commit.event", self.subject_prefix)],
+            Change::Origin { .. } => vec![format!("{}.transactions.origin.event", self.subject_prefix)],
             Change::Relation { schema, table, .. } => {
-                format!("{}.{}.{}.relation", self.subject_prefix, schema, table)
+                vec![format!("{}.{}.{}.relation", self.subject_prefix, schema, table)]
+            }
+            Change::SchemaChanged { schema, table, .. } => {
+                vec![format!("{}.{}.{}.schema", self.subject_prefix, schema, table)]
             }
             Change::Insert { schema, table, .. } => {
-                format!("{}.{}.{}.insert", self.subject_prefix, schema, table)
+                vec![format!("{}.{}.{}.insert", self.subject_prefix, schema, table)]
             }
             Change::Update { schema, table, .. } => {
-                format!("{}.{}.{}.update", self.subject_prefix, schema, table)
+                vec![format!("{}.{}.{}.update", self.subject_prefix, schema, table)]
             }
             Change::Delete { schema, table, .. } => {
-                format!("{}.{}.{}.delete", self.subject_prefix, schema, table)
+                vec![format!("{}.{}.{}.delete", self.subject_prefix, schema, table)]
+            }
+            Change::Snapshot { schema, table, .. } => {
+                vec![format!("{}.{}.{}.snapshot", self.subject_prefix, schema, table)]
+            }
+            Change::Message { prefix, .. } => {
+                vec![format!("{}.messages.{}", self.subject_prefix, subject_token(prefix))]
             }
+            // Published once per truncated table
+            Change::Truncate { relations, .. } => relations
+                .iter()
+                .map(|r| format!("{}.{}.{}.truncate", self.subject_prefix, r.schema, r.table))
+                .collect(),
+            Change::StreamStart { .. } => vec![format!("{}.transactions.stream_start.event", self.subject_prefix)],
+            Change::StreamStop => vec![format!("{}.transactions.stream_stop.event", self.subject_prefix)],
+            Change::StreamCommit { .. } => vec![format!("{}.transactions.stream_commit.event", self.subject_prefix)],
+            Change::StreamAbort { .. } => vec![format!("{}.transactions.stream_abort.event", self.subject_prefix)],
+            Change::BeginPrepare { .. } => vec![format!("{}.transactions.begin_prepare.event", self.subject_prefix)],
+            Change::Prepare { .. } => vec![format!("{}.transactions.prepare.event", self.subject_prefix)],
+            Change::CommitPrepared { .. } => vec![format!("{}.transactions.commit_prepared.event", self.subject_prefix)],
+            Change::RollbackPrepared { .. } => vec![format!("{}.transactions.rollback_prepared.event", self.subject_prefix)],
+            Change::StreamPrepare { .. } => vec![format!("{}.transactions.stream_prepare.event", self.subject_prefix)],
+            Change::Transaction { .. } => vec![format!("{}.transactions.transaction.event", self.subject_prefix)],
         }
     }
 }
@@ -410,23 +813,103 @@ impl NatsOutput {
 #[async_trait::async_trait]
 impl OutputTarget for NatsOutput {
     async fn write_change(&self, change: &Change) -> Result<()> {
-        let subject = self.get_subject(change);
         let payload = serde_json::to_vec(change)?;
         
-        self.context.publish(subject.clone(), payload.into())
-            .await
-            .map_err(|e| anyhow!("Failed to publish to NATS subject {}: {}", subject, e))?;
+        for subject in self.get_subjects(change) {
+            // Wait for the JetStream ack so the message is persisted before we move on
+            self.context.publish(subject.clone(), payload.clone().into())
+                .await
+                .map_err(|e| anyhow!("Failed to publish to NATS subject {}: {}", subject, e))?
+                .await
+                .map_err(|e| anyhow!("NATS did not acknowledge message on subject {}: {}", subject, e))?;
+        }
         
         Ok(())
     }
 }
 
+/// What the Feldera target does with a TRUNCATE, which its ingress API cannot express
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FelderaTruncatePolicy {
+    /// Fail the stream so the pipeline is never left silently stale
+    Error,
+    /// Log a warning and continue; the Feldera tables keep the truncated rows
+    Skip,
+}
+
+impl FelderaTruncatePolicy {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(FelderaTruncatePolicy::Error),
+            "skip" => Ok(FelderaTruncatePolicy::Skip),
+            _ => Err(anyhow!("Unknown Feldera truncate policy: {}. Valid options: error, skip", s)),
+        }
+    }
+}
+
+/// What the Feldera target does when a table's columns change mid-stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FelderaSchemaChangePolicy {
+    /// Fail the stream before rows with the new shape are pushed
+    Error,
+    /// Log a warning and keep pushing rows to the existing Feldera table
+    Warn,
+}
+
+impl FelderaSchemaChangePolicy {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(FelderaSchemaChangePolicy::Error),
+            "warn" => Ok(FelderaSchemaChangePolicy::Warn),
+            _ => Err(anyhow!("Unknown Feldera schema change policy: {}. Valid options: error, warn", s)),
+        }
+    }
+}
+
+/// One-line summary of a SchemaChanged event, e.g. `added: email; retyped: name`
+fn describe_schema_change(change: &Change) -> String {
+    let Change::SchemaChanged { added, dropped, renamed, retyped, .. } = change else {
+        return String::new();
+    };
+    let names = |columns: &[ColumnInfo]| columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>().join(", ");
+    let mut parts = Vec::new();
+    if !added.is_empty() {
+        parts.push(format!("added: {}", names(added)));
+    }
+    if !dropped.is_empty() {
+        parts.push(format!("dropped: {}", names(dropped)));
+    }
+    if !renamed.is_empty() {
+        let pairs: Vec<String> = renamed.iter().map(|c| format!("{} -> {}", c.before.name, c.after.name)).collect();
+        parts.push(format!("renamed: {}", pairs.join(", ")));
+    }
+    if !retyped.is_empty() {
+        let after: Vec<ColumnInfo> = retyped.iter().map(|c| c.after.clone()).collect();
+        parts.push(format!("retyped: {}", names(&after)));
+    }
+    parts.join("; ")
+}
+
+/// Make a free-form string usable inside a NATS subject (no spaces or wildcards)
+fn subject_token(s: &str) -> String {
+    let token: String = s
+        .chars()
+        .map(|c| if c.is_whitespace() || c == '*' || c == '>' { '_' } else { c })
+        .collect();
+    if token.is_empty() { "_".to_string() } else { token }
+}
+
 /// Feldera HTTP output target
 pub struct FelderaOutput {
     client: Client,
     base_url: String,
     pipeline: String,
     allowed_tables: Option<HashSet<String>>,
+    truncate_policy: FelderaTruncatePolicy,
+    schema_change_policy: FelderaSchemaChangePolicy,
+    relations: RelationColumns,
 }
 
 impl FelderaOutput {
@@ -476,18 +959,97 @@ impl FelderaOutput {
             base_url: base_url.trim_end_matches('/').to_string(),
             pipeline: pipeline.to_string(),
             allowed_tables: allowed_tables_set,
+            truncate_policy: FelderaTruncatePolicy::Error,
+            schema_change_policy: FelderaSchemaChangePolicy::Warn,
+            relations: RelationColumns::default(),
         })
     }
+
+    /// Set how TRUNCATE events are handled (defaults to `Error`)
+    pub fn with_truncate_policy(mut self, policy: FelderaTruncatePolicy) -> Self {
+        self.truncate_policy = policy;
+        self
+    }
+
+    /// Set how column changes of a streamed table are handled (defaults to `Warn`)
+    pub fn with_schema_change_policy(mut self, policy: FelderaSchemaChangePolicy) -> Self {
+        self.schema_change_policy = policy;
+        self
+    }
+
+    fn handle_schema_change(&self, change: &Change) -> Result<()> {
+        let Change::SchemaChanged { schema, table, .. } = change else {
+            return Ok(());
+        };
+        let qualified_table = Self::qualify_table_name(schema, table);
+        if self.allowed_tables.as_ref().is_some_and(|allowed| !allowed.contains(&qualified_table)) {
+            return Ok(());
+        }
+
+        match self.schema_change_policy {
+            FelderaSchemaChangePolicy::Error => Err(anyhow!(
+                "Columns of {} changed ({}); update the Feldera table and restart, \
+                 or restart with --feldera-schema-change warn",
+                qualified_table,
+                describe_schema_change(change)
+            )),
+            FelderaSchemaChangePolicy::Warn => {
+                eprintln!(
+                    "Warning: Columns of {} changed ({}); rows are pushed with the new shape",
+                    qualified_table,
+                    describe_schema_change(change)
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_truncate(&self, relations: &[TruncatedRelation]) -> Result<()> {
+        let tables: Vec<String> = relations
+            .iter()
+            .map(|r| Self::qualify_table_name(&r.schema, &r.table))
+            .filter(|t| self.allowed_tables.as_ref().is_none_or(|allowed| allowed.contains(t)))
+            .collect();
+        if tables.is_empty() {
+            return Ok(());
+        }
+
+        match self.truncate_policy {
+            FelderaTruncatePolicy::Error => Err(anyhow!(
+                "TRUNCATE of {} cannot be applied through the Feldera ingress API; \
+                 resynchronize the pipeline or restart with --feldera-truncate skip",
+                tables.join(", ")
+            )),
+            FelderaTruncatePolicy::Skip => {
+                eprintln!(
+                    "Warning: Skipping TRUNCATE of {} for Feldera; pipeline tables keep the old rows",
+                    tables.join(", ")
+                );
+                Ok(())
+            }
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl OutputTarget for FelderaOutput {
     async fn write_change(&self, change: &Change) -> Result<()> {
+        if let Some(changes) = change.expand_transaction() {
+            for change in &changes {
+                self.write_change(change).await?;
+            }
+            return Ok(());
+        }
+        self.relations.observe(change);
+
         // Extract schema and table from the change event
         let (schema, table) = match change {
             Change::Insert { schema, table, .. } => (schema, table),
             Change::Update { schema, table, .. } => (schema, table),
             Change::Delete { schema, table, .. } => (schema, table),
+            Change::Snapshot { schema, table, .. } => (schema, table),
+            Change::Truncate { relations, .. } => return self.handle_truncate(relations),
+            Change::SchemaChanged { .. } => return self.handle_schema_change(change),
             // Skip non-data events (Begin, Commit, Relation)
             _ => return Ok(()),
         };
@@ -504,7 +1066,7 @@ impl OutputTarget for FelderaOutput {
         }
         
         // Convert to Feldera InsertDelete format
-        let feldera_events = convert_to_feldera(change);
+        let feldera_events = convert_to_feldera(change, &self.relations)?;
         
         // Skip if conversion resulted in empty events
         if feldera_events.is_empty() {
@@ -560,6 +1122,259 @@ impl OutputTarget for CompositeOutput {
         }
         Ok(())
     }
+
+    async fn flush(&self) -> Result<()> {
+        for target in &self.targets {
+            target.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// How changes from streamed (in-progress) transactions reach a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamedTransactionMode {
+    /// Forward stream control messages and chunks as they arrive
+    Passthrough,
+    /// Buffer chunks per transaction and release them on Stream Commit
+    Reassemble,
+}
+
+impl StreamedTransactionMode {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "passthrough" => Ok(StreamedTransactionMode::Passthrough),
+            "reassemble" => Ok(StreamedTransactionMode::Reassemble),
+            _ => Err(anyhow!("Unknown streamed transaction mode: {}. Valid options: passthrough, reassemble", s)),
+        }
+    }
+}
+
+/// Output wrapper that reassembles streamed transactions.
+///
+/// Changes between Stream Start and Stream Stop are buffered per top-level
+/// transaction. On Stream Commit they are released to the wrapped target as a
+/// regular Begin / changes / Commit sequence (or Begin Prepare / changes /
+/// Prepare on Stream Prepare); Stream Abort discards the whole transaction or
+/// just the aborted subtransaction's changes.
+pub struct ReassemblingOutput {
+    inner: Arc<dyn OutputTarget>,
+    state: tokio::sync::Mutex<ReassemblyState>,
+}
+
+#[derive(Default)]
+struct ReassemblyState {
+    current_xid: Option<u32>,
+    pending: HashMap<u32, Vec<Change>>,
+}
+
+impl ReassemblingOutput {
+    pub fn new(inner: Arc<dyn OutputTarget>) -> Self {
+        Self {
+            inner,
+            state: tokio::sync::Mutex::new(ReassemblyState::default()),
+        }
+    }
+}
+
+/// Transaction id a change was streamed under, if any
+fn streamed_xid(change: &Change) -> Option<u32> {
+    match change {
+        Change::Insert { xid, .. }
+        | Change::Update { xid, .. }
+        | Change::Delete { xid, .. }
+        | Change::Truncate { xid, .. }
+        | Change::Message { xid, .. } => *xid,
+        _ => None,
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputTarget for ReassemblingOutput {
+    async fn write_change(&self, change: &Change) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        match change {
+            Change::StreamStart { xid, .. } => {
+                state.current_xid = Some(*xid);
+                state.pending.entry(*xid).or_default();
+            }
+            Change::StreamStop => {
+                state.current_xid = None;
+            }
+            // Non-transactional messages are never rolled back, so they are not held
+            Change::Message { transactional: false, .. } => {
+                drop(state);
+                self.inner.write_change(change).await?;
+            }
+            Change::StreamAbort { xid, subxid } => {
+                if xid == subxid {
+                    state.pending.remove(xid);
+                } else if let Some(changes) = state.pending.get_mut(xid) {
+                    changes.retain(|c| streamed_xid(c) != Some(*subxid));
+                }
+            }
+            Change::StreamCommit { xid, lsn, end_lsn, timestamp } => {
+                let changes = state.pending.remove(xid).unwrap_or_default();
+                drop(state);
+
+                self.inner.write_change(&Change::Begin {
+                    final_lsn: *lsn,
+                    timestamp: *timestamp,
+                    xid: *xid,
+                    origin: None,
+                }).await?;
+                for mut buffered in changes {
+                    // The commit timestamp was unknown while the transaction was streamed
+                    if let Change::Insert { transaction: Some(transaction), .. }
+                    | Change::Update { transaction: Some(transaction), .. }
+                    | Change::Delete { transaction: Some(transaction), .. }
+                    | Change::Truncate { transaction: Some(transaction), .. } = &mut buffered
+                    {
+                        transaction.commit_timestamp = Some(*timestamp);
+                    }
+                    self.inner.write_change(&buffered).await?;
+                }
+                self.inner.write_change(&Change::Commit {
+                    flags: 0,
+                    lsn: *lsn,
+                    end_lsn: *end_lsn,
+                    timestamp: *timestamp,
+                    xid: Some(*xid),
+                }).await?;
+            }
+            Change::StreamPrepare { xid, lsn, timestamp, gid } => {
+                let changes = state.pending.remove(xid).unwrap_or_default();
+                drop(state);
+
+                self.inner.write_change(&Change::BeginPrepare {
+                    lsn: *lsn,
+                    timestamp: *timestamp,
+                    xid: *xid,
+                    gid: gid.clone(),
+                }).await?;
+                for buffered in &changes {
+                    self.inner.write_change(buffered).await?;
+                }
+                self.inner.write_change(&Change::Prepare {
+                    lsn: *lsn,
+                    timestamp: *timestamp,
+                    xid: *xid,
+                    gid: gid.clone(),
+                }).await?;
+            }
+            _ => match state.current_xid {
+                Some(xid) => state.pending.entry(xid).or_default().push(change.clone()),
+                None => {
+                    drop(state);
+                    self.inner.write_change(change).await?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await
+    }
+}
+
+/// Output wrapper that groups each transaction into a single record.
+///
+/// Changes between Begin and Commit are buffered and written to the wrapped target
+/// as one `Change::Transaction` at Commit. A transaction with more than
+/// `max_changes` changes is not held in memory: its Begin and buffered changes are
+/// released and the rest of it is passed through one change at a time.
+pub struct TransactionGroupingOutput {
+    inner: Arc<dyn OutputTarget>,
+    max_changes: usize,
+    state: tokio::sync::Mutex<Option<OpenTransaction>>,
+}
+
+/// Transaction between its Begin and Commit
+#[allow(clippy::large_enum_variant)]
+enum OpenTransaction {
+    Buffering { begin: Change, changes: Vec<Change> },
+    /// Too large to group; its changes go straight to the wrapped target
+    Overflowed,
+}
+
+impl TransactionGroupingOutput {
+    pub fn new(inner: Arc<dyn OutputTarget>, max_changes: usize) -> Self {
+        Self {
+            inner,
+            max_changes,
+            state: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputTarget for TransactionGroupingOutput {
+    async fn write_change(&self, change: &Change) -> Result<()> {
+        let mut state = self.state.lock().await;
+
+        match (change, state.take()) {
+            // A transaction still open here never committed on this stream
+            (Change::Begin { .. }, _) => {
+                *state = Some(OpenTransaction::Buffering {
+                    begin: change.clone(),
+                    changes: Vec::new(),
+                });
+            }
+            (
+                Change::Commit { lsn, end_lsn, timestamp, .. },
+                Some(OpenTransaction::Buffering { begin: Change::Begin { xid, origin, .. }, changes }),
+            ) => {
+                drop(state);
+                self.inner.write_change(&Change::Transaction {
+                    xid,
+                    commit_lsn: *lsn,
+                    end_lsn: *end_lsn,
+                    commit_ts: *timestamp,
+                    origin,
+                    changes,
+                }).await?;
+            }
+            (Change::Commit { .. }, _) => {
+                drop(state);
+                self.inner.write_change(change).await?;
+            }
+            // Non-transactional messages are never rolled back, so they are not held
+            (Change::Message { transactional: false, .. }, open) => {
+                *state = open;
+                drop(state);
+                self.inner.write_change(change).await?;
+            }
+            (_, Some(OpenTransaction::Buffering { begin, mut changes })) => {
+                changes.push(change.clone());
+                if changes.len() <= self.max_changes {
+                    *state = Some(OpenTransaction::Buffering { begin, changes });
+                    return Ok(());
+                }
+
+                *state = Some(OpenTransaction::Overflowed);
+                drop(state);
+                self.inner.write_change(&begin).await?;
+                for buffered in &changes {
+                    self.inner.write_change(buffered).await?;
+                }
+            }
+            (_, open) => {
+                *state = open;
+                drop(state);
+                self.inner.write_change(change).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await
+    }
 }
 
 // Kept for backward compatibility (currently unused)
@@ -576,12 +1391,21 @@ pub fn print_change(change: &Change, format: &OutputFormat) -> Result<()> {
             print_text_format(change);
         }
         OutputFormat::Debezium => {
-            if let Some(debezium_event) = convert_to_debezium(change) {
+            if let Some(debezium_event) = convert_to_debezium(change, None) {
                 println!("{}", serde_json::to_string(&debezium_event)?);
+            } else if let Some(transaction_event) = convert_to_debezium_transaction(change) {
+                println!("{}", serde_json::to_string(&transaction_event)?);
+            }
+            for truncate_event in convert_to_debezium_truncate(change) {
+                println!("{}", serde_json::to_string(&truncate_event)?);
+            }
+            if let Some(message_event) = convert_to_debezium_message(change) {
+                println!("{}", serde_json::to_string(&message_event)?);
             }
         }
         OutputFormat::Feldera => {
-            for feldera_event in convert_to_feldera(change) {
+            // No relation metadata is tracked here, so values are emitted as strings
+            for feldera_event in convert_to_feldera(change, &RelationColumns::default())? {
                 println!("{}", serde_json::to_string(&feldera_event)?);
             }
         }
@@ -591,21 +1415,57 @@ pub fn print_change(change: &Change, format: &OutputFormat) -> Result<()> {
 
 fn print_text_format(change: &Change) {
     match change {
-        Change::Begin { lsn, timestamp, xid } => {
-            println!("BEGIN [LSN: {}, XID: {}, Time: {}]", lsn, xid, timestamp);
+        Change::Begin { final_lsn, timestamp, xid, origin } => {
+            match origin {
+                Some(origin) => println!("BEGIN [Final LSN: {}, XID: {}, Time: {}, Origin: {}]", final_lsn, xid, timestamp, origin),
+                None => println!("BEGIN [Final LSN: {}, XID: {}, Time: {}]", final_lsn, xid, timestamp),
+            }
+        }
+        Change::Origin { lsn, name } => {
+            println!("ORIGIN [{}, LSN: {}]", name, lsn);
         }
-        Change::Commit { lsn, timestamp } => {
-            println!("COMMIT [LSN: {}, Time: {}]", lsn, timestamp);
+        Change::Message { transactional, prefix, content, lsn, xid } => {
+            println!(
+                "MESSAGE [Prefix: {}, Transactional: {}, LSN: {}]{}",
+                prefix, transactional, lsn, streamed_xid_label(*xid)
+            );
+            println!("  Content: {}", content);
+        }
+        Change::Commit { lsn, end_lsn, timestamp, xid, .. } => {
+            match xid {
+                Some(xid) => println!("COMMIT [LSN: {}, End LSN: {}, XID: {}, Time: {}]", lsn, end_lsn, xid, timestamp),
+                None => println!("COMMIT [LSN: {}, End LSN: {}, Time: {}]", lsn, end_lsn, timestamp),
+            }
         }
-        Change::Relation { relation_id, schema, table, columns } => {
-            println!("RELATION [{}.{} (ID: {})]", schema, table, relation_id);
+        Change::Relation { relation_id, schema, table, replica_identity, columns } => {
+            println!("RELATION [{}.{} (ID: {}, Replica identity: {:?})]", schema, table, relation_id, replica_identity);
             println!("  Columns:");
             for col in columns {
-                println!("    - {} (type_id: {}, flags: {})", col.name, col.type_id, col.flags);
+                let key = if col.is_key() { " [key]" } else { "" };
+                match col.formatted_type() {
+                    Some(type_name) => println!("    - {}{} (type: {}, type_id: {}, flags: {})", col.name, key, type_name, col.type_id, col.flags),
+                    None => println!("    - {}{} (type_id: {}, flags: {})", col.name, key, col.type_id, col.flags),
+                }
+            }
+        }
+        Change::SchemaChanged { relation_id, schema, table, added, dropped, renamed, retyped } => {
+            println!("SCHEMA CHANGED [{}.{} (ID: {})]", schema, table, relation_id);
+            let type_of = |col: &ColumnInfo| col.formatted_type().unwrap_or_else(|| format!("type_id {}", col.type_id));
+            for col in added {
+                println!("  + {} ({})", col.name, type_of(col));
+            }
+            for col in dropped {
+                println!("  - {} ({})", col.name, type_of(col));
+            }
+            for change in renamed {
+                println!("  ~ {} -> {} (renamed?)", change.before.name, change.after.name);
+            }
+            for change in retyped {
+                println!("  ~ {}: {} -> {}", change.after.name, type_of(&change.before), type_of(&change.after));
             }
         }
-        Change::Insert { relation_id, schema, table, new_tuple } => {
-            println!("INSERT into {}.{} (ID: {})", schema, table, relation_id);
+        Change::Insert { relation_id, schema, table, new_tuple, lsn, xid, .. } => {
+            println!("INSERT into {}.{} (ID: {}){}{}", schema, table, relation_id, lsn_label(lsn), streamed_xid_label(*xid));
             println!("  New values:");
             for (key, value) in new_tuple {
                 match value {
@@ -614,8 +1474,8 @@ fn print_text_format(change: &Change) {
                 }
             }
         }
-        Change::Update { relation_id, schema, table, old_tuple, new_tuple } => {
-            println!("UPDATE {}.{} (ID: {})", schema, table, relation_id);
+        Change::Update { relation_id, schema, table, old_tuple, new_tuple, unchanged_columns, lsn, xid, .. } => {
+            println!("UPDATE {}.{} (ID: {}){}{}", schema, table, relation_id, lsn_label(lsn), streamed_xid_label(*xid));
             if let Some(old) = old_tuple {
                 println!("  Old values:");
                 for (key, value) in old {
@@ -632,9 +1492,12 @@ fn print_text_format(change: &Change) {
                     None => println!("    {}: NULL", key),
                 }
             }
+            for key in unchanged_columns {
+                println!("    {}: (unchanged)", key);
+            }
         }
-        Change::Delete { relation_id, schema, table, old_tuple } => {
-            println!("DELETE from {}.{} (ID: {})", schema, table, relation_id);
+        Change::Delete { relation_id, schema, table, old_tuple, lsn, xid, .. } => {
+            println!("DELETE from {}.{} (ID: {}){}{}", schema, table, relation_id, lsn_label(lsn), streamed_xid_label(*xid));
             println!("  Old values:");
             for (key, value) in old_tuple {
                 match value {
@@ -643,24 +1506,129 @@ fn print_text_format(change: &Change) {
                 }
             }
         }
+        Change::Snapshot { relation_id, schema, table, new_tuple, lsn, incremental, .. } => {
+            println!(
+                "SNAPSHOT of {}.{} (ID: {}){}{}",
+                schema,
+                table,
+                relation_id,
+                lsn_label(&Some(*lsn)),
+                if *incremental { " [Incremental]" } else { "" }
+            );
+            println!("  Values:");
+            for (key, value) in new_tuple {
+                match value {
+                    Some(v) => println!("    {}: {}", key, v),
+                    None => println!("    {}: NULL", key),
+                }
+            }
+        }
+        Change::Truncate { relations, cascade, restart_identity, lsn, xid, .. } => {
+            let tables: Vec<String> = relations
+                .iter()
+                .map(|r| format!("{}.{}", r.schema, r.table))
+                .collect();
+            let mut options = Vec::new();
+            if *cascade {
+                options.push("CASCADE");
+            }
+            if *restart_identity {
+                options.push("RESTART IDENTITY");
+            }
+            let options = if options.is_empty() { String::new() } else { format!(" [{}]", options.join(", ")) };
+            println!("TRUNCATE {}{}{}{}", tables.join(", "), options, lsn_label(lsn), streamed_xid_label(*xid));
+        }
+        Change::StreamStart { xid, first_segment } => {
+            println!("STREAM START [XID: {}, First segment: {}]", xid, first_segment);
+        }
+        Change::StreamStop => {
+            println!("STREAM STOP");
+        }
+        Change::StreamCommit { xid, lsn, end_lsn, timestamp } => {
+            println!("STREAM COMMIT [LSN: {}, End LSN: {}, XID: {}, Time: {}]", lsn, end_lsn, xid, timestamp);
+        }
+        Change::StreamAbort { xid, subxid } => {
+            println!("STREAM ABORT [XID: {}, Subtransaction: {}]", xid, subxid);
+        }
+        Change::BeginPrepare { lsn, timestamp, xid, gid } => {
+            println!("BEGIN PREPARE [LSN: {}, XID: {}, GID: {}, Time: {}]", lsn, xid, gid, timestamp);
+        }
+        Change::Prepare { lsn, timestamp, xid, gid } => {
+            println!("PREPARE [LSN: {}, XID: {}, GID: {}, Time: {}]", lsn, xid, gid, timestamp);
+        }
+        Change::CommitPrepared { lsn, timestamp, xid, gid } => {
+            println!("COMMIT PREPARED [LSN: {}, XID: {}, GID: {}, Time: {}]", lsn, xid, gid, timestamp);
+        }
+        Change::RollbackPrepared { lsn, timestamp, xid, gid, .. } => {
+            println!("ROLLBACK PREPARED [LSN: {}, XID: {}, GID: {}, Time: {}]", lsn, xid, gid, timestamp);
+        }
+        Change::StreamPrepare { lsn, timestamp, xid, gid } => {
+            println!("STREAM PREPARE [LSN: {}, XID: {}, GID: {}, Time: {}]", lsn, xid, gid, timestamp);
+        }
+        Change::Transaction { xid, commit_lsn, end_lsn, commit_ts, origin, changes } => {
+            let origin = origin.as_ref().map(|origin| format!(", Origin: {}", origin)).unwrap_or_default();
+            println!(
+                "TRANSACTION [XID: {}, Commit LSN: {}, End LSN: {}, Time: {}{}, Changes: {}]",
+                xid, commit_lsn, end_lsn, commit_ts, origin, changes.len()
+            );
+            for change in changes {
+                print_text_format(change);
+            }
+            println!("END TRANSACTION [XID: {}]", xid);
+        }
     }
 }
 
+/// Suffix with the WAL position of a data change, when known
+fn lsn_label(lsn: &Option<Lsn>) -> String {
+    lsn.as_ref().map(|lsn| format!(" [LSN: {}]", lsn)).unwrap_or_default()
+}
+
+/// Suffix marking a change that arrived inside a streamed block
+fn streamed_xid_label(xid: Option<u32>) -> String {
+    xid.map(|x| format!(" [streamed, XID: {}]", x)).unwrap_or_default()
+}
+
 /// Public test helper to expose convert_to_debezium for testing
 #[doc(hidden)]
 pub fn convert_to_debezium_test(change: &Change) -> Option<DebeziumEnvelope> {
-    convert_to_debezium(change)
+    convert_to_debezium(change, None)
+}
+
+/// Public test helper to expose convert_to_debezium with typed rows for testing
+#[doc(hidden)]
+pub fn convert_to_debezium_typed_test(change: &Change, columns: &[ColumnInfo]) -> Option<DebeziumEnvelope> {
+    convert_to_debezium(change, Some(columns))
+}
+
+/// Public test helper to expose convert_to_debezium_transaction for testing
+#[doc(hidden)]
+pub fn convert_to_debezium_transaction_test(change: &Change) -> Option<DebeziumTransactionEvent> {
+    convert_to_debezium_transaction(change)
+}
+
+/// Public test helper to expose convert_to_debezium_truncate for testing
+#[doc(hidden)]
+pub fn convert_to_debezium_truncate_test(change: &Change) -> Vec<DebeziumEnvelope> {
+    convert_to_debezium_truncate(change)
+}
+
+/// Public test helper to expose convert_to_debezium_message for testing
+#[doc(hidden)]
+pub fn convert_to_debezium_message_test(change: &Change) -> Option<DebeziumMessageEvent> {
+    convert_to_debezium_message(change)
 }
 
 /// Public test helper to expose convert_to_feldera for testing
 #[doc(hidden)]
-pub fn convert_to_feldera_test(change: &Change) -> Vec<FelderaUpdate> {
-    convert_to_feldera(change)
+pub fn convert_to_feldera_test(change: &Change) -> Result<Vec<FelderaUpdate>> {
+    convert_to_feldera(change, &RelationColumns::default())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decoder::ReplicaIdentity;
 
     /// Tests qualify_table_name() basic functionality
     #[test]
@@ -720,14 +1688,18 @@ mod tests {
             None,
         ).await.unwrap();
 
-        let mut tuple = HashMap::new();
-        tuple.insert("id".to_string(), Some("1".to_string()));
+        let mut tuple = Tuple::new();
+        tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
 
         let change = Change::Insert {
             relation_id: 16384,
             schema: "public".to_string(),
             table: "users".to_string(),
             new_tuple: tuple,
+            key_columns: Vec::new(),
+            lsn: None,
+            transaction: None,
+            xid: None,
         };
 
         // Should succeed without error
@@ -747,14 +1719,18 @@ mod tests {
             None,
         ).await.unwrap();
 
-        let mut tuple = HashMap::new();
-        tuple.insert("id".to_string(), Some("1".to_string()));
+        let mut tuple = Tuple::new();
+        tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
 
         let change = Change::Insert {
             relation_id: 16384,
             schema: "public".to_string(),
             table: "orders".to_string(), // Not in allowed list
             new_tuple: tuple,
+            key_columns: Vec::new(),
+            lsn: None,
+            transaction: None,
+            xid: None,
         };
 
         // Should succeed by skipping the table
@@ -772,18 +1748,71 @@ mod tests {
             None,
         ).await.unwrap();
 
-        let mut tuple = HashMap::new();
-        tuple.insert("id".to_string(), Some("1".to_string()));
+        let mut tuple = Tuple::new();
+        tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
 
         let change = Change::Insert {
             relation_id: 16384,
             schema: "analytics".to_string(),
             table: "events".to_string(),
             new_tuple: tuple,
+            key_columns: Vec::new(),
+            lsn: None,
+            transaction: None,
+            xid: None,
         };
 
         // Should attempt to process (will fail at HTTP send)
         let result = output.write_change(&change).await;
         assert!(result.is_err()); // Will fail at HTTP send, not at filtering
     }
+
+    /// Tests subject_token() with message prefixes that are not valid subject tokens
+    #[test]
+    fn test_subject_token() {
+        assert_eq!(subject_token("audit"), "audit");
+        assert_eq!(subject_token("billing.invoices"), "billing.invoices");
+        assert_eq!(subject_token("domain events*"), "domain_events_");
+        assert_eq!(subject_token(""), "_");
+    }
+
+    /// Tests that Feldera conversion types values with the columns of observed Relation events
+    #[test]
+    fn test_convert_to_feldera_uses_observed_relation() {
+        let relations = RelationColumns::default();
+        let mut new_tuple = Tuple::new();
+        new_tuple.insert("id".to_string(), Some(PgValue::Text("42".to_string())));
+        let insert = Change::Insert {
+            relation_id: 7,
+            schema: "public".to_string(),
+            table: "users".to_string(),
+            new_tuple,
+            key_columns: Vec::new(),
+            lsn: None,
+            transaction: None,
+            xid: None,
+        };
+
+        let events = convert_to_feldera(&insert, &relations).unwrap();
+        assert_eq!(events[0].insert.as_ref().unwrap()["id"], "42");
+
+        relations.observe(&Change::Relation {
+            relation_id: 7,
+            schema: "public".to_string(),
+            table: "users".to_string(),
+            replica_identity: ReplicaIdentity::Default,
+            columns: vec![ColumnInfo {
+                name: "id".to_string(),
+                type_id: 23,
+                flags: 1,
+                type_modifier: -1,
+                type_name: None,
+                length: None,
+                precision: None,
+                scale: None,
+            }],
+        });
+        let events = convert_to_feldera(&insert, &relations).unwrap();
+        assert_eq!(events[0].insert.as_ref().unwrap()["id"], 42);
+    }
 }
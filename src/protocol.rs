@@ -0,0 +1,386 @@
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use postgres_protocol::authentication::md5_hash;
+use postgres_protocol::authentication::sasl::{ChannelBinding, ScramSha256, SCRAM_SHA_256};
+use postgres_protocol::message::frontend;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_postgres::config::Host;
+use tokio_postgres::Config;
+
+use crate::lsn::Lsn;
+
+/// Byte stream the replication connection runs over (TCP or Unix socket)
+trait Socket: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Socket for T {}
+
+/// A message received from the walsender inside the CopyBoth stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplicationMessage {
+    /// XLogData ('w'): a chunk of WAL, here a single pgoutput message
+    XLogData {
+        wal_start: Lsn,
+        wal_end: Lsn,
+        server_clock: i64,
+        data: Bytes,
+    },
+    /// Primary keepalive ('k'): server WAL position and whether a reply is expected
+    PrimaryKeepalive {
+        wal_end: Lsn,
+        server_clock: i64,
+        reply_requested: bool,
+    },
+}
+
+/// Microseconds between the Unix epoch and the PostgreSQL epoch (2000-01-01)
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+/// Current time in the protocol's clock format (microseconds since 2000-01-01)
+pub fn current_pg_timestamp() -> i64 {
+    chrono::Utc::now().timestamp_micros() - PG_EPOCH_OFFSET_MICROS
+}
+
+/// Encode a Standby Status Update ('r') reporting the client's WAL positions
+pub fn encode_standby_status_update(
+    write_lsn: Lsn,
+    flush_lsn: Lsn,
+    apply_lsn: Lsn,
+    client_clock: i64,
+    reply_requested: bool,
+) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(34);
+    buf.put_u8(b'r');
+    buf.put_u64(write_lsn.0);
+    buf.put_u64(flush_lsn.0);
+    buf.put_u64(apply_lsn.0);
+    buf.put_i64(client_clock);
+    buf.put_u8(reply_requested as u8);
+    buf
+}
+
+/// Parse the payload of a CopyData message sent by the walsender
+pub fn parse_replication_message(data: &[u8]) -> Result<ReplicationMessage> {
+    let mut buf = data;
+    if buf.is_empty() {
+        return Err(anyhow!("Empty replication message"));
+    }
+
+    match buf.get_u8() {
+        b'w' => {
+            if buf.len() < 24 {
+                return Err(anyhow!("Invalid XLogData message length"));
+            }
+            let wal_start = Lsn(buf.get_u64());
+            let wal_end = Lsn(buf.get_u64());
+            let server_clock = buf.get_i64();
+            Ok(ReplicationMessage::XLogData {
+                wal_start,
+                wal_end,
+                server_clock,
+                data: Bytes::copy_from_slice(buf),
+            })
+        }
+        b'k' => {
+            if buf.len() < 17 {
+                return Err(anyhow!("Invalid primary keepalive message length"));
+            }
+            let wal_end = Lsn(buf.get_u64());
+            let server_clock = buf.get_i64();
+            let reply_requested = buf.get_u8() == 1;
+            Ok(ReplicationMessage::PrimaryKeepalive {
+                wal_end,
+                server_clock,
+                reply_requested,
+            })
+        }
+        other => Err(anyhow!("Unknown replication message type: {}", other as char)),
+    }
+}
+
+/// Split one complete backend message (type byte, i32 length, body) off the front of
+/// `buf`; `None` while more bytes are needed
+pub fn split_message(buf: &mut BytesMut) -> Result<Option<(u8, Bytes)>> {
+    if buf.len() < 5 {
+        return Ok(None);
+    }
+    let len = i32::from_be_bytes(buf[1..5].try_into()?);
+    // The length counts itself, so anything below 4 (or negative) is malformed
+    if len < 4 {
+        return Err(anyhow!("Invalid message length from server: {}", len));
+    }
+    let len = len as usize;
+    if buf.len() <= len {
+        return Ok(None);
+    }
+    let tag = buf.get_u8();
+    buf.advance(4);
+    Ok(Some((tag, buf.split_to(len - 4).freeze())))
+}
+
+/// Values of a DataRow ('D') message as text; `None` for NULL
+pub fn parse_data_row(mut body: Bytes) -> Result<Vec<Option<String>>> {
+    let count = body.try_get_i16()?;
+    let mut row = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let len = body.try_get_i32()?;
+        if len < 0 {
+            row.push(None);
+            continue;
+        }
+        if len as usize > body.remaining() {
+            return Err(anyhow!(
+                "DataRow value of {} bytes exceeds the {} bytes left in the message",
+                len,
+                body.remaining()
+            ));
+        }
+        let value = body.split_to(len as usize);
+        row.push(Some(String::from_utf8_lossy(&value).to_string()));
+    }
+    Ok(row)
+}
+
+/// Minimal client for the PostgreSQL streaming replication protocol.
+///
+/// tokio-postgres cannot open `replication=database` connections or enter
+/// CopyBoth mode, so this speaks just enough of the wire protocol to
+/// authenticate, run replication commands and exchange CopyData frames.
+pub struct ReplicationConnection {
+    socket: Box<dyn Socket>,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+}
+
+impl ReplicationConnection {
+    /// Open a `replication=database` connection using the settings of a regular connection string
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let host = config
+            .get_hosts()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Host::Tcp("localhost".to_string()));
+        let port = config.get_ports().first().copied().unwrap_or(5432);
+
+        let socket: Box<dyn Socket> = match host {
+            Host::Tcp(ref name) => Box::new(TcpStream::connect((name.as_str(), port)).await?),
+            #[cfg(unix)]
+            Host::Unix(ref dir) => {
+                let path = dir.join(format!(".s.PGSQL.{}", port));
+                Box::new(tokio::net::UnixStream::connect(path).await?)
+            }
+        };
+
+        let mut conn = Self {
+            socket,
+            read_buf: BytesMut::with_capacity(8192),
+            write_buf: BytesMut::new(),
+        };
+        conn.startup(config).await?;
+        Ok(conn)
+    }
+
+    async fn startup(&mut self, config: &Config) -> Result<()> {
+        let user = config
+            .get_user()
+            .ok_or_else(|| anyhow!("Connection string must specify a user"))?;
+        let dbname = config.get_dbname().unwrap_or(user);
+
+        let mut params = vec![
+            ("user", user),
+            ("database", dbname),
+            ("replication", "database"),
+            ("client_encoding", "UTF8"),
+        ];
+        if let Some(application_name) = config.get_application_name() {
+            params.push(("application_name", application_name));
+        }
+
+        frontend::startup_message(params, &mut self.write_buf)?;
+        self.flush().await?;
+
+        loop {
+            let (tag, mut body) = self.read_message().await?;
+            match tag {
+                b'R' => self.authenticate(config, user, &mut body).await?,
+                b'Z' => return Ok(()),
+                b'E' => return Err(anyhow!("Replication connection failed: {}", error_message(&body))),
+                // ParameterStatus, BackendKeyData, NoticeResponse
+                _ => {}
+            }
+        }
+    }
+
+    async fn authenticate(&mut self, config: &Config, user: &str, body: &mut Bytes) -> Result<()> {
+        let password = || {
+            config
+                .get_password()
+                .ok_or_else(|| anyhow!("Server requested a password but none was provided"))
+        };
+
+        match body.try_get_i32()? {
+            // AuthenticationOk
+            0 => Ok(()),
+            // AuthenticationCleartextPassword
+            3 => {
+                frontend::password_message(password()?, &mut self.write_buf)?;
+                self.flush().await
+            }
+            // AuthenticationMD5Password
+            5 => {
+                let mut salt = [0u8; 4];
+                body.try_copy_to_slice(&mut salt)?;
+                let hash = md5_hash(user.as_bytes(), password()?, salt);
+                frontend::password_message(hash.as_bytes(), &mut self.write_buf)?;
+                self.flush().await
+            }
+            // AuthenticationSASL
+            10 => {
+                let mechanisms = read_cstrings(body);
+                if !mechanisms.iter().any(|m| m == SCRAM_SHA_256) {
+                    return Err(anyhow!("Unsupported SASL mechanisms: {}", mechanisms.join(", ")));
+                }
+                let mut scram = ScramSha256::new(password()?, ChannelBinding::unsupported());
+                frontend::sasl_initial_response(SCRAM_SHA_256, scram.message(), &mut self.write_buf)?;
+                self.flush().await?;
+
+                let (_, mut body) = self.expect_auth().await?;
+                if body.try_get_i32()? != 11 {
+                    return Err(anyhow!("Expected AuthenticationSASLContinue"));
+                }
+                scram.update(&body)?;
+                frontend::sasl_response(scram.message(), &mut self.write_buf)?;
+                self.flush().await?;
+
+                let (_, mut body) = self.expect_auth().await?;
+                if body.try_get_i32()? != 12 {
+                    return Err(anyhow!("Expected AuthenticationSASLFinal"));
+                }
+                scram.finish(&body)?;
+                Ok(())
+            }
+            other => Err(anyhow!("Unsupported authentication method: {}", other)),
+        }
+    }
+
+    async fn expect_auth(&mut self) -> Result<(u8, Bytes)> {
+        let (tag, body) = self.read_message().await?;
+        match tag {
+            b'R' => Ok((tag, body)),
+            b'E' => Err(anyhow!("Authentication failed: {}", error_message(&body))),
+            other => Err(anyhow!("Unexpected message during authentication: {}", other as char)),
+        }
+    }
+
+    /// Run a replication command or SQL query and return its rows as text values
+    pub async fn simple_query(&mut self, query: &str) -> Result<Vec<Vec<Option<String>>>> {
+        frontend::query(query, &mut self.write_buf)?;
+        self.flush().await?;
+
+        let mut rows = Vec::new();
+        let mut error = None;
+        loop {
+            let (tag, body) = self.read_message().await?;
+            match tag {
+                b'D' => rows.push(parse_data_row(body)?),
+                b'E' => error = Some(error_message(&body)),
+                b'Z' => break,
+                // RowDescription, CommandComplete, NoticeResponse, ...
+                _ => {}
+            }
+        }
+
+        match error {
+            Some(message) => Err(anyhow!("{}", message)),
+            None => Ok(rows),
+        }
+    }
+
+    /// Issue START_REPLICATION and switch the connection into CopyBoth mode
+    pub async fn start_replication(&mut self, command: &str) -> Result<()> {
+        frontend::query(command, &mut self.write_buf)?;
+        self.flush().await?;
+
+        loop {
+            let (tag, body) = self.read_message().await?;
+            match tag {
+                // CopyBothResponse
+                b'W' => return Ok(()),
+                b'E' => {
+                    let message = error_message(&body);
+                    // Drain up to ReadyForQuery so the error is reported cleanly
+                    while self.read_message().await?.0 != b'Z' {}
+                    return Err(anyhow!("START_REPLICATION failed: {}", message));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Receive the next walsender message; `None` once the server ends the copy stream
+    pub async fn recv(&mut self) -> Result<Option<ReplicationMessage>> {
+        loop {
+            let (tag, body) = self.read_message().await?;
+            match tag {
+                b'd' => return parse_replication_message(&body).map(Some),
+                b'c' => return Ok(None),
+                b'E' => return Err(anyhow!("Replication stream error: {}", error_message(&body))),
+                _ => {}
+            }
+        }
+    }
+
+    /// Send a CopyData message to the walsender
+    pub async fn send_copy_data(&mut self, data: &[u8]) -> Result<()> {
+        self.write_buf.put_u8(b'd');
+        self.write_buf.put_i32(data.len() as i32 + 4);
+        self.write_buf.put_slice(data);
+        self.flush().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.socket.write_all(&self.write_buf).await?;
+        self.socket.flush().await?;
+        self.write_buf.clear();
+        Ok(())
+    }
+
+    /// Read one backend message. Partial frames stay in `read_buf`, so this is
+    /// safe to cancel (e.g. from `tokio::select!`).
+    async fn read_message(&mut self) -> Result<(u8, Bytes)> {
+        loop {
+            if let Some(message) = split_message(&mut self.read_buf)? {
+                return Ok(message);
+            }
+
+            if self.socket.read_buf(&mut self.read_buf).await? == 0 {
+                return Err(anyhow!("Replication connection closed by server"));
+            }
+        }
+    }
+}
+
+/// Extract the human-readable message from an ErrorResponse body
+fn error_message(body: &[u8]) -> String {
+    let mut severity = None;
+    let mut message = None;
+    for field in body.split(|b| *b == 0).filter(|f| !f.is_empty()) {
+        let value = String::from_utf8_lossy(&field[1..]).to_string();
+        match field[0] {
+            b'S' => severity = Some(value),
+            b'M' => message = Some(value),
+            _ => {}
+        }
+    }
+    match (severity, message) {
+        (Some(severity), Some(message)) => format!("{}: {}", severity, message),
+        (None, Some(message)) => message,
+        _ => "unknown server error".to_string(),
+    }
+}
+
+fn read_cstrings(body: &[u8]) -> Vec<String> {
+    body.split(|b| *b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).to_string())
+        .collect()
+}
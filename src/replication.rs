@@ -1,17 +1,142 @@
-use anyhow::Result;
-use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
+use anyhow::{anyhow, Result};
+use postgres_protocol::escape::{escape_identifier, escape_literal};
+use tokio_postgres::{Client, NoTls};
 use std::time::Duration;
+use tokio::time::Instant;
 use std::collections::VecDeque;
 
-use crate::decoder::{decode_pgoutput_message, Change};
+use crate::admin;
+use crate::decoder::{Change, Decoder};
+use crate::incremental::{IncrementalSnapshot, IncrementalSnapshotOptions};
+use crate::lsn::Lsn;
+use crate::protocol::{
+    current_pg_timestamp, encode_standby_status_update, ReplicationConnection, ReplicationMessage,
+};
+use crate::snapshot::{SnapshotOptions, SnapshotState, TableSnapshot};
+
+pub use crate::admin::SlotStatus;
+
+/// How changes are read from the replication slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicationMode {
+    /// START_REPLICATION over a `replication=database` connection (CopyBoth)
+    Streaming,
+    /// Poll `pg_logical_slot_get_binary_changes` over a regular connection
+    Polling,
+}
+
+impl ReplicationMode {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "streaming" | "stream" => Ok(ReplicationMode::Streaming),
+            "polling" | "poll" => Ok(ReplicationMode::Polling),
+            _ => Err(anyhow!("Unknown replication mode: {}. Valid options: streaming, polling", s)),
+        }
+    }
+}
+
+/// When a transaction's position is confirmed back to PostgreSQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Confirm as soon as changes are received; a failed sink loses them
+    AtMostOnce,
+    /// Confirm only after every output target has durably accepted the transaction
+    AtLeastOnce,
+}
+
+impl DeliveryMode {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "at-most-once" | "at_most_once" => Ok(DeliveryMode::AtMostOnce),
+            "at-least-once" | "at_least_once" => Ok(DeliveryMode::AtLeastOnce),
+            _ => Err(anyhow!("Unknown delivery mode: {}. Valid options: at-least-once, at-most-once", s)),
+        }
+    }
+}
+
+/// Settings for opening a replication stream
+#[derive(Debug, Clone)]
+pub struct ReplicationOptions {
+    /// Create the replication slot if it doesn't exist
+    pub create_slot: bool,
+    /// Create the slot with an exported snapshot and copy the published tables under
+    /// it before streaming; the slot must not exist yet unless an earlier snapshot of
+    /// it is being resumed
+    pub snapshot: Option<SnapshotOptions>,
+    /// Watch a signal table for requests to snapshot single tables while streaming
+    pub incremental_snapshot: Option<IncrementalSnapshotOptions>,
+    /// Position to resume from
+    pub start_lsn: Option<Lsn>,
+    pub mode: ReplicationMode,
+    pub delivery: DeliveryMode,
+    /// How often a Standby Status Update is sent in streaming mode
+    pub status_interval: Duration,
+    /// pgoutput protocol version to request
+    pub protocol_version: u32,
+    /// Stream large in-progress transactions (requires protocol version 2+)
+    pub streaming: bool,
+    /// Decode prepared transactions at PREPARE time (requires protocol version 3+)
+    pub two_phase: bool,
+    /// Ask pgoutput to send column values in binary format
+    pub binary: bool,
+    /// Request logical decoding messages (pg_logical_emit_message)
+    pub messages: bool,
+    /// Only pass on messages with one of these prefixes (all messages when empty)
+    pub message_prefixes: Vec<String>,
+}
+
+impl Default for ReplicationOptions {
+    fn default() -> Self {
+        Self {
+            create_slot: false,
+            snapshot: None,
+            incremental_snapshot: None,
+            start_lsn: None,
+            mode: ReplicationMode::Streaming,
+            delivery: DeliveryMode::AtLeastOnce,
+            status_interval: Duration::from_secs(10),
+            protocol_version: 1,
+            streaming: false,
+            two_phase: false,
+            binary: false,
+            messages: false,
+            message_prefixes: Vec::new(),
+        }
+    }
+}
 
 pub struct ReplicationStream {
     client: Client,
     slot_name: String,
-    publication_name: String,
-    change_buffer: VecDeque<Change>,
-    last_received_lsn: Option<String>,
-    last_processed_lsn: Option<String>,
+    mode: ReplicationMode,
+    delivery: DeliveryMode,
+    replication_conn: Option<ReplicationConnection>,
+    plugin_options: Vec<(&'static str, String)>,
+    /// Relations and types announced on this stream
+    decoder: Decoder,
+    in_stream_block: bool,
+    /// BEGIN held back until the next message shows whether an ORIGIN follows it
+    pending_begin: Option<Change>,
+    change_buffer: VecDeque<(Lsn, Change)>,
+    last_received_lsn: Option<Lsn>,
+    last_processed_lsn: Option<Lsn>,
+    last_flushed_lsn: Option<Lsn>,
+    in_transaction: bool,
+    /// Changes were handed out since the last `confirm_commit`, so the targets may
+    /// not hold them yet
+    unconfirmed: bool,
+    status_interval: Duration,
+    next_status_update: Instant,
+    message_prefixes: Vec<String>,
+    /// Initial copy of the published tables, read before any streamed change and
+    /// kept until its last chunks are confirmed
+    snapshot: Option<TableSnapshot>,
+    /// START_REPLICATION held back until the snapshot has been read
+    pending_start: Option<String>,
+    /// Incremental snapshots requested through the signal table
+    incremental: Option<IncrementalSnapshot>,
 }
 
 impl ReplicationStream {
@@ -19,9 +144,30 @@ impl ReplicationStream {
         connection_string: &str,
         slot_name: &str,
         publication_name: &str,
-        create_slot: bool,
-        _start_lsn: Option<String>,
+        options: ReplicationOptions,
     ) -> Result<Self> {
+        let plugin_options = build_plugin_options(publication_name, &options)?;
+        let ReplicationOptions {
+            create_slot,
+            snapshot,
+            incremental_snapshot,
+            start_lsn,
+            mode,
+            delivery,
+            status_interval,
+            two_phase,
+            message_prefixes,
+            ..
+        } = options;
+
+        if snapshot.is_some() && start_lsn.is_some() {
+            return Err(anyhow!("A snapshot starts at the new slot's consistent point; it cannot be combined with a start LSN"));
+        }
+        let incremental = incremental_snapshot
+            .as_ref()
+            .map(|options| IncrementalSnapshot::new(options, publication_name))
+            .transpose()?;
+
         // Parse connection string
         let config = connection_string.parse::<tokio_postgres::Config>()?;
 
@@ -34,13 +180,62 @@ impl ReplicationStream {
             }
         });
 
-        // Create replication slot if requested
-        if create_slot {
-            match Self::create_replication_slot(&client, slot_name).await {
+        // A new snapshot needs a slot created over the replication protocol, which exports
+        // the snapshot the slot's changes start from; an interrupted one is resumed from
+        // its state file
+        let mut table_snapshot = None;
+        let mut snapshot_conn = None;
+        if let Some(snapshot_options) = &snapshot {
+            let state = match &snapshot_options.state_path {
+                Some(path) => SnapshotState::load(path)?,
+                None => None,
+            };
+            match state {
+                Some(state) if state.slot != slot_name || state.publication != publication_name => {
+                    return Err(anyhow!(
+                        "Snapshot state file belongs to slot '{}' and publication '{}'",
+                        state.slot,
+                        state.publication
+                    ));
+                }
+                Some(state) if state.complete => {
+                    eprintln!("Snapshot of slot '{}' is already complete, continuing with streaming", slot_name);
+                }
+                Some(state) => {
+                    table_snapshot = Some(TableSnapshot::resume(&config, snapshot_options, state).await?);
+                }
+                None => {
+                    let mut conn = ReplicationConnection::connect(&config).await?;
+                    let rows = conn
+                        .simple_query(&build_create_slot_command(slot_name, two_phase))
+                        .await
+                        .map_err(|e| anyhow!("Failed to create replication slot '{}' for the snapshot: {}", slot_name, e))?;
+                    let (consistent_point, snapshot_name) = match rows.first().map(Vec::as_slice) {
+                        Some([_, Some(point), Some(name), ..]) => (point.parse::<Lsn>()?, name.clone()),
+                        _ => return Err(anyhow!("CREATE_REPLICATION_SLOT did not export a snapshot")),
+                    };
+                    eprintln!("Created replication slot: {} (consistent point {})", slot_name, consistent_point);
+
+                    table_snapshot = Some(
+                        TableSnapshot::begin(
+                            &config,
+                            snapshot_options,
+                            slot_name,
+                            publication_name,
+                            &snapshot_name,
+                            consistent_point,
+                        )
+                        .await?,
+                    );
+                    snapshot_conn = Some(conn);
+                }
+            }
+        } else if create_slot {
+            match admin::create_slot(&client, slot_name, two_phase).await {
                 Ok(_) => eprintln!("Created replication slot: {}", slot_name),
                 Err(e) => {
                     let err_msg = e.to_string().to_lowercase();
-                    if err_msg.contains("already exists") || err_msg.contains("exist") {
+                    if err_msg.contains("already exists") {
                         eprintln!("Replication slot '{}' already exists, continuing...", slot_name);
                     } else {
                         return Err(e);
@@ -49,47 +244,221 @@ impl ReplicationStream {
             }
         }
 
-        eprintln!("Starting replication stream...\n");
+        // Refuse to resume from a position the slot has already moved past
+        if let Some(lsn) = start_lsn {
+            let status = admin::slot_status(&client, slot_name).await?;
+            let confirmed = status.confirmed_flush_lsn;
+            if lsn < confirmed {
+                return Err(anyhow!(
+                    "Start LSN {} is behind the slot's confirmed_flush_lsn {}; changes before it are no longer available",
+                    lsn,
+                    confirmed
+                ));
+            }
+            eprintln!("Resuming from LSN {}", lsn);
+
+            // The walsender applies the start position itself; the SQL functions
+            // don't, so move the slot forward before polling
+            if mode == ReplicationMode::Polling && lsn > confirmed {
+                admin::advance_slot(&client, slot_name, lsn).await?;
+            }
+        }
+
+        // Open the walsender connection and enter CopyBoth mode. After a snapshot the
+        // walsender would expect replies during the copy, so streaming waits for it.
+        let start_lsn = start_lsn.or(table_snapshot.as_ref().map(TableSnapshot::consistent_point));
+        let mut pending_start = None;
+        let replication_conn = match mode {
+            ReplicationMode::Streaming => {
+                let mut conn = match snapshot_conn {
+                    Some(conn) => conn,
+                    None => ReplicationConnection::connect(&config).await?,
+                };
+                let command = build_start_replication_command(
+                    slot_name,
+                    start_lsn.unwrap_or(Lsn::ZERO),
+                    &plugin_options,
+                );
+                if table_snapshot.is_some() {
+                    pending_start = Some(command);
+                } else {
+                    conn.start_replication(&command).await?;
+                }
+                Some(conn)
+            }
+            ReplicationMode::Polling => None,
+        };
+
+        eprintln!("Starting replication stream ({:?} mode, {:?} delivery)...\n", mode, delivery);
 
         Ok(Self {
             client,
             slot_name: slot_name.to_string(),
-            publication_name: publication_name.to_string(),
+            mode,
+            delivery,
+            replication_conn,
+            plugin_options,
+            decoder: Decoder::new(),
+            in_stream_block: false,
+            pending_begin: None,
             change_buffer: VecDeque::new(),
             last_received_lsn: None,
             last_processed_lsn: None,
+            last_flushed_lsn: None,
+            in_transaction: false,
+            unconfirmed: false,
+            status_interval,
+            next_status_update: Instant::now() + status_interval,
+            message_prefixes,
+            snapshot: table_snapshot,
+            pending_start,
+            incremental,
         })
     }
 
-    async fn create_replication_slot(client: &Client, slot_name: &str) -> Result<()> {
-        // Use SQL function instead of replication protocol command
-        let query = format!(
-            "SELECT pg_create_logical_replication_slot('{}', 'pgoutput')",
-            slot_name
-        );
-        
-        let rows = client.simple_query(&query).await?;
-        
-        for row in rows {
-            if let SimpleQueryMessage::Row(row) = row {
-                eprintln!("Slot created: {:?}", row);
+    pub async fn next_message(&mut self) -> Result<Option<Change>> {
+        loop {
+            // Changes released by an incremental snapshot come first
+            if let Some(change) = self.incremental.as_mut().and_then(IncrementalSnapshot::pop_ready) {
+                return Ok(Some(change));
+            }
+            match (self.next_change().await?, self.incremental.as_mut()) {
+                (Some(change), Some(incremental)) => incremental.handle(&self.client, change).await?,
+                (change, _) => return Ok(change),
             }
         }
-        
-        Ok(())
     }
 
-    pub async fn next_message(&mut self) -> Result<Option<Change>> {
+    /// Next change of the initial snapshot, or from the slot once it has been read
+    async fn next_change(&mut self) -> Result<Option<Change>> {
+        if let Some(snapshot) = self.snapshot.as_mut().filter(|s| !s.is_finished()) {
+            if let Some(change) = snapshot.next_change().await? {
+                return Ok(Some(change));
+            }
+            if let Some(command) = self.pending_start.take() {
+                let conn = self
+                    .replication_conn
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("Replication connection is not open"))?;
+                conn.start_replication(&command).await?;
+                self.next_status_update = Instant::now() + self.status_interval;
+            }
+            // Hand back control so the finished snapshot is checkpointed right away,
+            // not only once the slot delivers a change
+            if self.snapshot_checkpoint_due() {
+                return Ok(None);
+            }
+        }
+
         // If we have buffered changes, return the next one
-        if let Some(change) = self.change_buffer.pop_front() {
+        if let Some(change) = self.pop_buffered() {
             return Ok(Some(change));
         }
 
+        match self.mode {
+            ReplicationMode::Streaming => self.next_streamed_message().await,
+            ReplicationMode::Polling => self.next_polled_message().await,
+        }
+    }
+
+    /// Read CopyData frames from the walsender until one decodes to a change.
+    /// Standby status updates are sent every `status_interval` and whenever a
+    /// keepalive asks for a reply, so the server neither times out the connection
+    /// nor holds on to WAL we have already dealt with.
+    async fn next_streamed_message(&mut self) -> Result<Option<Change>> {
+        loop {
+            let conn = self
+                .replication_conn
+                .as_mut()
+                .ok_or_else(|| anyhow!("Replication connection is not open"))?;
+
+            // recv() keeps partial frames buffered, so timing it out is safe
+            let message = match tokio::time::timeout_at(self.next_status_update, conn.recv()).await {
+                Ok(message) => message?,
+                Err(_) => {
+                    self.send_status_update().await?;
+                    continue;
+                }
+            };
+
+            match message {
+                Some(ReplicationMessage::XLogData { wal_start, data, .. }) => {
+                    for mut change in self.decode(&data)? {
+                        change.set_lsn(wal_start);
+                        self.change_buffer.push_back((wal_start, change));
+                    }
+                    if let Some(change) = self.pop_buffered() {
+                        return Ok(Some(change));
+                    }
+                }
+                Some(ReplicationMessage::PrimaryKeepalive { wal_end, reply_requested, .. }) => {
+                    // A non-transactional message handed out but not yet confirmed may
+                    // still be sitting in a target's buffer below `wal_end`
+                    let idle = !self.in_transaction
+                        && self.pending_begin.is_none()
+                        && self.change_buffer.is_empty()
+                        && !self.unconfirmed;
+                    if idle && wal_end > self.last_flushed_lsn.unwrap_or(Lsn::ZERO) {
+                        // Nothing is outstanding, so WAL up to the server's position holds
+                        // no changes for us and the slot may move past it
+                        self.last_received_lsn = Some(wal_end);
+                        self.last_flushed_lsn = Some(wal_end);
+                    }
+                    if reply_requested {
+                        self.send_status_update().await?;
+                    }
+                    // Keep-alive, no data
+                    return Ok(None);
+                }
+                None => return Err(anyhow!("Replication stream closed by server")),
+            }
+        }
+    }
+
+    /// Report received (write), durably delivered (flush) and processed (apply)
+    /// positions to the walsender
+    async fn send_status_update(&mut self) -> Result<()> {
+        let write_lsn = self.last_received_lsn.unwrap_or(Lsn::ZERO);
+        let flush_lsn = self.last_flushed_lsn.unwrap_or(Lsn::ZERO);
+        let apply_lsn = self.last_processed_lsn.unwrap_or(Lsn::ZERO);
+
+        let conn = self
+            .replication_conn
+            .as_mut()
+            .ok_or_else(|| anyhow!("Replication connection is not open"))?;
+        let status = encode_standby_status_update(
+            write_lsn,
+            flush_lsn,
+            apply_lsn,
+            current_pg_timestamp(),
+            false,
+        );
+        conn.send_copy_data(&status).await?;
+
+        self.next_status_update = Instant::now() + self.status_interval;
+        Ok(())
+    }
+
+    async fn next_polled_message(&mut self) -> Result<Option<Change>> {
+        // Peeking leaves the changes in the slot until confirm_commit() advances it
+        let function = match self.delivery {
+            DeliveryMode::AtMostOnce => "pg_logical_slot_get_binary_changes",
+            DeliveryMode::AtLeastOnce => "pg_logical_slot_peek_binary_changes",
+        };
+
+        let plugin_options: String = self
+            .plugin_options
+            .iter()
+            .map(|(name, value)| format!(", '{}', {}", name, escape_literal(value)))
+            .collect();
+
         // Poll for changes and buffer them
         loop {
             let query = format!(
-                "SELECT lsn, xid, data FROM pg_logical_slot_get_binary_changes('{}', NULL, NULL, 'proto_version', '1', 'publication_names', '{}')",
-                self.slot_name, self.publication_name
+                "SELECT lsn::text, xid, data FROM {}({}, NULL, NULL{})",
+                function,
+                escape_literal(&self.slot_name),
+                plugin_options
             );
 
             let rows = self.client.query(&query, &[]).await?;
@@ -102,67 +471,257 @@ impl ReplicationStream {
 
             // Process all rows and buffer the changes
             for row in rows {
-                let lsn: String = row.get(0);
+                let lsn: Lsn = row.get::<_, &str>(0).parse()?;
                 let data: Vec<u8> = row.get(2);
                 
-                // Update last received LSN
-                self.last_received_lsn = Some(lsn.clone());
-                
-                // Decode the pgoutput message
-                if let Some(change) = decode_pgoutput_message(&data)? {
-                    self.change_buffer.push_back(change);
+                // Decode the pgoutput message, remembering where it came from
+                for mut change in self.decode(&data)? {
+                    change.set_lsn(lsn);
+                    self.change_buffer.push_back((lsn, change));
                 }
             }
 
             // Return the first buffered change
-            if let Some(change) = self.change_buffer.pop_front() {
+            if let Some(change) = self.pop_buffered() {
                 return Ok(Some(change));
             }
         }
     }
     
+    /// Hand out the next buffered change, recording its position and transaction state
+    fn pop_buffered(&mut self) -> Option<Change> {
+        let (lsn, change) = self.change_buffer.pop_front()?;
+        self.last_received_lsn = Some(lsn);
+        if self.delivery == DeliveryMode::AtLeastOnce {
+            self.unconfirmed = true;
+        }
+
+        if change.starts_transaction() {
+            self.in_transaction = true;
+        } else if change.ends_transaction()
+            && self.mode == ReplicationMode::Streaming
+            && self.delivery == DeliveryMode::AtMostOnce
+        {
+            // The commit/prepare message starts at the transaction's end LSN;
+            // it is reported as flushed with the next status update
+            self.in_transaction = false;
+            self.last_flushed_lsn = self.last_received_lsn;
+        }
+
+        Some(change)
+    }
+
+    /// Decode a pgoutput message, tracking whether we are inside a streamed block.
+    ///
+    /// A BEGIN is held back until the next message so that an ORIGIN following it
+    /// can be attached to it; the BEGIN is then returned together with that next change.
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<Change>> {
+        let change = if self.in_stream_block {
+            self.decoder.decode_streamed_pgoutput_message(data)?
+        } else {
+            self.decoder.decode_pgoutput_message(data)?
+        };
+
+        let Some(change) = change else {
+            return Ok(Vec::new());
+        };
+
+        if let Change::Message { prefix, .. } = &change {
+            if !self.message_prefixes.is_empty() && !self.message_prefixes.contains(prefix) {
+                return Ok(Vec::new());
+            }
+        }
+
+        match change {
+            Change::StreamStart { .. } => self.in_stream_block = true,
+            Change::StreamStop => self.in_stream_block = false,
+            _ => {}
+        }
+
+        let mut changes: Vec<Change> = self.pending_begin.take().into_iter().collect();
+        match change {
+            Change::Origin { name, .. } if !changes.is_empty() => {
+                if let Some(Change::Begin { origin, .. }) = changes.first_mut() {
+                    *origin = Some(name);
+                }
+            }
+            Change::Begin { .. } => {
+                // Emit whatever was pending and hold this BEGIN in turn
+                self.pending_begin = Some(change);
+            }
+            change => {
+                let is_relation = matches!(change, Change::Relation { .. });
+                changes.push(change);
+                if is_relation {
+                    changes.extend(self.decoder.take_schema_change());
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Confirm to PostgreSQL that the transaction whose COMMIT was returned last has been
+    /// durably delivered, allowing the slot to advance past it.
+    ///
+    /// With at-least-once delivery this must be called after every COMMIT: in polling mode
+    /// the same changes are peeked again until the slot is advanced. With at-most-once
+    /// delivery positions are already confirmed on receipt and this only records progress.
+    pub async fn confirm_commit(&mut self) -> Result<()> {
+        // For a COMMIT this is the end of the commit record, i.e. the restart point
+        let Some(lsn) = self.last_received_lsn else {
+            return Ok(());
+        };
+
+        self.last_processed_lsn = Some(lsn);
+
+        if self.delivery == DeliveryMode::AtLeastOnce {
+            self.in_transaction = false;
+            self.unconfirmed = false;
+            self.last_flushed_lsn = Some(lsn);
+            match self.mode {
+                ReplicationMode::Streaming => self.send_status_update().await?,
+                ReplicationMode::Polling => {
+                    admin::advance_slot(&self.client, &self.slot_name, lsn).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether changes outside any transaction, such as non-transactional logical
+    /// messages, were handed out and not yet confirmed. Once the targets have flushed
+    /// them, `confirm_commit` lets the slot move past them; until then keepalives do
+    /// not advance the flushed position.
+    pub fn confirm_due(&self) -> bool {
+        self.unconfirmed && !self.in_transaction && self.pending_begin.is_none()
+    }
+
+    /// Whether snapshot chunks were read completely since the last checkpoint, which
+    /// `save_snapshot_checkpoint` records as done once the targets hold their rows.
+    /// `next_message` returns `None` as soon as the snapshot has finished, so callers
+    /// should check this on every message, data or not.
+    pub fn snapshot_checkpoint_due(&self) -> bool {
+        self.snapshot.as_ref().is_some_and(TableSnapshot::checkpoint_due)
+    }
+
+    /// Record the snapshot chunks read so far as done. Call after the targets have
+    /// flushed; unlike `confirm_commit` this leaves the slot position alone, so it may
+    /// be called in the middle of a transaction.
+    pub fn save_snapshot_checkpoint(&mut self) -> Result<()> {
+        let Some(snapshot) = self.snapshot.as_mut().filter(|s| s.checkpoint_due()) else {
+            return Ok(());
+        };
+        snapshot.save_checkpoint()?;
+        if snapshot.is_finished() {
+            self.snapshot = None;
+        }
+        Ok(())
+    }
+
     /// Mark an LSN as successfully processed
-    /// Note: pg_logical_slot_get_binary_changes already auto-confirms,
-    /// but this tracks progress for monitoring/debugging
-    pub fn mark_processed(&mut self, lsn: &str) {
-        self.last_processed_lsn = Some(lsn.to_string());
+    /// Note: this only tracks progress for monitoring/debugging;
+    /// use confirm_commit() to let the slot advance
+    pub fn mark_processed(&mut self, lsn: Lsn) {
+        self.last_processed_lsn = Some(lsn);
     }
     
     /// Get the last received LSN from PostgreSQL
-    pub fn last_received_lsn(&self) -> Option<&str> {
-        self.last_received_lsn.as_deref()
+    pub fn last_received_lsn(&self) -> Option<Lsn> {
+        self.last_received_lsn
     }
     
     /// Get the last successfully processed LSN
-    pub fn last_processed_lsn(&self) -> Option<&str> {
-        self.last_processed_lsn.as_deref()
+    pub fn last_processed_lsn(&self) -> Option<Lsn> {
+        self.last_processed_lsn
+    }
+    
+    /// Get the last LSN confirmed to PostgreSQL as durably delivered
+    pub fn last_flushed_lsn(&self) -> Option<Lsn> {
+        self.last_flushed_lsn
+    }
+
+    /// Get the decoding context holding the relations and types seen on this stream
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
     }
     
     /// Get replication slot status from PostgreSQL
     pub async fn get_slot_status(&self) -> Result<SlotStatus> {
-        let query = format!(
-            "SELECT confirmed_flush_lsn, restart_lsn, active FROM pg_replication_slots WHERE slot_name = '{}'",
-            self.slot_name
-        );
-        
-        let rows = self.client.query(&query, &[]).await?;
-        
-        if rows.is_empty() {
-            return Err(anyhow::anyhow!("Replication slot '{}' not found", self.slot_name));
-        }
-        
-        let row = &rows[0];
-        Ok(SlotStatus {
-            confirmed_flush_lsn: row.get(0),
-            restart_lsn: row.get(1),
-            active: row.get(2),
+        admin::slot_status(&self.client, &self.slot_name).await
+    }
+}
+
+/// Build the pgoutput plugin options for a publication, or a comma-separated list of them
+pub fn build_plugin_options(
+    publication_name: &str,
+    options: &ReplicationOptions,
+) -> Result<Vec<(&'static str, String)>> {
+    if options.streaming && options.protocol_version < 2 {
+        return Err(anyhow!("Streaming in-progress transactions requires protocol version 2 or later"));
+    }
+
+    if options.two_phase && options.protocol_version < 3 {
+        return Err(anyhow!("Two-phase commit decoding requires protocol version 3 or later"));
+    }
+
+    // Each name is quoted on its own; pgoutput splits the list on commas outside quotes
+    let publication_names = publication_name
+        .split(',')
+        .map(|name| match name.trim() {
+            "" => Err(anyhow!("Empty publication name in '{}'", publication_name)),
+            name => Ok(escape_identifier(name)),
         })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut plugin_options = vec![
+        ("proto_version", options.protocol_version.to_string()),
+        ("publication_names", publication_names.join(",")),
+    ];
+    if options.streaming {
+        plugin_options.push(("streaming", "on".to_string()));
+    }
+    if options.two_phase {
+        plugin_options.push(("two_phase", "on".to_string()));
+    }
+    if options.binary {
+        plugin_options.push(("binary", "true".to_string()));
+    }
+    if options.messages {
+        plugin_options.push(("messages", "true".to_string()));
     }
+    Ok(plugin_options)
 }
 
-#[derive(Debug, Clone)]
-pub struct SlotStatus {
-    pub confirmed_flush_lsn: String,
-    pub restart_lsn: String,
-    pub active: bool,
+/// Build the CREATE_REPLICATION_SLOT command for a pgoutput slot that exports the
+/// snapshot its changes start from
+pub fn build_create_slot_command(slot_name: &str, two_phase: bool) -> String {
+    if two_phase {
+        // Two-phase decoding needs the option list syntax of PostgreSQL 15+
+        format!(
+            "CREATE_REPLICATION_SLOT {} LOGICAL pgoutput (TWO_PHASE true, SNAPSHOT 'export')",
+            escape_identifier(slot_name)
+        )
+    } else {
+        format!("CREATE_REPLICATION_SLOT {} LOGICAL pgoutput EXPORT_SNAPSHOT", escape_identifier(slot_name))
+    }
+}
+
+/// Build the START_REPLICATION command for a pgoutput slot
+pub fn build_start_replication_command(
+    slot_name: &str,
+    start_lsn: Lsn,
+    plugin_options: &[(&str, String)],
+) -> String {
+    let plugin_options: Vec<String> = plugin_options
+        .iter()
+        .map(|(name, value)| format!("{} {}", name, escape_literal(value)))
+        .collect();
+    format!(
+        "START_REPLICATION SLOT {} LOGICAL {} ({})",
+        escape_identifier(slot_name),
+        start_lsn,
+        plugin_options.join(", "),
+    )
 }
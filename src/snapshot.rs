@@ -0,0 +1,791 @@
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use postgres_protocol::escape::{escape_identifier, escape_literal};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_postgres::{Client, Config, NoTls};
+
+use crate::decoder::{Change, ColumnInfo, ReplicaIdentity, Tuple};
+use crate::lsn::Lsn;
+use crate::value::{builtin_type_name, decode_text, TypeModifier};
+
+/// OIDs below this belong to objects created by initdb, which pgoutput never sends Type messages for
+const FIRST_NORMAL_OBJECT_ID: u32 = 16384;
+
+/// How often snapshot progress is reported on stderr
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Rows a worker may read ahead of the targets
+const ROW_BUFFER: usize = 1024;
+
+/// Upper bound on the chunks one table is split into, which keeps the plan and the
+/// state file small on huge tables with a small chunk size
+pub const MAX_CHUNKS_PER_TABLE: usize = 10_000;
+
+/// Sampled key values per planned chunk, from which the chunk boundaries are picked
+const SAMPLE_ROWS_PER_CHUNK: usize = 20;
+
+/// How the initial snapshot is split up and where its progress is kept
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// Connections copying chunks in parallel
+    pub workers: usize,
+    /// Rows per chunk, for tables with a single integer primary key
+    pub chunk_size: i64,
+    /// File recording completed chunks, so an interrupted snapshot can resume
+    pub state_path: Option<PathBuf>,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            chunk_size: 100_000,
+            state_path: None,
+        }
+    }
+}
+
+/// A table of the publication, as its rows are published
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishedTable {
+    pub relation_id: u32,
+    pub schema: String,
+    pub table: String,
+    pub replica_identity: ReplicaIdentity,
+    /// Partitioned tables are read with their partitions, other tables without inheritance children
+    pub partitioned: bool,
+    /// Columns the publication is restricted to (PostgreSQL 15+ column lists)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_list: Option<Vec<String>>,
+    /// Row filter of the publication (PostgreSQL 15+), as SQL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_filter: Option<String>,
+}
+
+/// A range of primary key values copied as one unit; open-ended where a bound is `None`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+    /// Every row of the chunk has been delivered to the targets
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// A table's columns and the chunks it is copied in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TablePlan {
+    #[serde(flatten)]
+    pub table: PublishedTable,
+    pub columns: Vec<ColumnInfo>,
+    /// Single integer primary key column the chunks are ranges of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_key: Option<String>,
+    pub chunks: Vec<Chunk>,
+}
+
+impl TablePlan {
+    pub(crate) fn relation(&self) -> Change {
+        Change::Relation {
+            relation_id: self.table.relation_id,
+            schema: self.table.schema.clone(),
+            table: self.table.table.clone(),
+            replica_identity: self.table.replica_identity,
+            columns: self.columns.clone(),
+        }
+    }
+
+    pub(crate) fn key_columns(&self) -> Vec<String> {
+        self.columns.iter().filter(|c| c.is_key()).map(|c| c.name.clone()).collect()
+    }
+}
+
+/// Progress of a slot's initial snapshot, persisted between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotState {
+    pub slot: String,
+    pub publication: String,
+    /// Slot position the snapshot is consistent with; streaming starts here
+    pub consistent_point: Lsn,
+    pub tables: Vec<TablePlan>,
+    /// All chunks were delivered and streaming has taken over
+    #[serde(default)]
+    pub complete: bool,
+}
+
+impl SnapshotState {
+    /// Read the state file; `None` if it doesn't exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| anyhow!("Invalid snapshot state file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow!("Failed to read snapshot state file {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write the state file, replacing it atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)
+            .map_err(|e| anyhow!("Failed to write snapshot state file {}: {}", path.display(), e))
+    }
+
+    /// Chunks not yet delivered, as (table index, chunk index)
+    pub fn pending_chunks(&self) -> Vec<(usize, usize)> {
+        self.tables
+            .iter()
+            .enumerate()
+            .flat_map(|(t, plan)| {
+                plan.chunks.iter().enumerate().filter(|(_, c)| !c.done).map(move |(c, _)| (t, c))
+            })
+            .collect()
+    }
+}
+
+/// A chunk handed to a worker
+struct ChunkTask {
+    table_index: usize,
+    chunk_index: usize,
+    plan: Arc<TablePlan>,
+}
+
+/// What workers report back, in order per worker
+#[allow(clippy::large_enum_variant)]
+enum SnapshotEvent {
+    Row(Change),
+    ChunkDone { table_index: usize, chunk_index: usize },
+}
+
+/// Initial copy of every table in a publication.
+///
+/// Tables with a single integer primary key are split into key ranges that several
+/// workers copy in parallel, each in a transaction that imported the same snapshot.
+/// A fresh snapshot uses the one exported with the replication slot, which sees exactly
+/// the transactions committed before the slot's consistent point, so streaming from that
+/// point afterwards neither misses nor repeats a change.
+///
+/// Every table is announced with a Relation event before any of its Snapshot rows.
+/// Chunks are recorded as done in the state file once the targets have flushed their
+/// rows (see `save_checkpoint`), and an interrupted snapshot resumes with the rest.
+pub struct TableSnapshot {
+    state: SnapshotState,
+    state_path: Option<PathBuf>,
+    relations: VecDeque<Change>,
+    events: mpsc::Receiver<Result<SnapshotEvent>>,
+    /// Chunks whose rows have all been handed out but not yet confirmed
+    delivered: Vec<(usize, usize)>,
+    total_chunks: usize,
+    chunks_done: usize,
+    rows: u64,
+    started: Instant,
+    last_report: Instant,
+    finished: bool,
+}
+
+impl TableSnapshot {
+    /// Plan a new snapshot of the publication under the snapshot exported when the slot
+    /// was created, and start copying it.
+    ///
+    /// The exported snapshot can only be imported while the connection that created the
+    /// slot is still idle; every worker imports it before this returns.
+    pub async fn begin(
+        config: &Config,
+        options: &SnapshotOptions,
+        slot_name: &str,
+        publication_name: &str,
+        snapshot_name: &str,
+        consistent_point: Lsn,
+    ) -> Result<Self> {
+        let planner = connect(config).await?;
+        import_snapshot(&planner, snapshot_name).await?;
+
+        let mut tables = Vec::new();
+        for table in published_tables(&planner, publication_name).await? {
+            let columns = table_columns(&planner, &table).await?;
+            let chunk_key = integer_primary_key(&planner, &table).await?;
+            let chunks = match &chunk_key {
+                Some(key) => plan_chunks(&planner, &table, key, options.chunk_size).await?,
+                None => vec![Chunk { start: None, end: None, done: false }],
+            };
+            tables.push(TablePlan { table, columns, chunk_key, chunks });
+        }
+
+        let state = SnapshotState {
+            slot: slot_name.to_string(),
+            publication: publication_name.to_string(),
+            consistent_point,
+            tables,
+            complete: false,
+        };
+        if let Some(path) = &options.state_path {
+            state.save(path)?;
+        }
+
+        let snapshot = Self::launch(config, options, state, snapshot_name).await?;
+        planner.batch_execute("COMMIT").await?;
+        Ok(snapshot)
+    }
+
+    /// Copy the chunks an interrupted snapshot did not deliver.
+    ///
+    /// The slot's exported snapshot ended with the run that created it, so the remaining
+    /// chunks are read under a new snapshot shared by the workers. Their rows may already
+    /// contain changes made after the consistent point, which streaming then delivers again,
+    /// so consumers of a resumed snapshot must apply rows as upserts by primary key.
+    pub async fn resume(config: &Config, options: &SnapshotOptions, state: SnapshotState) -> Result<Self> {
+        let exporter = connect(config).await?;
+        exporter.batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY").await?;
+        let row = exporter.query_one("SELECT pg_export_snapshot()", &[]).await?;
+        let snapshot_name: String = row.get(0);
+
+        eprintln!(
+            "Resuming snapshot of slot '{}': {} chunk(s) left; rows changed since {} may be delivered twice",
+            state.slot,
+            state.pending_chunks().len(),
+            state.consistent_point
+        );
+        let snapshot = Self::launch(config, options, state, &snapshot_name).await?;
+        exporter.batch_execute("COMMIT").await?;
+        Ok(snapshot)
+    }
+
+    /// Import the snapshot on each worker connection and start copying the pending chunks
+    async fn launch(
+        config: &Config,
+        options: &SnapshotOptions,
+        state: SnapshotState,
+        snapshot_name: &str,
+    ) -> Result<Self> {
+        let pending = state.pending_chunks();
+        let plans: Vec<Arc<TablePlan>> = state.tables.iter().cloned().map(Arc::new).collect();
+
+        let mut relations = VecDeque::new();
+        for (t, plan) in plans.iter().enumerate() {
+            if pending.iter().any(|(table_index, _)| *table_index == t) {
+                relations.push_back(plan.relation());
+            }
+        }
+        let queue: VecDeque<ChunkTask> = pending
+            .iter()
+            .map(|&(table_index, chunk_index)| ChunkTask {
+                table_index,
+                chunk_index,
+                plan: plans[table_index].clone(),
+            })
+            .collect();
+        let queue = Arc::new(Mutex::new(queue));
+
+        let (tx, events) = mpsc::channel(ROW_BUFFER);
+        let workers = options.workers.clamp(1, pending.len().max(1));
+        for _ in 0..workers {
+            let client = connect(config).await?;
+            import_snapshot(&client, snapshot_name).await?;
+            tokio::spawn(run_worker(client, queue.clone(), tx.clone(), state.consistent_point));
+        }
+
+        let total_chunks = state.tables.iter().map(|t| t.chunks.len()).sum();
+        eprintln!(
+            "Snapshotting {} table(s) of publication '{}' at LSN {}: {} chunk(s), {} worker(s)",
+            relations.len(),
+            state.publication,
+            state.consistent_point,
+            pending.len(),
+            workers
+        );
+
+        let now = Instant::now();
+        Ok(Self {
+            state_path: options.state_path.clone(),
+            relations,
+            events,
+            delivered: Vec::new(),
+            chunks_done: total_chunks - pending.len(),
+            total_chunks,
+            rows: 0,
+            started: now,
+            last_report: now,
+            finished: false,
+            state,
+        })
+    }
+
+    /// Slot position the snapshot is consistent with
+    pub fn consistent_point(&self) -> Lsn {
+        self.state.consistent_point
+    }
+
+    /// Whether every chunk has been read
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Next Relation or Snapshot event; `None` once every chunk has been read
+    pub async fn next_change(&mut self) -> Result<Option<Change>> {
+        if let Some(relation) = self.relations.pop_front() {
+            return Ok(Some(relation));
+        }
+
+        while let Some(event) = self.events.recv().await {
+            match event? {
+                SnapshotEvent::Row(change) => {
+                    self.rows += 1;
+                    return Ok(Some(change));
+                }
+                SnapshotEvent::ChunkDone { table_index, chunk_index } => {
+                    self.delivered.push((table_index, chunk_index));
+                    self.chunks_done += 1;
+                    if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+                        self.report_progress();
+                    }
+                }
+            }
+        }
+
+        if !self.finished {
+            self.finished = true;
+            eprintln!(
+                "Snapshot complete: {} rows in {:.1}s",
+                self.rows,
+                self.started.elapsed().as_secs_f64()
+            );
+        }
+        Ok(None)
+    }
+
+    fn report_progress(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        eprintln!(
+            "Snapshot progress: {}/{} chunks, {} left, {} rows ({:.0} rows/s)",
+            self.chunks_done,
+            self.total_chunks,
+            self.total_chunks - self.chunks_done,
+            self.rows,
+            if elapsed > 0.0 { self.rows as f64 / elapsed } else { 0.0 }
+        );
+        self.last_report = Instant::now();
+    }
+
+    /// Whether chunks were completed since the last checkpoint, or the snapshot finished
+    pub fn checkpoint_due(&self) -> bool {
+        !self.delivered.is_empty() || (self.finished && !self.state.complete)
+    }
+
+    /// Record the chunks read so far as done. Call once the targets have durably
+    /// accepted every change handed out, like a transaction's commit.
+    pub fn save_checkpoint(&mut self) -> Result<()> {
+        for (table_index, chunk_index) in self.delivered.drain(..) {
+            self.state.tables[table_index].chunks[chunk_index].done = true;
+        }
+        self.state.complete = self.finished;
+        match &self.state_path {
+            Some(path) => self.state.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Copy chunks from the shared queue until it is empty, reporting rows and finished
+/// chunks over `events`
+async fn run_worker(
+    client: Client,
+    queue: Arc<Mutex<VecDeque<ChunkTask>>>,
+    events: mpsc::Sender<Result<SnapshotEvent>>,
+    consistent_point: Lsn,
+) {
+    loop {
+        let Some(task) = queue.lock().unwrap().pop_front() else {
+            break;
+        };
+        if let Err(e) = copy_chunk(&client, &task, &events, consistent_point).await {
+            let _ = events.send(Err(e)).await;
+            return;
+        }
+        let done = SnapshotEvent::ChunkDone {
+            table_index: task.table_index,
+            chunk_index: task.chunk_index,
+        };
+        if events.send(Ok(done)).await.is_err() {
+            // The stream was dropped, e.g. on shutdown
+            return;
+        }
+    }
+    let _ = client.batch_execute("COMMIT").await;
+}
+
+async fn copy_chunk(
+    client: &Client,
+    task: &ChunkTask,
+    events: &mpsc::Sender<Result<SnapshotEvent>>,
+    consistent_point: Lsn,
+) -> Result<()> {
+    let plan = &task.plan;
+    let query = build_copy_query(plan, &plan.chunks[task.chunk_index]);
+    let mut stream = Box::pin(client.copy_out(query.as_str()).await?);
+    let key_columns = plan.key_columns();
+
+    let mut buffer: Vec<u8> = Vec::new();
+    loop {
+        let line = match buffer.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                let mut line: Vec<u8> = buffer.drain(..=end).collect();
+                line.pop();
+                line
+            }
+            None => match stream.next().await {
+                Some(chunk) => {
+                    buffer.extend_from_slice(&chunk?);
+                    continue;
+                }
+                None if buffer.is_empty() => return Ok(()),
+                None => std::mem::take(&mut buffer),
+            },
+        };
+
+        let change = row_change(plan, &key_columns, &line, consistent_point)?;
+        if events.send(Ok(SnapshotEvent::Row(change))).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn row_change(plan: &TablePlan, key_columns: &[String], line: &[u8], consistent_point: Lsn) -> Result<Change> {
+    let values = parse_copy_line(line);
+    if values.len() != plan.columns.len() {
+        return Err(anyhow!(
+            "COPY of {}.{} returned {} values for {} columns",
+            plan.table.schema,
+            plan.table.table,
+            values.len(),
+            plan.columns.len()
+        ));
+    }
+
+    let new_tuple: Tuple = plan
+        .columns
+        .iter()
+        .zip(values)
+        .map(|(column, value)| (column.name.clone(), value.map(|v| decode_text(column.type_id, &v))))
+        .collect();
+    Ok(Change::Snapshot {
+        relation_id: plan.table.relation_id,
+        schema: plan.table.schema.clone(),
+        table: plan.table.table.clone(),
+        new_tuple,
+        key_columns: key_columns.to_vec(),
+        lsn: consistent_point,
+        incremental: false,
+    })
+}
+
+async fn connect(config: &Config) -> Result<Client> {
+    let (client, connection) = config.connect(NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Snapshot connection error: {}", e);
+        }
+    });
+    Ok(client)
+}
+
+/// Open a read-only transaction that sees the given exported snapshot
+async fn import_snapshot(client: &Client, snapshot_name: &str) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY; SET TRANSACTION SNAPSHOT {}",
+            escape_literal(snapshot_name)
+        ))
+        .await
+        .map_err(|e| anyhow!("Failed to import snapshot {}: {}", snapshot_name, e))
+}
+
+/// Tables whose changes the publication sends. Column lists and row filters are read
+/// through `to_jsonb` so the query also runs on servers that predate them.
+pub(crate) async fn published_tables(client: &Client, publication_name: &str) -> Result<Vec<PublishedTable>> {
+    let rows = client
+        .query(
+            "SELECT c.oid, n.nspname, c.relname, c.relreplident::text, c.relkind = 'p', \
+                    to_jsonb(pt) -> 'attnames', to_jsonb(pt) ->> 'rowfilter' \
+             FROM pg_publication_tables pt \
+             JOIN pg_namespace n ON n.nspname = pt.schemaname \
+             JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = pt.tablename \
+             WHERE pt.pubname = $1 \
+             ORDER BY n.nspname, c.relname",
+            &[&publication_name],
+        )
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let column_list = row
+                .get::<_, Option<serde_json::Value>>(5)
+                .map(serde_json::from_value::<Vec<String>>)
+                .transpose()?;
+            Ok(PublishedTable {
+                relation_id: row.get(0),
+                schema: row.get(1),
+                table: row.get(2),
+                replica_identity: ReplicaIdentity::from_byte(row.get::<_, &str>(3).as_bytes()[0])?,
+                partitioned: row.get(4),
+                column_list,
+                row_filter: row.get(6),
+            })
+        })
+        .collect()
+}
+
+/// Columns of a table as pgoutput describes them in a Relation message:
+/// generated columns are left out and replica identity columns are flagged as keys
+pub(crate) async fn table_columns(client: &Client, table: &PublishedTable) -> Result<Vec<ColumnInfo>> {
+    let rows = client
+        .query(
+            "SELECT a.attname, a.atttypid, a.atttypmod, tn.nspname, t.typname, \
+                    c.relreplident = 'f' OR EXISTS ( \
+                        SELECT 1 FROM pg_index i \
+                        WHERE i.indrelid = c.oid AND a.attnum = ANY(i.indkey) \
+                          AND CASE c.relreplident WHEN 'd' THEN i.indisprimary \
+                                                  WHEN 'i' THEN i.indisreplident \
+                                                  ELSE false END) \
+             FROM pg_class c \
+             JOIN pg_attribute a ON a.attrelid = c.oid \
+             JOIN pg_type t ON t.oid = a.atttypid \
+             JOIN pg_namespace tn ON tn.oid = t.typnamespace \
+             WHERE c.oid = $1 AND a.attnum > 0 AND NOT a.attisdropped AND a.attgenerated = '' \
+             ORDER BY a.attnum",
+            &[&table.relation_id],
+        )
+        .await?;
+
+    let columns = rows
+        .iter()
+        .map(|row| {
+            let name: String = row.get(0);
+            let type_id: u32 = row.get(1);
+            let type_modifier: i32 = row.get(2);
+            let modifier = TypeModifier::decode(type_id, type_modifier);
+            let type_name = if type_id < FIRST_NORMAL_OBJECT_ID {
+                builtin_type_name(type_id).map(str::to_string)
+            } else {
+                Some(format!("{}.{}", row.get::<_, &str>(3), row.get::<_, &str>(4)))
+            };
+            ColumnInfo {
+                name,
+                type_id,
+                flags: row.get::<_, bool>(5) as u8,
+                type_modifier,
+                type_name,
+                length: modifier.length,
+                precision: modifier.precision,
+                scale: modifier.scale,
+            }
+        })
+        .filter(|column| {
+            table.column_list.as_ref().is_none_or(|list| list.contains(&column.name))
+        })
+        .collect::<Vec<_>>();
+
+    if columns.is_empty() {
+        return Err(anyhow!("Table {}.{} has no columns to snapshot", table.schema, table.table));
+    }
+    Ok(columns)
+}
+
+/// The table's primary key column if it is a single int2, int4 or int8 column
+async fn integer_primary_key(client: &Client, table: &PublishedTable) -> Result<Option<String>> {
+    let row = client
+        .query_opt(
+            "SELECT a.attname \
+             FROM pg_index i \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0] \
+             WHERE i.indrelid = $1 AND i.indisprimary AND i.indnatts = 1 \
+               AND a.atttypid IN (20, 21, 23)",
+            &[&table.relation_id],
+        )
+        .await?;
+    Ok(row.map(|row| row.get(0)))
+}
+
+/// Split the table into chunks of about `chunk_size` rows. The row count comes from the
+/// planner's estimate and the boundaries from a block sample of the key, so planning
+/// does not scan the whole table before the workers start; sparse keys still get
+/// chunks of similar size.
+async fn plan_chunks(client: &Client, table: &PublishedTable, key: &str, chunk_size: i64) -> Result<Vec<Chunk>> {
+    let key = escape_identifier(key);
+    let from = table_reference(table);
+
+    let mut rows = estimated_rows(client, table).await?;
+    if rows == 0 {
+        // Never vacuumed or analyzed: no estimate to go by
+        rows = client.query_one(&format!("SELECT count(*) FROM {}", from), &[]).await?.get(0);
+    }
+    let count = chunk_count(rows, chunk_size);
+    if count <= 1 {
+        return Ok(chunk_ranges(&[]));
+    }
+
+    let percent = (100.0 * (count * SAMPLE_ROWS_PER_CHUNK) as f64 / rows as f64).clamp(0.0001, 100.0);
+    let sample: Vec<i64> = client
+        .query(
+            &format!(
+                "SELECT {}::int8 FROM {} TABLESAMPLE SYSTEM ({}) ORDER BY 1",
+                key, from, percent
+            ),
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    Ok(chunk_ranges(&sample_boundaries(&sample, count)))
+}
+
+/// Rows of the table according to the planner's statistics, with its partitions for a
+/// partitioned table; 0 when it has none
+async fn estimated_rows(client: &Client, table: &PublishedTable) -> Result<i64> {
+    let query = if table.partitioned {
+        "WITH RECURSIVE tree(oid) AS ( \
+             SELECT $1::oid \
+             UNION ALL SELECT i.inhrelid FROM pg_inherits i JOIN tree t ON i.inhparent = t.oid) \
+         SELECT coalesce(sum(greatest(c.reltuples, 0)), 0)::int8 FROM pg_class c JOIN tree USING (oid)"
+    } else {
+        "SELECT greatest(reltuples, 0)::int8 FROM pg_class WHERE oid = $1"
+    };
+    Ok(client.query_one(query, &[&table.relation_id]).await?.get(0))
+}
+
+/// Boundaries splitting a sorted sample of key values into `count` parts of equal size
+pub fn sample_boundaries(sample: &[i64], count: usize) -> Vec<i64> {
+    let mut boundaries: Vec<i64> = (1..count)
+        .filter_map(|i| sample.get(i * sample.len() / count).copied())
+        .collect();
+    boundaries.dedup();
+    boundaries
+}
+
+/// Number of chunks a table of `rows` rows is split into: enough for `chunk_size` rows
+/// each, but at most `MAX_CHUNKS_PER_TABLE`
+pub fn chunk_count(rows: i64, chunk_size: i64) -> usize {
+    let chunk_size = chunk_size.max(1);
+    let count = rows.max(0) / chunk_size + (rows.max(0) % chunk_size != 0) as i64;
+    count.clamp(1, MAX_CHUNKS_PER_TABLE as i64) as usize
+}
+
+/// Chunks split at the given ascending key values, each starting at its boundary. The
+/// first and last chunks are open-ended, so rows outside the planned range (seen by a
+/// resumed snapshot) are not missed.
+pub fn chunk_ranges(boundaries: &[i64]) -> Vec<Chunk> {
+    let starts = std::iter::once(None).chain(boundaries.iter().copied().map(Some));
+    let ends = boundaries.iter().copied().map(Some).chain(std::iter::once(None));
+    starts
+        .zip(ends)
+        .map(|(start, end)| Chunk { start, end, done: false })
+        .collect()
+}
+
+/// `FROM` target for a published table: partitioned tables with their partitions,
+/// other tables without inheritance children
+pub(crate) fn table_reference(table: &PublishedTable) -> String {
+    format!(
+        "{}{}.{}",
+        if table.partitioned { "" } else { "ONLY " },
+        escape_identifier(&table.schema),
+        escape_identifier(&table.table)
+    )
+}
+
+/// COPY statement reading one chunk of a published table in its publication's shape
+pub fn build_copy_query(plan: &TablePlan, chunk: &Chunk) -> String {
+    let column_names: Vec<String> = plan.columns.iter().map(|c| escape_identifier(&c.name)).collect();
+    let mut conditions = Vec::new();
+    if let Some(filter) = &plan.table.row_filter {
+        conditions.push(format!("({})", filter));
+    }
+    if let Some(key) = &plan.chunk_key {
+        let key = escape_identifier(key);
+        if let Some(start) = chunk.start {
+            conditions.push(format!("{} >= {}", key, start));
+        }
+        if let Some(end) = chunk.end {
+            conditions.push(format!("{} < {}", key, end));
+        }
+    }
+
+    let mut query = format!("COPY (SELECT {} FROM {}", column_names.join(", "), table_reference(&plan.table));
+    if !conditions.is_empty() {
+        query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    query.push_str(") TO STDOUT");
+    query
+}
+
+/// Split a row of COPY text output into its values; `\N` is NULL
+pub fn parse_copy_line(line: &[u8]) -> Vec<Option<String>> {
+    line.split(|&b| b == b'\t')
+        .map(|field| {
+            if field == b"\\N" {
+                None
+            } else {
+                Some(String::from_utf8_lossy(&unescape_copy_field(field)).into_owned())
+            }
+        })
+        .collect()
+}
+
+/// Undo the backslash escapes of COPY's text format
+fn unescape_copy_field(field: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(field.len());
+    let mut i = 0;
+    while i < field.len() {
+        let b = field[i];
+        i += 1;
+        if b != b'\\' || i == field.len() {
+            out.push(b);
+            continue;
+        }
+
+        let escaped = field[i];
+        i += 1;
+        match escaped {
+            b'b' => out.push(0x08),
+            b'f' => out.push(0x0C),
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b't' => out.push(b'\t'),
+            b'v' => out.push(0x0B),
+            b'0'..=b'7' => {
+                let mut value = (escaped - b'0') as u32;
+                for _ in 0..2 {
+                    match field.get(i) {
+                        Some(&d @ b'0'..=b'7') => {
+                            value = value * 8 + (d - b'0') as u32;
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                out.push(value as u8);
+            }
+            b'x' => {
+                let digits = field[i..].iter().take(2).take_while(|d| d.is_ascii_hexdigit()).count();
+                if digits == 0 {
+                    out.push(b'x');
+                } else {
+                    let hex = std::str::from_utf8(&field[i..i + digits]).unwrap_or("0");
+                    out.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+                    i += digits;
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
@@ -0,0 +1,825 @@
+use anyhow::{anyhow, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Built-in type OIDs with a binary decoder
+const BOOL: u32 = 16;
+const BYTEA: u32 = 17;
+const INT8: u32 = 20;
+const INT2: u32 = 21;
+const INT4: u32 = 23;
+const TEXT: u32 = 25;
+const OID: u32 = 26;
+const JSON: u32 = 114;
+const FLOAT4: u32 = 700;
+const FLOAT8: u32 = 701;
+const BPCHAR: u32 = 1042;
+const VARCHAR: u32 = 1043;
+const DATE: u32 = 1082;
+const TIME: u32 = 1083;
+const TIMESTAMP: u32 = 1114;
+const TIMESTAMPTZ: u32 = 1184;
+const INTERVAL: u32 = 1186;
+const NUMERIC: u32 = 1700;
+const UUID: u32 = 2950;
+const JSONB: u32 = 3802;
+
+// Built-in type OIDs without a binary decoder whose type modifier is decoded
+const TIMETZ: u32 = 1266;
+const BIT: u32 = 1560;
+const VARBIT: u32 = 1562;
+
+/// Array type OIDs of the built-in types above
+const ARRAY_TYPES: &[u32] = &[
+    199,  // json[]
+    1000, // bool[]
+    1001, // bytea[]
+    1005, // int2[]
+    1007, // int4[]
+    1009, // text[]
+    1014, // bpchar[]
+    1015, // varchar[]
+    1016, // int8[]
+    1021, // float4[]
+    1022, // float8[]
+    1028, // oid[]
+    1115, // timestamp[]
+    1182, // date[]
+    1183, // time[]
+    1185, // timestamptz[]
+    1187, // interval[]
+    1231, // numeric[]
+    2951, // uuid[]
+    3807, // jsonb[]
+];
+
+/// A column value from a decoded tuple.
+///
+/// Text-format tuple data is kept as `Text`; binary-format data for built-in
+/// types is decoded into the matching typed variant. `Display` renders the
+/// value in PostgreSQL's text output format, so both modes print alike.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgValue {
+    /// Text-format value, or a binary value of a type without a decoder
+    Text(String),
+    Bool(bool),
+    /// int2, int4, int8 and oid
+    Int(i64),
+    /// float4 and float8
+    Float(f64),
+    /// Exact decimal representation of a numeric (including NaN and +/-Infinity)
+    Numeric(String),
+    Bytea(Vec<u8>),
+    Uuid([u8; 16]),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Interval {
+        months: i32,
+        days: i32,
+        microseconds: i64,
+    },
+    /// json and jsonb
+    Json(serde_json::Value),
+    /// One-dimensional or nested array; `None` elements are NULL
+    Array(Vec<Option<PgValue>>),
+}
+
+impl PgValue {
+    /// The string contents of a text-format value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PgValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Natural JSON representation: booleans, numbers, arrays and json columns keep
+    /// their type; numerics are exact decimal strings, bytea is base64, timestamptz
+    /// is RFC 3339 in UTC, timestamp is ISO 8601 without an offset (it has none) and
+    /// intervals are ISO 8601 durations. Everything else uses the PostgreSQL text format.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+
+        match self {
+            PgValue::Text(s) => Value::String(s.clone()),
+            PgValue::Bool(b) => Value::Bool(*b),
+            PgValue::Int(n) => Value::Number((*n).into()),
+            PgValue::Float(f) => serde_json::Number::from_f64(*f)
+                .map(Value::Number)
+                .unwrap_or_else(|| Value::String(self.to_string())),
+            PgValue::Bytea(bytes) => Value::String(BASE64_STANDARD.encode(bytes)),
+            PgValue::Timestamp(ts) => Value::String(format!(
+                "{}{}",
+                ts.format("%Y-%m-%dT%H:%M:%S"),
+                fraction(ts.nanosecond() / 1000)
+            )),
+            PgValue::TimestampTz(ts) => Value::String(format!(
+                "{}{}Z",
+                ts.format("%Y-%m-%dT%H:%M:%S"),
+                fraction(ts.nanosecond() / 1000)
+            )),
+            PgValue::Interval { months, days, microseconds } => {
+                Value::String(iso_interval(*months, *days, *microseconds))
+            }
+            PgValue::Json(v) => v.clone(),
+            PgValue::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| item.as_ref().map_or(Value::Null, PgValue::to_json))
+                    .collect(),
+            ),
+            _ => Value::String(self.to_string()),
+        }
+    }
+
+    /// Inverse of `to_json` for values read back from serialized changes
+    fn from_json(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::String(s) => PgValue::Text(s),
+            Value::Bool(b) => PgValue::Bool(b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => PgValue::Int(i),
+                None => PgValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::Array(items) => PgValue::Array(
+                items
+                    .into_iter()
+                    .map(|item| match item {
+                        Value::Null => None,
+                        other => Some(PgValue::from_json(other)),
+                    })
+                    .collect(),
+            ),
+            other => PgValue::Json(other),
+        }
+    }
+}
+
+impl From<&str> for PgValue {
+    fn from(s: &str) -> Self {
+        PgValue::Text(s.to_string())
+    }
+}
+
+impl From<String> for PgValue {
+    fn from(s: String) -> Self {
+        PgValue::Text(s)
+    }
+}
+
+impl Serialize for PgValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PgValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_json::Value::deserialize(deserializer).map(PgValue::from_json)
+    }
+}
+
+impl fmt::Display for PgValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgValue::Text(s) | PgValue::Numeric(s) => f.write_str(s),
+            PgValue::Bool(b) => f.write_str(if *b { "t" } else { "f" }),
+            PgValue::Int(n) => write!(f, "{}", n),
+            PgValue::Float(v) if v.is_nan() => f.write_str("NaN"),
+            PgValue::Float(v) if v.is_infinite() => {
+                f.write_str(if *v > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            PgValue::Float(v) => write!(f, "{}", v),
+            PgValue::Bytea(bytes) => {
+                f.write_str("\\x")?;
+                bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+            PgValue::Uuid(b) => write!(
+                f,
+                "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+                b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+                b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+            ),
+            PgValue::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            PgValue::Time(t) => {
+                write!(f, "{}{}", t.format("%H:%M:%S"), fraction(t.nanosecond() / 1000))
+            }
+            PgValue::Timestamp(ts) => write!(
+                f,
+                "{}{}",
+                ts.format("%Y-%m-%d %H:%M:%S"),
+                fraction(ts.nanosecond() / 1000)
+            ),
+            PgValue::TimestampTz(ts) => write!(
+                f,
+                "{}{}+00",
+                ts.format("%Y-%m-%d %H:%M:%S"),
+                fraction(ts.nanosecond() / 1000)
+            ),
+            PgValue::Interval { months, days, microseconds } => {
+                fmt_interval(f, *months, *days, *microseconds)
+            }
+            PgValue::Json(v) => write!(f, "{}", v),
+            PgValue::Array(items) => {
+                f.write_str("{")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    match item {
+                        None => f.write_str("NULL")?,
+                        Some(nested @ PgValue::Array(_)) => write!(f, "{}", nested)?,
+                        Some(value) => f.write_str(&quote_array_element(&value.to_string()))?,
+                    }
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Fractional seconds as PostgreSQL prints them (no trailing zeros)
+fn fraction(micros: u32) -> String {
+    if micros == 0 {
+        return String::new();
+    }
+    format!(".{:06}", micros).trim_end_matches('0').to_string()
+}
+
+fn fmt_interval(f: &mut fmt::Formatter<'_>, months: i32, days: i32, microseconds: i64) -> fmt::Result {
+    let plural = |n: i64| if n.abs() == 1 { "" } else { "s" };
+    let mut parts = Vec::new();
+
+    let (years, months) = ((months / 12) as i64, (months % 12) as i64);
+    if years != 0 {
+        parts.push(format!("{} year{}", years, plural(years)));
+    }
+    if months != 0 {
+        parts.push(format!("{} mon{}", months, plural(months)));
+    }
+    if days != 0 {
+        parts.push(format!("{} day{}", days, plural(days as i64)));
+    }
+    if microseconds != 0 || parts.is_empty() {
+        let sign = if microseconds < 0 { "-" } else { "" };
+        let total = microseconds.unsigned_abs();
+        let secs = total / 1_000_000;
+        parts.push(format!(
+            "{}{:02}:{:02}:{:02}{}",
+            sign,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            fraction((total % 1_000_000) as u32)
+        ));
+    }
+
+    f.write_str(&parts.join(" "))
+}
+
+/// An interval as an ISO 8601 duration, e.g. `P1Y2M3DT4H5M6.5S`
+fn iso_interval(months: i32, days: i32, microseconds: i64) -> String {
+    let mut out = String::from("P");
+    let date_parts = [(months / 12, 'Y'), (months % 12, 'M'), (days, 'D')];
+    for (n, unit) in date_parts {
+        if n != 0 {
+            out.push_str(&format!("{}{}", n, unit));
+        }
+    }
+
+    if microseconds != 0 || out.len() == 1 {
+        out.push('T');
+        let sign = if microseconds < 0 { "-" } else { "" };
+        let total = microseconds.unsigned_abs();
+        let secs = total / 1_000_000;
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        if hours != 0 {
+            out.push_str(&format!("{}{}H", sign, hours));
+        }
+        if minutes != 0 {
+            out.push_str(&format!("{}{}M", sign, minutes));
+        }
+        let micros = (total % 1_000_000) as u32;
+        if seconds != 0 || micros != 0 || (hours == 0 && minutes == 0) {
+            out.push_str(&format!("{}{}{}S", sign, seconds, fraction(micros)));
+        }
+    }
+    out
+}
+
+/// Quote an array element the way PostgreSQL's array output does
+fn quote_array_element(s: &str) -> String {
+    let needs_quotes = s.is_empty()
+        || s.eq_ignore_ascii_case("NULL")
+        || s.chars().any(|c| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_whitespace());
+    if !needs_quotes {
+        return s.to_string();
+    }
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Names of built-in types as PostgreSQL's `format_type` prints them,
+/// with the element type of each array type
+const TYPE_NAMES: &[(u32, &str, Option<u32>)] = &[
+    (BOOL, "boolean", None),
+    (BYTEA, "bytea", None),
+    (18, "\"char\"", None),
+    (19, "name", None),
+    (INT8, "bigint", None),
+    (INT2, "smallint", None),
+    (INT4, "integer", None),
+    (TEXT, "text", None),
+    (OID, "oid", None),
+    (JSON, "json", None),
+    (142, "xml", None),
+    (FLOAT4, "real", None),
+    (FLOAT8, "double precision", None),
+    (790, "money", None),
+    (829, "macaddr", None),
+    (869, "inet", None),
+    (650, "cidr", None),
+    (BPCHAR, "character", None),
+    (VARCHAR, "character varying", None),
+    (DATE, "date", None),
+    (TIME, "time without time zone", None),
+    (TIMESTAMP, "timestamp without time zone", None),
+    (TIMESTAMPTZ, "timestamp with time zone", None),
+    (INTERVAL, "interval", None),
+    (TIMETZ, "time with time zone", None),
+    (BIT, "bit", None),
+    (VARBIT, "bit varying", None),
+    (NUMERIC, "numeric", None),
+    (UUID, "uuid", None),
+    (JSONB, "jsonb", None),
+    (199, "json[]", Some(JSON)),
+    (1000, "boolean[]", Some(BOOL)),
+    (1001, "bytea[]", Some(BYTEA)),
+    (1005, "smallint[]", Some(INT2)),
+    (1007, "integer[]", Some(INT4)),
+    (1009, "text[]", Some(TEXT)),
+    (1014, "character[]", Some(BPCHAR)),
+    (1015, "character varying[]", Some(VARCHAR)),
+    (1016, "bigint[]", Some(INT8)),
+    (1021, "real[]", Some(FLOAT4)),
+    (1022, "double precision[]", Some(FLOAT8)),
+    (1028, "oid[]", Some(OID)),
+    (1115, "timestamp without time zone[]", Some(TIMESTAMP)),
+    (1182, "date[]", Some(DATE)),
+    (1183, "time without time zone[]", Some(TIME)),
+    (1185, "timestamp with time zone[]", Some(TIMESTAMPTZ)),
+    (1187, "interval[]", Some(INTERVAL)),
+    (1231, "numeric[]", Some(NUMERIC)),
+    (2951, "uuid[]", Some(UUID)),
+    (3807, "jsonb[]", Some(JSONB)),
+];
+
+/// Human-readable name of a built-in type, e.g. `character varying` for 1043
+pub fn builtin_type_name(type_id: u32) -> Option<&'static str> {
+    TYPE_NAMES
+        .iter()
+        .find(|(id, _, _)| *id == type_id)
+        .map(|(_, name, _)| *name)
+}
+
+/// Element type of a built-in array type, e.g. `int4` (23) for `int4[]` (1007)
+fn array_element_type(type_id: u32) -> Option<u32> {
+    TYPE_NAMES
+        .iter()
+        .find(|(id, _, _)| *id == type_id)
+        .and_then(|(_, _, element)| *element)
+}
+
+/// Length, precision and scale carried in a column's type modifier (`atttypmod`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeModifier {
+    /// Maximum length of character and bit string types
+    pub length: Option<i32>,
+    /// Total digits of numeric, or fractional second digits of time types
+    pub precision: Option<i32>,
+    /// Digits after the decimal point of numeric
+    pub scale: Option<i32>,
+}
+
+impl TypeModifier {
+    /// Decode a type modifier; array types use their element type's modifier.
+    /// A modifier of -1 (none) and types without one decode to all `None`.
+    pub fn decode(type_id: u32, type_modifier: i32) -> Self {
+        let type_id = array_element_type(type_id).unwrap_or(type_id);
+        let mut modifier = TypeModifier::default();
+        if type_modifier < 0 {
+            return modifier;
+        }
+
+        match type_id {
+            // VARHDRSZ is added to the declared length
+            BPCHAR | VARCHAR if type_modifier >= 4 => modifier.length = Some(type_modifier - 4),
+            BIT | VARBIT => modifier.length = Some(type_modifier),
+            NUMERIC if type_modifier >= 4 => {
+                let typmod = type_modifier - 4;
+                modifier.precision = Some((typmod >> 16) & 0xffff);
+                // Scale is an 11-bit signed field (negative scales round left of the point)
+                modifier.scale = Some(((typmod & 0x7ff) ^ 1024) - 1024);
+            }
+            TIME | TIMETZ | TIMESTAMP | TIMESTAMPTZ => modifier.precision = Some(type_modifier),
+            // The upper half holds the field restriction (YEAR TO MONTH, ...)
+            INTERVAL if type_modifier & 0xffff != 0xffff => {
+                modifier.precision = Some(type_modifier & 0xffff)
+            }
+            _ => {}
+        }
+        modifier
+    }
+}
+
+/// Decode a binary-format ('b') column value of the given type.
+///
+/// Types without a decoder fall back to text when the bytes are valid UTF-8
+/// (e.g. enums, whose binary form is their label) and to raw bytes otherwise.
+pub fn decode_binary(type_id: u32, data: &[u8]) -> Result<PgValue> {
+    let value = match type_id {
+        BOOL => PgValue::Bool(fixed::<1>(type_id, data)?[0] != 0),
+        INT2 => PgValue::Int(i16::from_be_bytes(fixed(type_id, data)?) as i64),
+        INT4 => PgValue::Int(i32::from_be_bytes(fixed(type_id, data)?) as i64),
+        INT8 => PgValue::Int(i64::from_be_bytes(fixed(type_id, data)?)),
+        OID => PgValue::Int(u32::from_be_bytes(fixed(type_id, data)?) as i64),
+        FLOAT4 => {
+            let v = f32::from_be_bytes(fixed(type_id, data)?);
+            // Go through the shortest decimal form so 0.1f32 stays 0.1
+            PgValue::Float(v.to_string().parse().unwrap_or(v as f64))
+        }
+        FLOAT8 => PgValue::Float(f64::from_be_bytes(fixed(type_id, data)?)),
+        NUMERIC => PgValue::Numeric(decode_numeric(data)?),
+        TEXT | VARCHAR | BPCHAR => PgValue::Text(String::from_utf8_lossy(data).to_string()),
+        BYTEA => PgValue::Bytea(data.to_vec()),
+        UUID => PgValue::Uuid(fixed(type_id, data)?),
+        DATE => match i32::from_be_bytes(fixed(type_id, data)?) {
+            i32::MAX => PgValue::Text("infinity".to_string()),
+            i32::MIN => PgValue::Text("-infinity".to_string()),
+            days => PgValue::Date(
+                pg_epoch()
+                    .date()
+                    .checked_add_signed(Duration::days(days as i64))
+                    .ok_or_else(|| anyhow!("Date out of range: {} days", days))?,
+            ),
+        },
+        TIME => {
+            let micros = i64::from_be_bytes(fixed(type_id, data)?);
+            let invalid = || anyhow!("Invalid time value: {} microseconds", micros);
+            if !(0..=86_400_000_000).contains(&micros) {
+                return Err(invalid());
+            }
+            let secs = (micros / 1_000_000) as u32;
+            let nanos = (micros % 1_000_000) as u32 * 1000;
+            PgValue::Time(NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).ok_or_else(invalid)?)
+        }
+        TIMESTAMP | TIMESTAMPTZ => match i64::from_be_bytes(fixed(type_id, data)?) {
+            i64::MAX => PgValue::Text("infinity".to_string()),
+            i64::MIN => PgValue::Text("-infinity".to_string()),
+            micros => {
+                let ts = pg_epoch()
+                    .checked_add_signed(Duration::microseconds(micros))
+                    .ok_or_else(|| anyhow!("Timestamp out of range: {} microseconds", micros))?;
+                if type_id == TIMESTAMPTZ {
+                    PgValue::TimestampTz(ts.and_utc())
+                } else {
+                    PgValue::Timestamp(ts)
+                }
+            }
+        },
+        INTERVAL => {
+            let bytes: [u8; 16] = fixed(type_id, data)?;
+            PgValue::Interval {
+                microseconds: i64::from_be_bytes(bytes[0..8].try_into()?),
+                days: i32::from_be_bytes(bytes[8..12].try_into()?),
+                months: i32::from_be_bytes(bytes[12..16].try_into()?),
+            }
+        }
+        JSON | JSONB => {
+            let text = if type_id == JSONB {
+                // jsonb is prefixed with a format version byte
+                match data.split_first() {
+                    Some((1, rest)) => rest,
+                    _ => return Err(anyhow!("Unsupported jsonb binary format")),
+                }
+            } else {
+                data
+            };
+            match serde_json::from_slice(text) {
+                Ok(json) => PgValue::Json(json),
+                Err(_) => PgValue::Text(String::from_utf8_lossy(text).to_string()),
+            }
+        }
+        _ if ARRAY_TYPES.contains(&type_id) => decode_array(data)?,
+        _ => match std::str::from_utf8(data) {
+            Ok(s) => PgValue::Text(s.to_string()),
+            Err(_) => PgValue::Bytea(data.to_vec()),
+        },
+    };
+
+    Ok(value)
+}
+
+/// Parse a text-format ('t') column value of the given type into its typed variant.
+///
+/// Values that don't parse as their type (`infinity` timestamps, BC dates, intervals
+/// in a non-default IntervalStyle) and types without a parser are kept as `Text`.
+pub fn decode_text(type_id: u32, text: &str) -> PgValue {
+    let value = match type_id {
+        BOOL => match text {
+            "t" | "true" => Some(PgValue::Bool(true)),
+            "f" | "false" => Some(PgValue::Bool(false)),
+            _ => None,
+        },
+        INT2 | INT4 | INT8 | OID => text.parse().ok().map(PgValue::Int),
+        FLOAT4 | FLOAT8 => text.parse().ok().map(PgValue::Float),
+        NUMERIC => Some(PgValue::Numeric(text.to_string())),
+        BYTEA => text.strip_prefix("\\x").and_then(decode_hex).map(PgValue::Bytea),
+        UUID => decode_hex(&text.replace('-', "")).and_then(|bytes| bytes.try_into().ok()).map(PgValue::Uuid),
+        DATE => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().map(PgValue::Date),
+        TIME => NaiveTime::parse_from_str(text, "%H:%M:%S%.f").ok().map(PgValue::Time),
+        TIMESTAMP => NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .map(PgValue::Timestamp),
+        TIMESTAMPTZ => DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z")
+            .ok()
+            .map(|ts| PgValue::TimestampTz(ts.with_timezone(&Utc))),
+        INTERVAL => parse_interval(text),
+        JSON | JSONB => serde_json::from_str(text).ok().map(PgValue::Json),
+        _ => array_element_type(type_id).and_then(|element_type| parse_array(element_type, text)),
+    };
+
+    value.unwrap_or_else(|| PgValue::Text(text.to_string()))
+}
+
+/// Decode a string of hex digit pairs
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Parse an interval in the default `postgres` IntervalStyle, e.g. `1 year 2 mons -3 days 04:05:06.5`
+fn parse_interval(text: &str) -> Option<PgValue> {
+    let (mut months, mut days, mut microseconds) = (0i32, 0i32, 0i64);
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token.contains(':') {
+            microseconds = parse_interval_time(token)?;
+            continue;
+        }
+        let n: i32 = token.parse().ok()?;
+        match tokens.next()? {
+            "year" | "years" => months = months.checked_add(n.checked_mul(12)?)?,
+            "mon" | "mons" => months = months.checked_add(n)?,
+            "day" | "days" => days = n,
+            _ => return None,
+        }
+    }
+
+    Some(PgValue::Interval { months, days, microseconds })
+}
+
+/// Parse the `[-]HH:MM:SS[.ffffff]` part of an interval into microseconds
+fn parse_interval_time(token: &str) -> Option<i64> {
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    };
+    let mut parts = token.splitn(3, ':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds = parts.next()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let seconds: i64 = seconds.parse().ok()?;
+    if fraction.len() > 6 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let micros: i64 = format!("{:0<6}", fraction).parse().ok()?;
+
+    let total = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60 + seconds)?
+        .checked_mul(1_000_000)?
+        .checked_add(micros)?;
+    Some(if negative { -total } else { total })
+}
+
+/// Parse an array literal such as `{1,NULL,"a b"}` or `{{1,2},{3,4}}`
+fn parse_array(element_type: u32, text: &str) -> Option<PgValue> {
+    // Arrays with non-default lower bounds are prefixed with their bounds, e.g. `[0:1]={1,2}`
+    let text = if text.starts_with('[') { text.split_once('=')?.1 } else { text };
+    let mut chars = text.chars().peekable();
+    let value = parse_array_level(element_type, &mut chars, 0)?;
+    chars.all(char::is_whitespace).then_some(value)
+}
+
+/// Parse one `{...}` level of an array literal
+fn parse_array_level(element_type: u32, chars: &mut Peekable<Chars>, depth: usize) -> Option<PgValue> {
+    // PostgreSQL arrays have at most MAXDIM (6) dimensions
+    if depth >= 6 || chars.next()? != '{' {
+        return None;
+    }
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Some(PgValue::Array(Vec::new()));
+    }
+
+    let mut items = Vec::new();
+    loop {
+        skip_whitespace(chars);
+        let item = match chars.peek()? {
+            '{' => Some(parse_array_level(element_type, chars, depth + 1)?),
+            '"' => {
+                chars.next();
+                let mut element = String::new();
+                loop {
+                    match chars.next()? {
+                        '\\' => element.push(chars.next()?),
+                        '"' => break,
+                        c => element.push(c),
+                    }
+                }
+                Some(decode_text(element_type, &element))
+            }
+            _ => {
+                let mut element = String::new();
+                while let Some(c) = chars.next_if(|c| *c != ',' && *c != '}') {
+                    element.push(if c == '\\' { chars.next()? } else { c });
+                }
+                // A bare NULL is a null element; a quoted "NULL" is the string
+                match element.trim_end() {
+                    null if null.eq_ignore_ascii_case("NULL") => None,
+                    element => Some(decode_text(element_type, element)),
+                }
+            }
+        };
+        items.push(item);
+
+        skip_whitespace(chars);
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(PgValue::Array(items)),
+            _ => return None,
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Midnight on 2000-01-01, the origin of PostgreSQL's date and timestamp encodings
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .expect("PostgreSQL epoch is a valid date")
+}
+
+/// Check that a fixed-width value has exactly `N` bytes
+fn fixed<const N: usize>(type_id: u32, data: &[u8]) -> Result<[u8; N]> {
+    data.try_into().map_err(|_| {
+        anyhow!(
+            "Invalid binary value for type {}: expected {} bytes, got {}",
+            type_id,
+            N,
+            data.len()
+        )
+    })
+}
+
+/// Decode the binary numeric format into its exact decimal string
+fn decode_numeric(data: &[u8]) -> Result<String> {
+    if data.len() < 8 {
+        return Err(anyhow!("Invalid binary numeric length: {}", data.len()));
+    }
+
+    let ndigits = u16::from_be_bytes(data[0..2].try_into()?) as usize;
+    let weight = i16::from_be_bytes(data[2..4].try_into()?) as i64;
+    let sign = u16::from_be_bytes(data[4..6].try_into()?);
+    let dscale = u16::from_be_bytes(data[6..8].try_into()?) as usize;
+
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    if data.len() != 8 + ndigits * 2 {
+        return Err(anyhow!("Invalid binary numeric length: {}", data.len()));
+    }
+    // Base-10000 digits; digit k has weight `weight - k`
+    let digits: Vec<i64> = data[8..]
+        .chunks_exact(2)
+        .map(|c| i16::from_be_bytes([c[0], c[1]]) as i64)
+        .collect();
+    let digit = |k: i64| {
+        if k >= 0 && (k as usize) < digits.len() {
+            digits[k as usize]
+        } else {
+            0
+        }
+    };
+
+    let mut out = String::new();
+    if sign == 0x4000 {
+        out.push('-');
+    }
+
+    if weight < 0 {
+        out.push('0');
+    } else {
+        out.push_str(&digit(0).to_string());
+        for k in 1..=weight {
+            out.push_str(&format!("{:04}", digit(k)));
+        }
+    }
+
+    if dscale > 0 {
+        let mut frac = String::new();
+        let mut k = weight + 1;
+        while frac.len() < dscale {
+            frac.push_str(&format!("{:04}", digit(k)));
+            k += 1;
+        }
+        frac.truncate(dscale);
+        out.push('.');
+        out.push_str(&frac);
+    }
+
+    Ok(out)
+}
+
+/// Decode the binary array format (any dimension, any element type)
+fn decode_array(data: &[u8]) -> Result<PgValue> {
+    let read_i32 = |pos: usize| -> Result<i32> {
+        data.get(pos..pos + 4)
+            .map(|b| i32::from_be_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| anyhow!("Truncated binary array"))
+    };
+
+    let ndim = read_i32(0)?;
+    let _has_nulls = read_i32(4)?;
+    let element_type = read_i32(8)? as u32;
+    // PostgreSQL arrays have at most MAXDIM (6) dimensions
+    if !(0..=6).contains(&ndim) {
+        return Err(anyhow!("Invalid array dimension count: {}", ndim));
+    }
+
+    let mut pos = 12;
+    let mut dims = Vec::with_capacity(ndim as usize);
+    for _ in 0..ndim {
+        let size = read_i32(pos)?;
+        if size < 0 {
+            return Err(anyhow!("Invalid array dimension size: {}", size));
+        }
+        dims.push(size as usize);
+        // Lower bound is not needed to rebuild the nesting
+        pos += 8;
+    }
+
+    if dims.is_empty() || dims.contains(&0) {
+        return Ok(PgValue::Array(Vec::new()));
+    }
+    let total = dims
+        .iter()
+        .try_fold(1usize, |total, size| total.checked_mul(*size))
+        .ok_or_else(|| anyhow!("Invalid array dimensions: {:?}", dims))?;
+    let mut elements = Vec::with_capacity(total.min(data.len()));
+    for _ in 0..total {
+        let len = read_i32(pos)?;
+        pos += 4;
+        if len < 0 {
+            elements.push(None);
+            continue;
+        }
+        let bytes = data
+            .get(pos..pos + len as usize)
+            .ok_or_else(|| anyhow!("Truncated binary array element"))?;
+        elements.push(Some(decode_binary(element_type, bytes)?));
+        pos += len as usize;
+    }
+
+    Ok(nest(&dims, &mut elements.into_iter()))
+}
+
+/// Rebuild nested arrays from the flat, row-major element list
+fn nest(dims: &[usize], elements: &mut impl Iterator<Item = Option<PgValue>>) -> PgValue {
+    match dims.split_first() {
+        None => PgValue::Array(Vec::new()),
+        Some((size, [])) => PgValue::Array(elements.take(*size).collect()),
+        Some((size, rest)) => PgValue::Array((0..*size).map(|_| Some(nest(rest, elements))).collect()),
+    }
+}
@@ -0,0 +1,91 @@
+use pgoutput_stream::admin::*;
+
+/// Tests parsing of table specifications given on the command line.
+/// Verifies bare names, column lists, row filters and malformed specifications.
+#[test]
+fn test_table_spec_from_str() {
+    assert_eq!(
+        TableSpec::from_str("orders").unwrap(),
+        TableSpec {
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            columns: None,
+            row_filter: None,
+        }
+    );
+    assert_eq!(
+        TableSpec::from_str("sales.orders (id, status) where status <> 'draft'").unwrap(),
+        TableSpec {
+            schema: "sales".to_string(),
+            table: "orders".to_string(),
+            columns: Some(vec!["id".to_string(), "status".to_string()]),
+            row_filter: Some("status <> 'draft'".to_string()),
+        }
+    );
+    assert!(TableSpec::from_str("sales.orders (id, status").is_err());
+    assert!(TableSpec::from_str("sales.orders ()").is_err());
+    assert!(TableSpec::from_str("sales.orders WHERE ").is_err());
+}
+
+/// Tests rendering of table specifications in publication statements.
+/// Verifies identifiers are quoted and the row filter is parenthesized.
+#[test]
+fn test_table_spec_to_sql() {
+    let spec = TableSpec::from_str("public.Orders (id) WHERE qty > 0").unwrap();
+    assert_eq!(spec.to_sql(), "\"public\".\"Orders\" (\"id\") WHERE (qty > 0)");
+}
+
+/// Tests construction of CREATE PUBLICATION.
+/// Verifies table lists, FOR ALL TABLES, the publish option and invalid combinations.
+#[test]
+fn test_build_create_publication() {
+    let tables = vec![
+        TableSpec::from_str("public.users").unwrap(),
+        TableSpec::from_str("public.orders (id, status)").unwrap(),
+    ];
+    assert_eq!(
+        build_create_publication("my_pub", &tables, false, None).unwrap(),
+        "CREATE PUBLICATION \"my_pub\" FOR TABLE \"public\".\"users\", \"public\".\"orders\" (\"id\", \"status\")"
+    );
+    assert_eq!(
+        build_create_publication("my_pub", &[], true, Some("insert, UPDATE")).unwrap(),
+        "CREATE PUBLICATION \"my_pub\" FOR ALL TABLES WITH (publish = 'insert, update')"
+    );
+    assert_eq!(build_create_publication("my_pub", &[], false, None).unwrap(), "CREATE PUBLICATION \"my_pub\"");
+
+    assert!(build_create_publication("my_pub", &tables, true, None).is_err());
+    assert!(build_create_publication("my_pub", &[], true, Some("insert,upsert")).is_err());
+}
+
+/// Tests construction of ALTER PUBLICATION statements.
+/// Verifies adding and dropping tables together, replacing the table list and
+/// rejection of conflicting or empty changes.
+#[test]
+fn test_build_alter_publication() {
+    let add = vec![TableSpec::from_str("public.orders WHERE paid").unwrap()];
+    let drop = vec![TableSpec::from_str("public.users").unwrap()];
+    assert_eq!(
+        build_alter_publication("my_pub", &add, &drop, &[], Some("insert")).unwrap(),
+        vec![
+            "ALTER PUBLICATION \"my_pub\" ADD TABLE \"public\".\"orders\" WHERE (paid)",
+            "ALTER PUBLICATION \"my_pub\" DROP TABLE \"public\".\"users\"",
+            "ALTER PUBLICATION \"my_pub\" SET (publish = 'insert')",
+        ]
+    );
+    assert_eq!(
+        build_alter_publication("my_pub", &[], &[], &drop, None).unwrap(),
+        vec!["ALTER PUBLICATION \"my_pub\" SET TABLE \"public\".\"users\""]
+    );
+
+    assert!(build_alter_publication("my_pub", &add, &[], &drop, None).is_err());
+    assert!(build_alter_publication("my_pub", &[], &add, &[], None).is_err());
+    assert!(build_alter_publication("my_pub", &[], &[], &[], None).is_err());
+}
+
+/// Tests human-readable byte counts for retained WAL.
+#[test]
+fn test_format_bytes() {
+    assert_eq!(format_bytes(512), "512 bytes");
+    assert_eq!(format_bytes(16 * 1024 * 1024), "16.0 MiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GiB");
+}
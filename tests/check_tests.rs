@@ -0,0 +1,88 @@
+use pgoutput_stream::check::*;
+use pgoutput_stream::decoder::ReplicaIdentity;
+use pgoutput_stream::replication::ReplicationOptions;
+
+fn table(replica_identity: ReplicaIdentity, has_primary_key: bool) -> TableFacts {
+    TableFacts {
+        schema: "public".to_string(),
+        table: "Orders".to_string(),
+        replica_identity,
+        has_primary_key,
+        can_select: true,
+    }
+}
+
+/// Tests the wal_level check.
+#[test]
+fn test_check_wal_level() {
+    assert_eq!(check_wal_level("logical").status, CheckStatus::Pass);
+
+    let result = check_wal_level("replica");
+    assert_eq!(result.status, CheckStatus::Fail);
+    assert!(result.detail.contains("'replica'"));
+    assert!(result.fix.unwrap().contains("ALTER SYSTEM SET wal_level = logical"));
+}
+
+/// Tests the server version check against the requested protocol version and options.
+/// Verifies the newest unmet requirement is reported.
+#[test]
+fn test_check_server_version() {
+    assert_eq!(check_server_version(100000, &ReplicationOptions::default()).status, CheckStatus::Pass);
+    assert_eq!(check_server_version(90600, &ReplicationOptions::default()).status, CheckStatus::Fail);
+
+    let options = ReplicationOptions {
+        protocol_version: 4,
+        binary: true,
+        ..Default::default()
+    };
+    assert_eq!(check_server_version(160002, &options).status, CheckStatus::Pass);
+    let result = check_server_version(140011, &options);
+    assert_eq!(result.status, CheckStatus::Fail);
+    assert_eq!(result.detail, "protocol version 4 needs PostgreSQL 16+, the server is version 14");
+
+    let options = ReplicationOptions {
+        messages: true,
+        ..Default::default()
+    };
+    let result = check_server_version(130004, &options);
+    assert_eq!(result.status, CheckStatus::Fail);
+    assert!(result.detail.starts_with("--messages needs PostgreSQL 14+"));
+}
+
+/// Tests the replica identity check of published tables.
+/// Verifies tables without a usable identity fail only when UPDATE or DELETE is published,
+/// and that the suggested fix quotes the table name.
+#[test]
+fn test_check_replica_identity() {
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Default, true), true).status, CheckStatus::Pass);
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Full, false), true).status, CheckStatus::Pass);
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Index, false), true).status, CheckStatus::Pass);
+
+    let result = check_replica_identity(&table(ReplicaIdentity::Default, false), true);
+    assert_eq!(result.status, CheckStatus::Fail);
+    assert_eq!(
+        result.fix.as_deref(),
+        Some("ALTER TABLE \"public\".\"Orders\" REPLICA IDENTITY FULL; (or add a primary key)")
+    );
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Nothing, true), true).status, CheckStatus::Fail);
+
+    // Insert-only publications never need the old row
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Default, false), false).status, CheckStatus::Warn);
+    assert_eq!(check_replica_identity(&table(ReplicaIdentity::Nothing, true), false).status, CheckStatus::Pass);
+}
+
+/// Tests the printed form of check results and detection of failures.
+#[test]
+fn test_check_result_display() {
+    let results = vec![
+        CheckResult::pass("wal_level", "logical"),
+        CheckResult::fail("replication privilege", "app lacks the REPLICATION attribute", "ALTER ROLE app WITH REPLICATION;"),
+    ];
+    assert_eq!(results[0].to_string(), "[PASS] wal_level: logical");
+    assert_eq!(
+        results[1].to_string(),
+        "[FAIL] replication privilege: app lacks the REPLICATION attribute\n       Fix: ALTER ROLE app WITH REPLICATION;"
+    );
+    assert!(has_failures(&results));
+    assert!(!has_failures(&results[..1]));
+}
@@ -0,0 +1,164 @@
+use pgoutput_stream::decoder::*;
+use pgoutput_stream::value::{decode_binary, decode_text};
+use proptest::prelude::*;
+
+/// Message type bytes the decoder understands
+const MESSAGE_TYPES: &[u8] = b"BCRIUDSEcAbPKrpTOYM";
+
+/// Type OIDs with a binary decoder, plus an array type and an unknown OID
+const TYPE_IDS: &[u32] = &[
+    16, 17, 20, 21, 23, 25, 26, 114, 700, 701, 1007, 1042, 1043, 1082, 1083, 1114, 1184, 1186,
+    1700, 2950, 3802, 99999,
+];
+
+/// RELATION message for relation 1 with one column of each type in TYPE_IDS
+fn relation_message() -> Vec<u8> {
+    let mut data = vec![b'R'];
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data.extend_from_slice(b"public\0");
+    data.extend_from_slice(b"fuzz\0");
+    data.push(b'd');
+    data.extend_from_slice(&(TYPE_IDS.len() as u16).to_be_bytes());
+    for (i, type_id) in TYPE_IDS.iter().enumerate() {
+        data.push(0);
+        data.extend_from_slice(format!("c{}\0", i).as_bytes());
+        data.extend_from_slice(&type_id.to_be_bytes());
+        data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+    data
+}
+
+/// Decoder that already knows relation 1, so data messages reach the tuple decoder
+fn primed_decoder() -> Decoder {
+    let mut decoder = Decoder::new();
+    decoder.decode_pgoutput_message(&relation_message()).unwrap();
+    decoder
+}
+
+/// Arbitrary TupleData for relation 1: each column is NULL, unchanged, text or binary
+fn tuple_data() -> impl Strategy<Value = Vec<u8>> {
+    let column = prop_oneof![
+        Just(vec![b'n']),
+        Just(vec![b'u']),
+        prop::collection::vec(any::<u8>(), 0..24).prop_map(|bytes| {
+            let mut data = vec![b't'];
+            data.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            data.extend_from_slice(&bytes);
+            data
+        }),
+        prop::collection::vec(any::<u8>(), 0..24).prop_map(|bytes| {
+            let mut data = vec![b'b'];
+            data.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            data.extend_from_slice(&bytes);
+            data
+        }),
+        // Claims more bytes than follow
+        any::<u32>().prop_map(|length| {
+            let mut data = vec![b't'];
+            data.extend_from_slice(&length.to_be_bytes());
+            data
+        }),
+    ];
+    prop::collection::vec(column, 0..TYPE_IDS.len() + 2).prop_map(|columns| {
+        let mut data = (columns.len() as u16).to_be_bytes().to_vec();
+        for column in columns {
+            data.extend_from_slice(&column);
+        }
+        data
+    })
+}
+
+proptest! {
+    /// Tests that arbitrary bytes never panic the decoder, inside or outside a stream block.
+    #[test]
+    fn decode_arbitrary_bytes_never_panics(data in prop::collection::vec(any::<u8>(), 0..512)) {
+        let mut decoder = primed_decoder();
+        let _ = decoder.decode_pgoutput_message(&data);
+        let _ = decoder.decode_streamed_pgoutput_message(&data);
+    }
+
+    /// Tests that arbitrary bodies behind every known message type never panic the decoder.
+    #[test]
+    fn decode_arbitrary_message_body_never_panics(
+        message_type in prop::sample::select(MESSAGE_TYPES),
+        body in prop::collection::vec(any::<u8>(), 0..256),
+    ) {
+        let mut data = vec![message_type];
+        data.extend_from_slice(&body);
+
+        let mut decoder = primed_decoder();
+        let _ = decoder.decode_pgoutput_message(&data);
+        let _ = decoder.decode_streamed_pgoutput_message(&data);
+    }
+
+    /// Tests that INSERT/UPDATE/DELETE with arbitrary, possibly truncated tuple data never
+    /// panic, and that any decoded value can be rendered as text and JSON.
+    #[test]
+    fn decode_arbitrary_tuples_never_panics(
+        message_type in prop::sample::select(b"IUD".to_vec()),
+        kind in prop::sample::select(b"NKO".to_vec()),
+        tuple in tuple_data(),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let mut data = vec![message_type];
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.push(kind);
+        data.extend_from_slice(&tuple);
+
+        let mut decoder = primed_decoder();
+        for message in [&data[..], &data[..cut.index(data.len() + 1)]] {
+            if let Ok(Some(change)) = decoder.decode_pgoutput_message(message) {
+                let _ = serde_json::to_string(&change);
+                if let Change::Insert { new_tuple, .. } = change {
+                    for value in new_tuple.values().flatten() {
+                        let _ = value.to_string();
+                        let _ = value.to_json();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tests that arbitrary binary column values never panic the value decoder.
+    #[test]
+    fn decode_binary_never_panics(
+        type_id in prop::sample::select(TYPE_IDS),
+        data in prop::collection::vec(any::<u8>(), 0..64),
+    ) {
+        if let Ok(value) = decode_binary(type_id, &data) {
+            let _ = value.to_string();
+            let _ = value.to_json();
+        }
+    }
+
+    /// Tests that arbitrary text values, including array-like literals, never panic the text parser.
+    #[test]
+    fn decode_text_never_panics(
+        type_id in prop::sample::select(TYPE_IDS),
+        text in prop_oneof![any::<String>(), "[{}\\[\\]:=,\"\\\\ a-z0-9NUL.x-]{0,32}"],
+    ) {
+        let value = decode_text(type_id, &text);
+        let _ = value.to_string();
+        let _ = value.to_json();
+    }
+
+    /// Tests that binary arrays with arbitrary headers never panic or allocate unboundedly.
+    #[test]
+    fn decode_binary_array_headers_never_panic(
+        ndim in -1i32..8,
+        dims in prop::collection::vec((any::<i32>(), any::<i32>()), 0..8),
+        elements in prop::collection::vec(any::<u8>(), 0..64),
+    ) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&ndim.to_be_bytes());
+        data.extend_from_slice(&0i32.to_be_bytes());
+        data.extend_from_slice(&23u32.to_be_bytes());
+        for (size, lower_bound) in dims {
+            data.extend_from_slice(&size.to_be_bytes());
+            data.extend_from_slice(&lower_bound.to_be_bytes());
+        }
+        data.extend_from_slice(&elements);
+
+        let _ = decode_binary(1007, &data);
+    }
+}
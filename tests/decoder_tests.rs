@@ -1,20 +1,23 @@
 use pgoutput_stream::decoder::*;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::value::PgValue;
 
 /// Tests decoding of BEGIN transaction messages from the pgoutput protocol.
-/// Verifies that LSN (Log Sequence Number), timestamp, and transaction ID (xid) are correctly parsed.
+/// Verifies that the final LSN (Log Sequence Number), timestamp, and transaction ID (xid) are correctly parsed.
 #[test]
 fn test_decode_begin() {
+    let mut decoder = Decoder::new();
     // BEGIN message format: 'B' + LSN(8) + timestamp(8) + xid(4)
     let mut data = vec![b'B'];
     data.extend_from_slice(&0x0000000001234567u64.to_be_bytes()); // LSN
     data.extend_from_slice(&123456789i64.to_be_bytes());           // timestamp
     data.extend_from_slice(&999u32.to_be_bytes());                 // xid
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
-        Some(Change::Begin { lsn, timestamp, xid }) => {
-            assert_eq!(lsn, "0/1234567");
+        Some(Change::Begin { final_lsn, timestamp, xid, .. }) => {
+            assert_eq!(final_lsn, Lsn(0x1234567));
             assert_eq!(timestamp, 123456789);
             assert_eq!(xid, 999);
         }
@@ -23,37 +26,78 @@ fn test_decode_begin() {
 }
 
 /// Tests decoding of COMMIT transaction messages from the pgoutput protocol.
-/// Verifies that commit LSN and timestamp are correctly extracted.
+/// Verifies that flags, commit LSN, end LSN and timestamp are correctly extracted.
 #[test]
 fn test_decode_commit() {
+    let mut decoder = Decoder::new();
     // COMMIT message format: 'C' + flags(1) + LSN(8) + end_lsn(8) + timestamp(8)
     let mut data = vec![b'C', 0]; // 'C' + flags
     data.extend_from_slice(&0x0000000001234567u64.to_be_bytes()); // LSN
     data.extend_from_slice(&0x0000000001234568u64.to_be_bytes()); // end LSN
     data.extend_from_slice(&987654321i64.to_be_bytes());           // timestamp
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
-        Some(Change::Commit { lsn, timestamp }) => {
-            assert_eq!(lsn, "0/1234567");
+        Some(Change::Commit { flags, lsn, end_lsn, timestamp, xid }) => {
+            assert_eq!(flags, 0);
+            assert_eq!(lsn, Lsn(0x1234567));
+            assert_eq!(end_lsn, Lsn(0x1234568));
             assert_eq!(timestamp, 987654321);
+            // No BEGIN was decoded first
+            assert_eq!(xid, None);
         }
         _ => panic!("Expected Commit change"),
     }
 }
 
+/// Tests that truncated messages fail with a structured DecodeError instead of panicking.
+/// Verifies the message type, field offset and the expected and available lengths,
+/// and that every proper prefix of a valid message is rejected.
+#[test]
+fn test_decode_truncated_message_error() {
+    let mut decoder = Decoder::new();
+    let mut data = vec![b'C', 0];
+    data.extend_from_slice(&0x0000000001234567u64.to_be_bytes());
+    data.extend_from_slice(&0x0000000001234568u64.to_be_bytes());
+    data.extend_from_slice(&987654321i64.to_be_bytes());
+
+    // 20 bytes passes the old `len < 17` check but ends inside the timestamp
+    let err = decoder.decode_pgoutput_message(&data[..21]).unwrap_err();
+    let err = err.downcast_ref::<DecodeError>().expect("DecodeError");
+    assert_eq!(
+        *err,
+        DecodeError {
+            message_type: 'C',
+            field: "commit timestamp",
+            offset: 18,
+            expected: 8,
+            available: 3,
+        }
+    );
+
+    // A string without its terminator is a short read too
+    let err = decoder.decode_pgoutput_message(b"O\0\0\0\0\0\0\0\x01origin").unwrap_err();
+    let err = err.downcast_ref::<DecodeError>().expect("DecodeError");
+    assert_eq!((err.field, err.offset, err.expected), ("origin name", 9, 7));
+
+    for len in 1..data.len() {
+        assert!(decoder.decode_pgoutput_message(&data[..len]).is_err(), "prefix of {} bytes", len);
+    }
+}
+
 /// Tests decoding of RELATION metadata messages that define table schemas.
 /// Verifies extraction of relation ID, schema name, table name, and column definitions
 /// including column names, PostgreSQL type IDs, and flags.
 #[test]
 fn test_decode_relation() {
+    let mut decoder = Decoder::new();
     // RELATION message format: 'R' + relation_id(4) + schema + table + replica_identity(1) + column_count(2) + columns
     let mut data = vec![b'R'];
     data.extend_from_slice(&12345u32.to_be_bytes()); // relation_id
     data.extend_from_slice(b"public\0");              // schema name
     data.extend_from_slice(b"users\0");               // table name
-    data.push(1);                                     // replica_identity
+    data.push(b'd');                                  // replica_identity
     data.extend_from_slice(&2u16.to_be_bytes());     // column count
     
     // Column 1: id (type_id=23, flags=1)
@@ -68,10 +112,10 @@ fn test_decode_relation() {
     data.extend_from_slice(&1043u32.to_be_bytes());  // type_id (varchar)
     data.extend_from_slice(&(-1i32).to_be_bytes());  // type_modifier
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
-        Some(Change::Relation { relation_id, schema, table, columns }) => {
+        Some(Change::Relation { relation_id, schema, table, columns, .. }) => {
             assert_eq!(relation_id, 12345);
             assert_eq!(schema, "public");
             assert_eq!(table, "users");
@@ -92,12 +136,13 @@ fn test_decode_relation() {
 /// Verifies that column values are correctly extracted and matched to column names.
 #[test]
 fn test_decode_insert() {
+    let mut decoder = Decoder::new();
     // First, register a relation so we can decode the insert
     let mut relation_data = vec![b'R'];
     relation_data.extend_from_slice(&100u32.to_be_bytes());
     relation_data.extend_from_slice(b"public\0");
     relation_data.extend_from_slice(b"test_table\0");
-    relation_data.push(1);
+    relation_data.push(b'd');
     relation_data.extend_from_slice(&2u16.to_be_bytes());
     relation_data.push(1);
     relation_data.extend_from_slice(b"id\0");
@@ -108,7 +153,7 @@ fn test_decode_insert() {
     relation_data.extend_from_slice(&1043u32.to_be_bytes());
     relation_data.extend_from_slice(&(-1i32).to_be_bytes());
     
-    decode_pgoutput_message(&relation_data).unwrap();
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
 
     // INSERT message format: 'I' + relation_id(4) + 'N' + tuple_data
     let mut data = vec![b'I'];
@@ -126,31 +171,92 @@ fn test_decode_insert() {
     data.extend_from_slice(&5u32.to_be_bytes());   // length
     data.extend_from_slice(b"Alice");               // value
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
-        Some(Change::Insert { relation_id, schema, table, new_tuple }) => {
+        Some(Change::Insert { relation_id, schema, table, new_tuple, .. }) => {
             assert_eq!(relation_id, 100);
             assert_eq!(schema, "public");
             assert_eq!(table, "test_table");
-            assert_eq!(new_tuple.get("id"), Some(&Some("1".to_string())));
-            assert_eq!(new_tuple.get("name"), Some(&Some("Alice".to_string())));
+            assert_eq!(new_tuple.get("id"), Some(&Some(PgValue::Int(1))));
+            assert_eq!(new_tuple.get("name"), Some(&Some(PgValue::Text("Alice".to_string()))));
         }
         _ => panic!("Expected Insert change"),
     }
 }
 
+/// Tests that decoded rows keep the relation's column order and expose its key.
+/// Verifies the replica identity setting, key column flags, key_columns on data
+/// events and Change::key().
+#[test]
+fn test_decode_column_order_and_key() {
+    let mut decoder = Decoder::new();
+    // Columns deliberately out of alphabetical order; (tenant, id) form the key
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&110u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"accounts\0");
+    relation_data.push(b'i');
+    relation_data.extend_from_slice(&4u16.to_be_bytes());
+    for (flags, name) in [(0u8, &b"zone\0"[..]), (1, b"tenant\0"), (0, b"balance\0"), (1, b"id\0")] {
+        relation_data.push(flags);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&25u32.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+
+    match decoder.decode_pgoutput_message(&relation_data).unwrap() {
+        Some(Change::Relation { replica_identity, columns, .. }) => {
+            assert_eq!(replica_identity, ReplicaIdentity::Index);
+            let keys: Vec<_> = columns.iter().filter(|c| c.is_key()).map(|c| c.name.as_str()).collect();
+            assert_eq!(keys, vec!["tenant", "id"]);
+        }
+        other => panic!("Expected Relation change, got {:?}", other),
+    }
+
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&110u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&4u16.to_be_bytes());
+    for value in [&b"eu"[..], b"acme", b"10", b"7"] {
+        data.push(b't');
+        data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        data.extend_from_slice(value);
+    }
+
+    let change = decoder.decode_pgoutput_message(&data).unwrap().unwrap();
+    match &change {
+        Change::Insert { new_tuple, key_columns, .. } => {
+            let names: Vec<_> = new_tuple.keys().map(String::as_str).collect();
+            assert_eq!(names, vec!["zone", "tenant", "balance", "id"]);
+            assert_eq!(key_columns, &vec!["tenant".to_string(), "id".to_string()]);
+        }
+        other => panic!("Expected Insert change, got {:?}", other),
+    }
+
+    let key = change.key().unwrap();
+    assert_eq!(key.len(), 2);
+    assert_eq!(key.get("tenant"), Some(&Some(PgValue::Text("acme".to_string()))));
+    assert_eq!(key.get("id"), Some(&Some(PgValue::Text("7".to_string()))));
+
+    let json = serde_json::to_string(&change).unwrap();
+    let zone = json.find("\"zone\"").unwrap();
+    let id = json.find("\"id\"").unwrap();
+    assert!(zone < id, "columns out of order in {}", json);
+}
+
 /// Tests decoding of INSERT operations that contain NULL values.
 /// Verifies that NULL indicators in the protocol are correctly identified
 /// and represented as None in the resulting tuple data.
 #[test]
 fn test_decode_insert_with_null() {
+    let mut decoder = Decoder::new();
     // Register relation
     let mut relation_data = vec![b'R'];
     relation_data.extend_from_slice(&101u32.to_be_bytes());
     relation_data.extend_from_slice(b"public\0");
     relation_data.extend_from_slice(b"nullable_table\0");
-    relation_data.push(1);
+    relation_data.push(b'd');
     relation_data.extend_from_slice(&2u16.to_be_bytes());
     relation_data.push(1);
     relation_data.extend_from_slice(b"id\0");
@@ -161,7 +267,7 @@ fn test_decode_insert_with_null() {
     relation_data.extend_from_slice(&1043u32.to_be_bytes());
     relation_data.extend_from_slice(&(-1i32).to_be_bytes());
     
-    decode_pgoutput_message(&relation_data).unwrap();
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
 
     // INSERT with NULL value
     let mut data = vec![b'I'];
@@ -173,35 +279,76 @@ fn test_decode_insert_with_null() {
     data.push(b'1');
     data.push(b'n'); // NULL indicator
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
         Some(Change::Insert { new_tuple, .. }) => {
-            assert_eq!(new_tuple.get("id"), Some(&Some("1".to_string())));
+            assert_eq!(new_tuple.get("id"), Some(&Some(PgValue::Int(1))));
             assert_eq!(new_tuple.get("email"), Some(&None));
         }
         _ => panic!("Expected Insert change"),
     }
 }
 
+/// Tests that text-format values are parsed by their column type in the decoder.
+/// Verifies numeric, jsonb and int4[] columns reach every format as typed values.
+#[test]
+fn test_decode_insert_typed_text_values() {
+    let mut decoder = Decoder::new();
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&106u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"typed_table\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&3u16.to_be_bytes());
+    for (name, type_id) in [(&b"amount\0"[..], 1700u32), (&b"doc\0"[..], 3802), (&b"tags\0"[..], 1007)] {
+        relation_data.push(0);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&type_id.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
+
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&106u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&3u16.to_be_bytes());
+    for value in [&b"12.50"[..], br#"{"a": 1}"#, b"{1,2}"] {
+        data.push(b't');
+        data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        data.extend_from_slice(value);
+    }
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Insert { new_tuple, .. }) => {
+            assert_eq!(new_tuple.get("amount"), Some(&Some(PgValue::Numeric("12.50".to_string()))));
+            let json = serde_json::to_value(&new_tuple).unwrap();
+            assert_eq!(json["doc"], serde_json::json!({"a": 1}));
+            assert_eq!(json["tags"], serde_json::json!([1, 2]));
+        }
+        other => panic!("Expected Insert change, got {:?}", other),
+    }
+}
+
 /// Tests decoding of UPDATE operations that include old tuple data.
 /// When REPLICA IDENTITY FULL is set, UPDATE messages include both old and new values.
 /// Verifies that both old and new tuple data are correctly parsed.
 #[test]
 fn test_decode_update_with_old_tuple() {
+    let mut decoder = Decoder::new();
     // Register relation
     let mut relation_data = vec![b'R'];
     relation_data.extend_from_slice(&102u32.to_be_bytes());
     relation_data.extend_from_slice(b"public\0");
     relation_data.extend_from_slice(b"users\0");
-    relation_data.push(1);
+    relation_data.push(b'd');
     relation_data.extend_from_slice(&1u16.to_be_bytes());
     relation_data.push(1);
     relation_data.extend_from_slice(b"name\0");
     relation_data.extend_from_slice(&1043u32.to_be_bytes());
     relation_data.extend_from_slice(&(-1i32).to_be_bytes());
     
-    decode_pgoutput_message(&relation_data).unwrap();
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
 
     // UPDATE message with old tuple: 'U' + relation_id(4) + 'O' + old_tuple + 'N' + new_tuple
     let mut data = vec![b'U'];
@@ -217,14 +364,14 @@ fn test_decode_update_with_old_tuple() {
     data.extend_from_slice(&5u32.to_be_bytes());
     data.extend_from_slice(b"Alice");
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
         Some(Change::Update { old_tuple, new_tuple, .. }) => {
             assert!(old_tuple.is_some());
             let old = old_tuple.unwrap();
-            assert_eq!(old.get("name"), Some(&Some("Bob".to_string())));
-            assert_eq!(new_tuple.get("name"), Some(&Some("Alice".to_string())));
+            assert_eq!(old.get("name"), Some(&Some(PgValue::Text("Bob".to_string()))));
+            assert_eq!(new_tuple.get("name"), Some(&Some(PgValue::Text("Alice".to_string()))));
         }
         _ => panic!("Expected Update change"),
     }
@@ -235,19 +382,20 @@ fn test_decode_update_with_old_tuple() {
 /// Verifies that the decoder handles missing old tuple data correctly.
 #[test]
 fn test_decode_update_without_old_tuple() {
+    let mut decoder = Decoder::new();
     // Register relation
     let mut relation_data = vec![b'R'];
     relation_data.extend_from_slice(&103u32.to_be_bytes());
     relation_data.extend_from_slice(b"public\0");
     relation_data.extend_from_slice(b"users\0");
-    relation_data.push(1);
+    relation_data.push(b'd');
     relation_data.extend_from_slice(&1u16.to_be_bytes());
     relation_data.push(1);
     relation_data.extend_from_slice(b"name\0");
     relation_data.extend_from_slice(&1043u32.to_be_bytes());
     relation_data.extend_from_slice(&(-1i32).to_be_bytes());
     
-    decode_pgoutput_message(&relation_data).unwrap();
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
 
     // UPDATE message without old tuple: 'U' + relation_id(4) + 'N' + new_tuple
     let mut data = vec![b'U'];
@@ -258,35 +406,78 @@ fn test_decode_update_without_old_tuple() {
     data.extend_from_slice(&5u32.to_be_bytes());
     data.extend_from_slice(b"Carol");
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
         Some(Change::Update { old_tuple, new_tuple, .. }) => {
             assert!(old_tuple.is_none());
-            assert_eq!(new_tuple.get("name"), Some(&Some("Carol".to_string())));
+            assert_eq!(new_tuple.get("name"), Some(&Some(PgValue::Text("Carol".to_string()))));
         }
         _ => panic!("Expected Update change"),
     }
 }
 
+/// Tests that unchanged TOAST columns ('u') in an UPDATE are kept apart from NULLs.
+/// Verifies the column is absent from the new tuple and listed in unchanged_columns.
+#[test]
+fn test_decode_update_unchanged_toast_column() {
+    let mut decoder = Decoder::new();
+    // Register relation with columns id, payload, note
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&105u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"documents\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&3u16.to_be_bytes());
+    for (name, type_id) in [(&b"id\0"[..], 23u32), (&b"payload\0"[..], 3802), (&b"note\0"[..], 25)] {
+        relation_data.push(0);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&type_id.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
+
+    // UPDATE: id = '7', payload unchanged, note NULL
+    let mut data = vec![b'U'];
+    data.extend_from_slice(&105u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&3u16.to_be_bytes());
+    data.push(b't');
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data.extend_from_slice(b"7");
+    data.push(b'u');
+    data.push(b'n');
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Update { new_tuple, unchanged_columns, .. }) => {
+            assert_eq!(new_tuple.get("id"), Some(&Some(PgValue::Int(7))));
+            assert_eq!(new_tuple.get("note"), Some(&None));
+            assert!(!new_tuple.contains_key("payload"));
+            assert_eq!(unchanged_columns, vec!["payload".to_string()]);
+        }
+        other => panic!("Expected Update change, got {:?}", other),
+    }
+}
+
 /// Tests decoding of DELETE operations from the replication stream.
 /// Verifies that the old tuple data (deleted row values) is correctly extracted
 /// and associated with the proper relation.
 #[test]
 fn test_decode_delete() {
+    let mut decoder = Decoder::new();
     // Register relation
     let mut relation_data = vec![b'R'];
     relation_data.extend_from_slice(&104u32.to_be_bytes());
     relation_data.extend_from_slice(b"public\0");
     relation_data.extend_from_slice(b"users\0");
-    relation_data.push(1);
+    relation_data.push(b'd');
     relation_data.extend_from_slice(&1u16.to_be_bytes());
     relation_data.push(1);
     relation_data.extend_from_slice(b"id\0");
     relation_data.extend_from_slice(&23u32.to_be_bytes());
     relation_data.extend_from_slice(&(-1i32).to_be_bytes());
     
-    decode_pgoutput_message(&relation_data).unwrap();
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
 
     // DELETE message: 'D' + relation_id(4) + 'K' or 'O' + old_tuple
     let mut data = vec![b'D'];
@@ -297,12 +488,12 @@ fn test_decode_delete() {
     data.extend_from_slice(&2u32.to_be_bytes());
     data.extend_from_slice(b"42");
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
         Some(Change::Delete { relation_id, old_tuple, .. }) => {
             assert_eq!(relation_id, 104);
-            assert_eq!(old_tuple.get("id"), Some(&Some("42".to_string())));
+            assert_eq!(old_tuple.get("id"), Some(&Some(PgValue::Int(42))));
         }
         _ => panic!("Expected Delete change"),
     }
@@ -313,8 +504,9 @@ fn test_decode_delete() {
 /// without panicking or producing invalid output.
 #[test]
 fn test_decode_empty_message() {
+    let mut decoder = Decoder::new();
     let data = vec![];
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     assert!(result.is_none());
 }
 
@@ -323,8 +515,9 @@ fn test_decode_empty_message() {
 /// without causing errors or crashes.
 #[test]
 fn test_decode_unknown_message_type() {
+    let mut decoder = Decoder::new();
     let data = vec![b'X']; // Unknown message type
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     assert!(result.is_none());
 }
 
@@ -333,16 +526,17 @@ fn test_decode_unknown_message_type() {
 /// that use the full 64-bit range.
 #[test]
 fn test_decode_begin_with_large_lsn() {
+    let mut decoder = Decoder::new();
     let mut data = vec![b'B'];
     data.extend_from_slice(&0xFFFFFFFFFFFFFFFFu64.to_be_bytes());
     data.extend_from_slice(&9999999999i64.to_be_bytes());
     data.extend_from_slice(&4294967295u32.to_be_bytes()); // max u32
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
-        Some(Change::Begin { lsn, timestamp, xid }) => {
-            assert_eq!(lsn, "FFFFFFFF/FFFFFFFF");
+        Some(Change::Begin { final_lsn, timestamp, xid, .. }) => {
+            assert_eq!(final_lsn, Lsn(0xFFFFFFFFFFFFFFFF));
             assert_eq!(timestamp, 9999999999);
             assert_eq!(xid, 4294967295);
         }
@@ -355,18 +549,19 @@ fn test_decode_begin_with_large_lsn() {
 /// and other special characters are correctly handled.
 #[test]
 fn test_decode_relation_with_special_characters() {
+    let mut decoder = Decoder::new();
     let mut data = vec![b'R'];
     data.extend_from_slice(&999u32.to_be_bytes());
     data.extend_from_slice(b"my_schema\0");
     data.extend_from_slice(b"table_with_underscores\0");
-    data.push(1);
+    data.push(b'd');
     data.extend_from_slice(&1u16.to_be_bytes());
     data.push(0);
     data.extend_from_slice(b"col_name\0");
     data.extend_from_slice(&1043u32.to_be_bytes());
     data.extend_from_slice(&(-1i32).to_be_bytes());
 
-    let result = decode_pgoutput_message(&data).unwrap();
+    let result = decoder.decode_pgoutput_message(&data).unwrap();
     
     match result {
         Some(Change::Relation { schema, table, .. }) => {
@@ -377,39 +572,97 @@ fn test_decode_relation_with_special_characters() {
     }
 }
 
+/// Tests that each Decoder keeps its own relation cache.
+/// Two streams may use the same relation OID for different tables, and a
+/// decoder that never saw the RELATION message must reject the data message.
+#[test]
+fn test_decoders_do_not_share_relations() {
+    fn relation(table: &[u8], column: &[u8]) -> Vec<u8> {
+        let mut data = vec![b'R'];
+        data.extend_from_slice(&16384u32.to_be_bytes());
+        data.extend_from_slice(b"public\0");
+        data.extend_from_slice(table);
+        data.push(b'd');
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.push(1);
+        data.extend_from_slice(column);
+        data.extend_from_slice(&25u32.to_be_bytes());
+        data.extend_from_slice(&(-1i32).to_be_bytes());
+        data
+    }
+
+    let mut insert = vec![b'I'];
+    insert.extend_from_slice(&16384u32.to_be_bytes());
+    insert.push(b'N');
+    insert.extend_from_slice(&1u16.to_be_bytes());
+    insert.push(b't');
+    insert.extend_from_slice(&1u32.to_be_bytes());
+    insert.extend_from_slice(b"x");
+
+    let mut first = Decoder::new();
+    let mut second = Decoder::new();
+    first.decode_pgoutput_message(&relation(b"users\0", b"name\0")).unwrap();
+    second.decode_pgoutput_message(&relation(b"orders\0", b"code\0")).unwrap();
+
+    match first.decode_pgoutput_message(&insert).unwrap() {
+        Some(Change::Insert { table, new_tuple, .. }) => {
+            assert_eq!(table, "users");
+            assert!(new_tuple.contains_key("name"));
+        }
+        other => panic!("Expected Insert change, got {:?}", other),
+    }
+    match second.decode_pgoutput_message(&insert).unwrap() {
+        Some(Change::Insert { table, new_tuple, .. }) => {
+            assert_eq!(table, "orders");
+            assert!(new_tuple.contains_key("code"));
+        }
+        other => panic!("Expected Insert change, got {:?}", other),
+    }
+    assert_eq!(second.get_relation_columns(16384).unwrap()[0].name, "code");
+
+    assert!(Decoder::new().decode_pgoutput_message(&insert).is_err());
+}
+
 /// Tests LSN extraction from Begin events
 #[test]
 fn test_get_lsn_from_begin() {
     let change = Change::Begin {
-        lsn: "0/1234567".to_string(),
+        final_lsn: Lsn(0x1234567),
         timestamp: 123456789,
         xid: 999,
+        origin: None,
     };
     
-    assert_eq!(change.get_lsn(), Some("0/1234567"));
+    assert_eq!(change.get_lsn(), Some(Lsn(0x1234567)));
 }
 
 /// Tests LSN extraction from Commit events
 #[test]
 fn test_get_lsn_from_commit() {
     let change = Change::Commit {
-        lsn: "0/9ABCDEF".to_string(),
+        flags: 0,
+        lsn: Lsn(0x9ABCDEF),
+        end_lsn: Lsn(0x9ABCDEF),
         timestamp: 987654321,
+        xid: None,
     };
     
-    assert_eq!(change.get_lsn(), Some("0/9ABCDEF"));
+    assert_eq!(change.get_lsn(), Some(Lsn(0x9ABCDEF)));
 }
 
 /// Tests that data events (Insert, Update, Delete) return None for LSN
 #[test]
 fn test_get_lsn_from_data_events() {
-    use std::collections::HashMap;
     
     let insert = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "test".to_string(),
-        new_tuple: HashMap::new(),
+        new_tuple: Tuple::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     assert_eq!(insert.get_lsn(), None);
     
@@ -418,7 +671,13 @@ fn test_get_lsn_from_data_events() {
         schema: "public".to_string(),
         table: "test".to_string(),
         old_tuple: None,
-        new_tuple: HashMap::new(),
+        old_key_only: false,
+        new_tuple: Tuple::new(),
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     assert_eq!(update.get_lsn(), None);
     
@@ -426,7 +685,11 @@ fn test_get_lsn_from_data_events() {
         relation_id: 100,
         schema: "public".to_string(),
         table: "test".to_string(),
-        old_tuple: HashMap::new(),
+        old_tuple: Tuple::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     assert_eq!(delete.get_lsn(), None);
 }
@@ -438,8 +701,567 @@ fn test_get_lsn_from_relation() {
         relation_id: 100,
         schema: "public".to_string(),
         table: "test".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns: vec![],
     };
     
     assert_eq!(change.get_lsn(), None);
 }
+
+/// Tests decoding of STREAM START and STREAM STOP messages (protocol v2).
+/// Verifies the streamed transaction id and first-segment flag.
+#[test]
+fn test_decode_stream_start_and_stop() {
+    let mut decoder = Decoder::new();
+    // STREAM START format: 'S' + xid(4) + first_segment(1)
+    let mut data = vec![b'S'];
+    data.extend_from_slice(&4242u32.to_be_bytes());
+    data.push(1);
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::StreamStart { xid, first_segment }) => {
+            assert_eq!(xid, 4242);
+            assert!(first_segment);
+        }
+        _ => panic!("Expected StreamStart change"),
+    }
+
+    assert!(matches!(decoder.decode_pgoutput_message(b"E").unwrap(), Some(Change::StreamStop)));
+}
+
+/// Tests decoding of STREAM COMMIT messages (protocol v2).
+/// Verifies xid, commit LSN, end LSN and commit timestamp.
+#[test]
+fn test_decode_stream_commit() {
+    let mut decoder = Decoder::new();
+    // STREAM COMMIT format: 'c' + xid(4) + flags(1) + commit_lsn(8) + end_lsn(8) + timestamp(8)
+    let mut data = vec![b'c'];
+    data.extend_from_slice(&4242u32.to_be_bytes());
+    data.push(0);
+    data.extend_from_slice(&0x0000000001234567u64.to_be_bytes());
+    data.extend_from_slice(&0x0000000001234600u64.to_be_bytes());
+    data.extend_from_slice(&555i64.to_be_bytes());
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::StreamCommit { xid, lsn, end_lsn, timestamp }) => {
+            assert_eq!(xid, 4242);
+            assert_eq!(lsn, Lsn(0x1234567));
+            assert_eq!(end_lsn, Lsn(0x1234600));
+            assert_eq!(timestamp, 555);
+        }
+        _ => panic!("Expected StreamCommit change"),
+    }
+}
+
+/// Tests decoding of STREAM ABORT messages (protocol v2).
+/// Verifies the top-level and subtransaction ids.
+#[test]
+fn test_decode_stream_abort() {
+    let mut decoder = Decoder::new();
+    // STREAM ABORT format: 'A' + xid(4) + subxid(4)
+    let mut data = vec![b'A'];
+    data.extend_from_slice(&4242u32.to_be_bytes());
+    data.extend_from_slice(&4243u32.to_be_bytes());
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::StreamAbort { xid, subxid }) => {
+            assert_eq!(xid, 4242);
+            assert_eq!(subxid, 4243);
+        }
+        _ => panic!("Expected StreamAbort change"),
+    }
+}
+
+/// Tests decoding of an INSERT inside a streamed block.
+/// Verifies that the xid prefix is consumed and carried on the change.
+#[test]
+fn test_decode_streamed_insert() {
+    let mut decoder = Decoder::new();
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&4242u32.to_be_bytes()); // xid prefix
+    relation_data.extend_from_slice(&700u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"big_table\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&1u16.to_be_bytes());
+    relation_data.push(1);
+    relation_data.extend_from_slice(b"id\0");
+    relation_data.extend_from_slice(&23u32.to_be_bytes());
+    relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+
+    decoder.decode_streamed_pgoutput_message(&relation_data).unwrap();
+
+    // Streamed INSERT format: 'I' + xid(4) + relation_id(4) + 'N' + tuple_data
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&4243u32.to_be_bytes()); // subtransaction xid
+    data.extend_from_slice(&700u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.push(b't');
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(b"42");
+
+    match decoder.decode_streamed_pgoutput_message(&data).unwrap() {
+        Some(Change::Insert { relation_id, table, new_tuple, xid, .. }) => {
+            assert_eq!(relation_id, 700);
+            assert_eq!(table, "big_table");
+            assert_eq!(new_tuple.get("id"), Some(&Some(PgValue::Int(42))));
+            assert_eq!(xid, Some(4243));
+        }
+        _ => panic!("Expected streamed Insert change"),
+    }
+}
+
+/// Tests decoding of a BEGIN PREPARE message (protocol v3).
+/// Verifies the prepare LSN, xid and GID are extracted.
+#[test]
+fn test_decode_begin_prepare() {
+    let mut decoder = Decoder::new();
+    // BEGIN PREPARE format: 'b' + prepare_lsn(8) + end_lsn(8) + prepare_time(8) + xid(4) + gid
+    let mut data = vec![b'b'];
+    data.extend_from_slice(&0x0000000001000000u64.to_be_bytes()); // prepare_lsn
+    data.extend_from_slice(&0x0000000001000100u64.to_be_bytes()); // end_lsn
+    data.extend_from_slice(&1234567890i64.to_be_bytes());
+    data.extend_from_slice(&900u32.to_be_bytes());
+    data.extend_from_slice(b"tx-900\0");
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::BeginPrepare { lsn, timestamp, xid, gid }) => {
+            assert_eq!(lsn, Lsn(0x1000000));
+            assert_eq!(timestamp, 1234567890);
+            assert_eq!(xid, 900);
+            assert_eq!(gid, "tx-900");
+        }
+        _ => panic!("Expected BeginPrepare change"),
+    }
+}
+
+/// Tests decoding of PREPARE and STREAM PREPARE messages.
+/// Both share the same layout and differ only in the message type.
+#[test]
+fn test_decode_prepare_and_stream_prepare() {
+    let mut decoder = Decoder::new();
+    // PREPARE format: 'P' + flags(1) + prepare_lsn(8) + end_lsn(8) + prepare_time(8) + xid(4) + gid
+    let mut body = vec![0];
+    body.extend_from_slice(&0x0000000001000000u64.to_be_bytes());
+    body.extend_from_slice(&0x0000000001000100u64.to_be_bytes());
+    body.extend_from_slice(&42i64.to_be_bytes());
+    body.extend_from_slice(&900u32.to_be_bytes());
+    body.extend_from_slice(b"tx-900\0");
+
+    let mut data = vec![b'P'];
+    data.extend_from_slice(&body);
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Prepare { lsn, timestamp, xid, gid }) => {
+            assert_eq!(lsn, Lsn(0x1000000));
+            assert_eq!(timestamp, 42);
+            assert_eq!(xid, 900);
+            assert_eq!(gid, "tx-900");
+        }
+        _ => panic!("Expected Prepare change"),
+    }
+
+    let mut data = vec![b'p'];
+    data.extend_from_slice(&body);
+    assert!(matches!(
+        decoder.decode_pgoutput_message(&data).unwrap(),
+        Some(Change::StreamPrepare { xid: 900, .. })
+    ));
+}
+
+/// Tests decoding of COMMIT PREPARED and ROLLBACK PREPARED messages.
+/// Verifies the GID and positions of each outcome.
+#[test]
+fn test_decode_commit_and_rollback_prepared() {
+    let mut decoder = Decoder::new();
+    // COMMIT PREPARED format: 'K' + flags(1) + commit_lsn(8) + end_lsn(8) + commit_time(8) + xid(4) + gid
+    let mut data = vec![b'K', 0];
+    data.extend_from_slice(&0x0000000002000000u64.to_be_bytes());
+    data.extend_from_slice(&0x0000000002000100u64.to_be_bytes());
+    data.extend_from_slice(&99i64.to_be_bytes());
+    data.extend_from_slice(&900u32.to_be_bytes());
+    data.extend_from_slice(b"tx-900\0");
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::CommitPrepared { lsn, timestamp, xid, gid }) => {
+            assert_eq!(lsn, Lsn(0x2000000));
+            assert_eq!(timestamp, 99);
+            assert_eq!(xid, 900);
+            assert_eq!(gid, "tx-900");
+        }
+        _ => panic!("Expected CommitPrepared change"),
+    }
+
+    // ROLLBACK PREPARED format: 'r' + flags(1) + prepare_end_lsn(8) + rollback_end_lsn(8)
+    //   + prepare_time(8) + rollback_time(8) + xid(4) + gid
+    let mut data = vec![b'r', 0];
+    data.extend_from_slice(&0x0000000001000100u64.to_be_bytes());
+    data.extend_from_slice(&0x0000000003000000u64.to_be_bytes());
+    data.extend_from_slice(&42i64.to_be_bytes());
+    data.extend_from_slice(&100i64.to_be_bytes());
+    data.extend_from_slice(&901u32.to_be_bytes());
+    data.extend_from_slice(b"tx-901\0");
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::RollbackPrepared { lsn, timestamp, prepare_end_lsn, prepare_timestamp, xid, gid }) => {
+            assert_eq!(lsn, Lsn(0x3000000));
+            assert_eq!(timestamp, 100);
+            assert_eq!(prepare_end_lsn, Lsn(0x1000100));
+            assert_eq!(prepare_timestamp, 42);
+            assert_eq!(xid, 901);
+            assert_eq!(gid, "tx-901");
+        }
+        _ => panic!("Expected RollbackPrepared change"),
+    }
+}
+
+/// Tests that truncated two-phase messages are rejected.
+#[test]
+fn test_decode_truncated_prepare() {
+    let mut decoder = Decoder::new();
+    assert!(decoder.decode_pgoutput_message(&[b'b', 0, 0, 0]).is_err());
+    assert!(decoder.decode_pgoutput_message(&[b'P', 0, 0]).is_err());
+    assert!(decoder.decode_pgoutput_message(&[b'r', 0]).is_err());
+}
+
+/// Tests decoding of binary ('b') tuple data.
+/// Verifies values are decoded according to the column types from the Relation message.
+#[test]
+fn test_decode_insert_binary_tuple() {
+    let mut decoder = Decoder::new();
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&800u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"accounts\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&3u16.to_be_bytes());
+    for (name, type_id) in [(&b"id\0"[..], 20u32), (b"active\0", 16), (b"name\0", 25)] {
+        relation_data.push(0);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&type_id.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
+
+    let mut data = vec![b'I'];
+    data.extend_from_slice(&800u32.to_be_bytes());
+    data.push(b'N');
+    data.extend_from_slice(&3u16.to_be_bytes());
+    data.push(b'b');
+    data.extend_from_slice(&8u32.to_be_bytes());
+    data.extend_from_slice(&9000000000i64.to_be_bytes());
+    data.push(b'b');
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data.push(1);
+    data.push(b't');
+    data.extend_from_slice(&3u32.to_be_bytes());
+    data.extend_from_slice(b"Eve");
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Insert { new_tuple, .. }) => {
+            assert_eq!(new_tuple.get("id"), Some(&Some(PgValue::Int(9000000000))));
+            assert_eq!(new_tuple.get("active"), Some(&Some(PgValue::Bool(true))));
+            assert_eq!(new_tuple.get("name"), Some(&Some(PgValue::Text("Eve".to_string()))));
+        }
+        _ => panic!("Expected Insert change"),
+    }
+}
+
+/// Tests decoding of TRUNCATE messages.
+/// Verifies the affected relations are resolved from the cache and option flags are decoded.
+#[test]
+fn test_decode_truncate() {
+    let mut decoder = Decoder::new();
+    for (relation_id, table) in [(900u32, &b"orders\0"[..]), (901, b"order_items\0")] {
+        let mut relation_data = vec![b'R'];
+        relation_data.extend_from_slice(&relation_id.to_be_bytes());
+        relation_data.extend_from_slice(b"public\0");
+        relation_data.extend_from_slice(table);
+        relation_data.push(b'd');
+        relation_data.extend_from_slice(&0u16.to_be_bytes());
+        decoder.decode_pgoutput_message(&relation_data).unwrap();
+    }
+
+    // TRUNCATE format: 'T' + relation_count(4) + options(1) + relation_id(4) * count
+    let mut data = vec![b'T'];
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.push(3); // CASCADE | RESTART IDENTITY
+    data.extend_from_slice(&900u32.to_be_bytes());
+    data.extend_from_slice(&901u32.to_be_bytes());
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Truncate { relations, cascade, restart_identity, xid, .. }) => {
+            assert_eq!(relations.len(), 2);
+            assert_eq!(relations[0].table, "orders");
+            assert_eq!(relations[1].relation_id, 901);
+            assert_eq!(relations[1].table, "order_items");
+            assert!(cascade);
+            assert!(restart_identity);
+            assert_eq!(xid, None);
+        }
+        _ => panic!("Expected Truncate change"),
+    }
+
+    // Relation count larger than the payload
+    let mut truncated = vec![b'T'];
+    truncated.extend_from_slice(&5u32.to_be_bytes());
+    truncated.push(0);
+    assert!(decoder.decode_pgoutput_message(&truncated).is_err());
+}
+
+/// Tests decoding of ORIGIN messages.
+/// Verifies the origin LSN and name are extracted.
+#[test]
+fn test_decode_origin() {
+    let mut decoder = Decoder::new();
+    // ORIGIN format: 'O' + origin_lsn(8) + name
+    let mut data = vec![b'O'];
+    data.extend_from_slice(&0x0000000300000010u64.to_be_bytes());
+    data.extend_from_slice(b"pg_16390\0");
+
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Origin { lsn, name }) => {
+            assert_eq!(lsn, Lsn(0x300000010));
+            assert_eq!(name, "pg_16390");
+        }
+        _ => panic!("Expected Origin change"),
+    }
+}
+
+/// Tests that TYPE messages name the columns of later Relation messages.
+/// Verifies the type name is cached and built-in types get their SQL name.
+#[test]
+fn test_decode_type_names_relation_columns() {
+    let mut decoder = Decoder::new();
+    // TYPE format: 'Y' + type_oid(4) + namespace + name
+    let mut type_data = vec![b'Y'];
+    type_data.extend_from_slice(&70001u32.to_be_bytes());
+    type_data.extend_from_slice(b"public\0");
+    type_data.extend_from_slice(b"mood\0");
+    assert!(decoder.decode_pgoutput_message(&type_data).unwrap().is_none());
+    assert_eq!(decoder.get_type_name(70001), Some("public.mood".to_string()));
+
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&950u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"people\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&2u16.to_be_bytes());
+    for (name, type_id) in [(&b"id\0"[..], 23u32), (b"current_mood\0", 70001)] {
+        relation_data.push(0);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&type_id.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+
+    match decoder.decode_pgoutput_message(&relation_data).unwrap() {
+        Some(Change::Relation { columns, .. }) => {
+            assert_eq!(columns[0].type_name.as_deref(), Some("integer"));
+            assert_eq!(columns[1].type_name.as_deref(), Some("public.mood"));
+        }
+        _ => panic!("Expected Relation change"),
+    }
+}
+
+/// Tests decoding of logical decoding MESSAGE events.
+/// Verifies flags, LSN, prefix and content, and the xid prefix inside a streamed block.
+#[test]
+fn test_decode_message() {
+    let mut decoder = Decoder::new();
+    // MESSAGE format: 'M' + flags(1) + lsn(8) + prefix + content_length(4) + content
+    let mut body = vec![1];
+    body.extend_from_slice(&0x0000000000ABCDEFu64.to_be_bytes());
+    body.extend_from_slice(b"audit\0");
+    body.extend_from_slice(&17u32.to_be_bytes());
+    body.extend_from_slice(br#"{"user":"alice"}!"#);
+
+    let mut data = vec![b'M'];
+    data.extend_from_slice(&body);
+    match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Message { transactional, prefix, content, lsn, xid }) => {
+            assert!(transactional);
+            assert_eq!(prefix, "audit");
+            assert_eq!(content, r#"{"user":"alice"}!"#);
+            assert_eq!(lsn, Lsn(0xABCDEF));
+            assert_eq!(xid, None);
+        }
+        _ => panic!("Expected Message change"),
+    }
+
+    let mut streamed = vec![b'M'];
+    streamed.extend_from_slice(&4242u32.to_be_bytes());
+    streamed.extend_from_slice(&body);
+    assert!(matches!(
+        decoder.decode_streamed_pgoutput_message(&streamed).unwrap(),
+        Some(Change::Message { xid: Some(4242), .. })
+    ));
+
+    // Content length beyond the payload
+    let mut truncated = vec![b'M', 0];
+    truncated.extend_from_slice(&0u64.to_be_bytes());
+    truncated.extend_from_slice(b"audit\0");
+    truncated.extend_from_slice(&100u32.to_be_bytes());
+    assert!(decoder.decode_pgoutput_message(&truncated).is_err());
+}
+
+/// Tests that column type modifiers are kept and decoded into length, precision and scale.
+/// Verifies numeric, varchar, timestamptz, array and unmodified columns and their JSON form.
+#[test]
+fn test_decode_relation_type_modifiers() {
+    let mut decoder = Decoder::new();
+    let mut data = vec![b'R'];
+    data.extend_from_slice(&111u32.to_be_bytes());
+    data.extend_from_slice(b"public\0");
+    data.extend_from_slice(b"invoices\0");
+    data.push(b'd');
+    let columns: [(&[u8], u32, i32); 6] = [
+        (b"amount\0", 1700, ((12 << 16) | 2) + 4), // numeric(12,2)
+        (b"code\0", 1043, 64 + 4),                  // varchar(64)
+        (b"issued_at\0", 1184, 3),                  // timestamptz(3)
+        (b"tags\0", 1015, 10 + 4),                  // varchar(10)[]
+        (b"note\0", 25, -1),                        // text
+        (b"total\0", 1700, -1),                     // numeric
+    ];
+    data.extend_from_slice(&(columns.len() as u16).to_be_bytes());
+    for (name, type_id, type_modifier) in columns {
+        data.push(0);
+        data.extend_from_slice(name);
+        data.extend_from_slice(&type_id.to_be_bytes());
+        data.extend_from_slice(&type_modifier.to_be_bytes());
+    }
+
+    let columns = match decoder.decode_pgoutput_message(&data).unwrap() {
+        Some(Change::Relation { columns, .. }) => columns,
+        _ => panic!("Expected Relation change"),
+    };
+
+    let amount = &columns[0];
+    assert_eq!(amount.type_modifier, ((12 << 16) | 2) + 4);
+    assert_eq!((amount.length, amount.precision, amount.scale), (None, Some(12), Some(2)));
+    assert_eq!(amount.formatted_type().as_deref(), Some("numeric(12,2)"));
+
+    assert_eq!(columns[1].length, Some(64));
+    assert_eq!(columns[1].formatted_type().as_deref(), Some("character varying(64)"));
+
+    assert_eq!(columns[2].precision, Some(3));
+    assert_eq!(columns[2].formatted_type().as_deref(), Some("timestamp(3) with time zone"));
+
+    assert_eq!(columns[3].length, Some(10));
+    assert_eq!(columns[3].formatted_type().as_deref(), Some("character varying(10)[]"));
+
+    for column in &columns[4..] {
+        assert_eq!(column.type_modifier, -1);
+        assert_eq!((column.length, column.precision, column.scale), (None, None, None));
+    }
+    assert_eq!(columns[5].formatted_type().as_deref(), Some("numeric"));
+
+    let json = serde_json::to_value(&columns[0]).unwrap();
+    assert_eq!(json["type_name"], "numeric");
+    assert_eq!(json["precision"], 12);
+    assert_eq!(json["scale"], 2);
+    assert!(json.get("length").is_none());
+}
+
+/// Builds a RELATION message for relation 112 with the given (name, type_id, type_modifier) columns
+fn relation_112(columns: &[(&str, u32, i32)]) -> Vec<u8> {
+    let mut data = vec![b'R'];
+    data.extend_from_slice(&112u32.to_be_bytes());
+    data.extend_from_slice(b"public\0");
+    data.extend_from_slice(b"accounts\0");
+    data.push(b'd');
+    data.extend_from_slice(&(columns.len() as u16).to_be_bytes());
+    for (name, type_id, type_modifier) in columns {
+        data.push(0);
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&type_id.to_be_bytes());
+        data.extend_from_slice(&type_modifier.to_be_bytes());
+    }
+    data
+}
+
+/// Tests that a changed Relation definition produces a SchemaChanged event.
+/// Verifies added, dropped, renamed-looking and retyped columns, and that the first
+/// and unchanged definitions produce none.
+#[test]
+fn test_decode_relation_schema_change() {
+    let mut decoder = Decoder::new();
+    let original = relation_112(&[("id", 23, -1), ("name", 25, -1), ("age", 23, -1), ("note", 1043, 14)]);
+
+    decoder.decode_pgoutput_message(&original).unwrap();
+    assert!(decoder.take_schema_change().is_none());
+    decoder.decode_pgoutput_message(&original).unwrap();
+    assert!(decoder.take_schema_change().is_none());
+
+    let altered = relation_112(&[("id", 23, -1), ("full_name", 25, -1), ("age", 20, -1), ("email", 25, -1)]);
+    assert!(matches!(
+        decoder.decode_pgoutput_message(&altered).unwrap(),
+        Some(Change::Relation { .. })
+    ));
+    match decoder.take_schema_change() {
+        Some(Change::SchemaChanged { relation_id, schema, table, added, dropped, renamed, retyped }) => {
+            assert_eq!(relation_id, 112);
+            assert_eq!((schema.as_str(), table.as_str()), ("public", "accounts"));
+            assert_eq!(added.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["email"]);
+            assert_eq!(dropped.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["note"]);
+            assert_eq!(renamed.len(), 1);
+            assert_eq!((renamed[0].before.name.as_str(), renamed[0].after.name.as_str()), ("name", "full_name"));
+            assert_eq!(retyped.len(), 1);
+            assert_eq!(retyped[0].after.name, "age");
+            assert_eq!((retyped[0].before.type_id, retyped[0].after.type_id), (23, 20));
+        }
+        other => panic!("Expected SchemaChanged, got {:?}", other),
+    }
+    assert!(decoder.take_schema_change().is_none());
+
+    // The changed definition is what later messages are decoded with
+    assert_eq!(decoder.get_relation_columns(112).unwrap()[1].name, "full_name");
+}
+
+/// Tests that data changes carry their transaction and commits carry the BEGIN's xid.
+/// Verifies the commit timestamp from BEGIN, the stream xid inside streamed blocks,
+/// and that a position recorded with set_lsn is reported by get_lsn.
+#[test]
+fn test_decode_transaction_metadata() {
+    let mut decoder = Decoder::new();
+    decoder.decode_pgoutput_message(&relation_112(&[("id", 23, -1)])).unwrap();
+    let insert = |xid: Option<u32>| {
+        let mut data = vec![b'I'];
+        if let Some(xid) = xid {
+            data.extend_from_slice(&xid.to_be_bytes());
+        }
+        data.extend_from_slice(&112u32.to_be_bytes());
+        data.extend_from_slice(&[b'N', 0, 1, b't', 0, 0, 0, 1, b'7']);
+        data
+    };
+
+    let mut begin = vec![b'B'];
+    begin.extend_from_slice(&0x0000000001000200u64.to_be_bytes());
+    begin.extend_from_slice(&5000i64.to_be_bytes());
+    begin.extend_from_slice(&77u32.to_be_bytes());
+    decoder.decode_pgoutput_message(&begin).unwrap();
+
+    let mut change = decoder.decode_pgoutput_message(&insert(None)).unwrap().unwrap();
+    assert_eq!(change.transaction(), Some(&TransactionInfo { xid: 77, commit_timestamp: Some(5000) }));
+    assert_eq!(change.get_lsn(), None);
+    change.set_lsn(Lsn(0x1000100));
+    assert_eq!(change.get_lsn(), Some(Lsn(0x1000100)));
+
+    let mut commit = vec![b'C', 0];
+    commit.extend_from_slice(&0x0000000001000200u64.to_be_bytes());
+    commit.extend_from_slice(&0x0000000001000230u64.to_be_bytes());
+    commit.extend_from_slice(&5000i64.to_be_bytes());
+    assert!(matches!(
+        decoder.decode_pgoutput_message(&commit).unwrap(),
+        Some(Change::Commit { xid: Some(77), .. })
+    ));
+
+    // Inside a streamed block the commit timestamp is not known yet
+    let mut stream_start = vec![b'S'];
+    stream_start.extend_from_slice(&88u32.to_be_bytes());
+    stream_start.push(1);
+    decoder.decode_pgoutput_message(&stream_start).unwrap();
+    let change = decoder.decode_streamed_pgoutput_message(&insert(Some(89))).unwrap().unwrap();
+    assert_eq!(change.transaction(), Some(&TransactionInfo { xid: 88, commit_timestamp: None }));
+    assert!(matches!(change, Change::Insert { xid: Some(89), .. }));
+}
@@ -1,46 +1,61 @@
 use pgoutput_stream::decoder::*;
-use std::collections::HashMap;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::value::PgValue;
 
 // Helper function to create test changes
 fn create_insert_change(schema: &str, table: &str) -> Change {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Test".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Test".to_string())));
     
     Change::Insert {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
 fn create_update_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Updated".to_string())));
     
     Change::Update {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
 fn create_delete_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     Change::Delete {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
@@ -140,9 +155,10 @@ fn test_feldera_multi_schema_same_table() {
 #[test]
 fn test_feldera_skip_begin() {
     let change = Change::Begin {
-        lsn: "0/1234567".to_string(),
+        final_lsn: Lsn(0x1234567),
         timestamp: 1706107200000,
         xid: 12345,
+        origin: None,
     };
     
     match change {
@@ -157,8 +173,11 @@ fn test_feldera_skip_begin() {
 #[test]
 fn test_feldera_skip_commit() {
     let change = Change::Commit {
-        lsn: "0/1234567".to_string(),
+        flags: 0,
+        lsn: Lsn(0x1234567),
+        end_lsn: Lsn(0x1234567),
         timestamp: 1706107200000,
+        xid: None,
     };
     
     match change {
@@ -176,6 +195,7 @@ fn test_feldera_skip_relation() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns: vec![],
     };
     
@@ -191,7 +211,7 @@ fn test_feldera_skip_relation() {
 /// When filtering by "public_users", only public.users should match.
 #[test]
 fn test_feldera_table_filtering_logic() {
-    let allowed = vec!["public_users".to_string(), "public_orders".to_string()];
+    let allowed = ["public_users".to_string(), "public_orders".to_string()];
     
     // Should match
     assert!(allowed.contains(&"public_users".to_string()));
@@ -236,18 +256,22 @@ fn test_feldera_table_with_underscores() {
 /// Verifies that INSERT events have the correct structure.
 #[test]
 fn test_feldera_format_insert() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("1".to_string()));
-    tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert_eq!(events.len(), 1);
     
     let event = &events[0];
@@ -259,23 +283,29 @@ fn test_feldera_format_insert() {
 /// Verifies that UPDATE events are split into DELETE + INSERT.
 #[test]
 fn test_feldera_format_update() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Bob".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Bob".to_string())));
     
     let change = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert_eq!(events.len(), 2);
     
     // First event should be DELETE
@@ -290,20 +320,140 @@ fn test_feldera_format_update() {
 /// Tests conversion to Feldera format for DELETE.
 #[test]
 fn test_feldera_format_delete() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Delete {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert_eq!(events.len(), 1);
     
     let event = &events[0];
     assert!(event.delete.is_some());
     assert!(event.insert.is_none());
 }
+
+fn create_truncate_change(schema: &str, table: &str) -> Change {
+    Change::Truncate {
+        relations: vec![TruncatedRelation {
+            relation_id: 16384,
+            schema: schema.to_string(),
+            table: table.to_string(),
+        }],
+        cascade: false,
+        restart_identity: false,
+        lsn: None,
+        transaction: None,
+        xid: None,
+    }
+}
+
+/// Tests the Feldera truncate policy parsing.
+#[test]
+fn test_feldera_truncate_policy_from_str() {
+    use pgoutput_stream::output::FelderaTruncatePolicy;
+
+    assert_eq!(FelderaTruncatePolicy::from_str("error").unwrap(), FelderaTruncatePolicy::Error);
+    assert_eq!(FelderaTruncatePolicy::from_str("SKIP").unwrap(), FelderaTruncatePolicy::Skip);
+    assert!(FelderaTruncatePolicy::from_str("reset").is_err());
+}
+
+/// Tests that a TRUNCATE fails the Feldera target by default and is skipped on request.
+/// Tables outside the allowed list are ignored regardless of policy.
+#[tokio::test]
+async fn test_feldera_truncate_policy() {
+    use pgoutput_stream::output::{FelderaOutput, FelderaTruncatePolicy, OutputTarget};
+
+    let truncate = create_truncate_change("public", "orders");
+
+    let output = FelderaOutput::new("http://localhost:8080", "pipeline", None, None).await.unwrap();
+    let err = output.write_change(&truncate).await.unwrap_err();
+    assert!(err.to_string().contains("public_orders"));
+
+    let output = FelderaOutput::new("http://localhost:8080", "pipeline", None, None)
+        .await
+        .unwrap()
+        .with_truncate_policy(FelderaTruncatePolicy::Skip);
+    assert!(output.write_change(&truncate).await.is_ok());
+
+    let output = FelderaOutput::new(
+        "http://localhost:8080",
+        "pipeline",
+        Some(vec!["public_users".to_string()]),
+        None,
+    ).await.unwrap();
+    assert!(output.write_change(&truncate).await.is_ok());
+}
+
+/// Tests the Feldera schema change policy parsing.
+#[test]
+fn test_feldera_schema_change_policy_from_str() {
+    use pgoutput_stream::output::FelderaSchemaChangePolicy;
+
+    assert_eq!(FelderaSchemaChangePolicy::from_str("error").unwrap(), FelderaSchemaChangePolicy::Error);
+    assert_eq!(FelderaSchemaChangePolicy::from_str("WARN").unwrap(), FelderaSchemaChangePolicy::Warn);
+    assert!(FelderaSchemaChangePolicy::from_str("skip").is_err());
+}
+
+/// Tests that a schema change only stops the Feldera target when configured to.
+/// Verifies the error names the table and the added column, and that tables outside
+/// the allowed list are ignored.
+#[tokio::test]
+async fn test_feldera_schema_change_policy() {
+    use pgoutput_stream::output::{FelderaOutput, FelderaSchemaChangePolicy, OutputTarget};
+
+    let change = Change::SchemaChanged {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "orders".to_string(),
+        added: vec![ColumnInfo {
+            name: "discount".to_string(),
+            type_id: 1700,
+            flags: 0,
+            type_modifier: -1,
+            type_name: Some("numeric".to_string()),
+            length: None,
+            precision: None,
+            scale: None,
+        }],
+        dropped: Vec::new(),
+        renamed: Vec::new(),
+        retyped: Vec::new(),
+    };
+
+    let output = FelderaOutput::new("http://localhost:8080", "pipeline", None, None).await.unwrap();
+    assert!(output.write_change(&change).await.is_ok());
+
+    let output = FelderaOutput::new("http://localhost:8080", "pipeline", None, None)
+        .await
+        .unwrap()
+        .with_schema_change_policy(FelderaSchemaChangePolicy::Error);
+    let err = output.write_change(&change).await.unwrap_err().to_string();
+    assert!(err.contains("public_orders"));
+    assert!(err.contains("added: discount"));
+
+    let output = FelderaOutput::new(
+        "http://localhost:8080",
+        "pipeline",
+        Some(vec!["public_users".to_string()]),
+        None,
+    ).await.unwrap()
+    .with_schema_change_policy(FelderaSchemaChangePolicy::Error);
+    assert!(output.write_change(&change).await.is_ok());
+}
+
+/// Tests that TRUNCATE produces no Feldera InsertDelete events.
+#[test]
+fn test_feldera_truncate_produces_no_events() {
+    let change = create_truncate_change("public", "orders");
+    assert!(pgoutput_stream::output::convert_to_feldera_test(&change).unwrap().is_empty());
+}
@@ -0,0 +1,257 @@
+use pgoutput_stream::decoder::{Change, ColumnInfo, ReplicaIdentity, Tuple};
+use pgoutput_stream::incremental::*;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::snapshot::{PublishedTable, TablePlan};
+use pgoutput_stream::value::PgValue;
+
+fn column(name: &str, type_id: u32) -> ColumnInfo {
+    ColumnInfo {
+        name: name.to_string(),
+        type_id,
+        flags: 1,
+        type_modifier: -1,
+        type_name: None,
+        length: None,
+        precision: None,
+        scale: None,
+    }
+}
+
+fn tuple(values: &[(&str, Option<PgValue>)]) -> Tuple {
+    values.iter().map(|(name, value)| (name.to_string(), value.clone())).collect()
+}
+
+fn text(value: &str) -> Option<PgValue> {
+    Some(PgValue::Text(value.to_string()))
+}
+
+fn chunk_row(id: &str) -> Change {
+    Change::Snapshot {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "orders".to_string(),
+        new_tuple: tuple(&[("id", text(id)), ("status", text("new"))]),
+        key_columns: vec!["id".to_string()],
+        lsn: Lsn::ZERO,
+        incremental: true,
+    }
+}
+
+fn delete(relation_id: u32, id: &str) -> Change {
+    Change::Delete {
+        relation_id,
+        schema: "public".to_string(),
+        table: "orders".to_string(),
+        old_tuple: tuple(&[("id", text(id))]),
+        key_columns: vec!["id".to_string()],
+        lsn: None,
+        transaction: None,
+        xid: None,
+    }
+}
+
+/// Tests splitting of signal table and data collection names.
+/// Verifies bare names default to the public schema and empty parts are rejected.
+#[test]
+fn test_parse_table_name() {
+    assert_eq!(parse_table_name("inventory.orders").unwrap(), ("inventory".to_string(), "orders".to_string()));
+    assert_eq!(parse_table_name("orders").unwrap(), ("public".to_string(), "orders".to_string()));
+    assert!(parse_table_name("inventory.").is_err());
+    assert!(parse_table_name("").is_err());
+}
+
+/// Tests reading execute-snapshot signals from inserted signal table rows.
+/// Verifies text and jsonb data columns and rejection of non-incremental snapshots.
+#[test]
+fn test_signal_execute_snapshot() {
+    let row = tuple(&[
+        ("id", text("adhoc-1")),
+        ("type", text("execute-snapshot")),
+        ("data", text(r#"{"data-collections": ["public.orders", "customers"], "type": "incremental"}"#)),
+    ]);
+    assert_eq!(
+        Signal::from_tuple(&row).unwrap(),
+        Signal::ExecuteSnapshot {
+            tables: vec![
+                ("public".to_string(), "orders".to_string()),
+                ("public".to_string(), "customers".to_string()),
+            ],
+        }
+    );
+
+    let row = tuple(&[
+        ("id", text("adhoc-2")),
+        ("type", text("execute-snapshot")),
+        ("data", Some(PgValue::Json(serde_json::json!({"data-collections": ["sales.orders"]})))),
+    ]);
+    assert_eq!(
+        Signal::from_tuple(&row).unwrap(),
+        Signal::ExecuteSnapshot { tables: vec![("sales".to_string(), "orders".to_string())] }
+    );
+
+    let row = tuple(&[
+        ("id", text("adhoc-3")),
+        ("type", text("execute-snapshot")),
+        ("data", text(r#"{"data-collections": ["public.orders"], "type": "blocking"}"#)),
+    ]);
+    assert!(Signal::from_tuple(&row).unwrap_err().to_string().contains("only incremental"));
+
+    let row = tuple(&[("id", text("adhoc-4")), ("type", text("execute-snapshot")), ("data", None)]);
+    assert!(Signal::from_tuple(&row).is_err());
+}
+
+/// Tests reading watermark and unknown signals.
+#[test]
+fn test_signal_watermarks() {
+    let row = tuple(&[("id", text("1a2b-1-open")), ("type", text("snapshot-window-open")), ("data", None)]);
+    assert_eq!(Signal::from_tuple(&row).unwrap(), Signal::WindowOpen { id: "1a2b-1-open".to_string() });
+
+    let row = tuple(&[("id", text("1a2b-1-close")), ("type", text("snapshot-window-close")), ("data", None)]);
+    assert_eq!(Signal::from_tuple(&row).unwrap(), Signal::WindowClose { id: "1a2b-1-close".to_string() });
+
+    let row = tuple(&[("id", text("x")), ("type", text("pause-snapshot")), ("data", None)]);
+    assert_eq!(Signal::from_tuple(&row).unwrap(), Signal::Other { kind: "pause-snapshot".to_string() });
+}
+
+/// Tests deduplication of chunk rows against changes streamed inside the window.
+/// Verifies changes before the low watermark are ignored, text and binary keys match,
+/// key changes drop the old row and the remaining rows take the high watermark's position.
+#[test]
+fn test_chunk_window_deduplicates() {
+    let mut window = ChunkWindow::new(16384, vec![column("id", 23)]);
+    for id in ["1", "2", "3", "4"] {
+        window.insert(chunk_row(id)).unwrap();
+    }
+
+    // Already reflected in the chunk
+    window.apply(&delete(16384, "1"));
+    assert_eq!(window.len(), 4);
+
+    window.open();
+    assert!(window.is_open());
+
+    // Binary-format key from the stream matches the text key read by the chunk
+    window.apply(&Change::Update {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "orders".to_string(),
+        old_tuple: Some(tuple(&[("id", Some(PgValue::Int(4)))])),
+        old_key_only: false,
+        new_tuple: tuple(&[("id", Some(PgValue::Int(40))), ("status", text("paid"))]),
+        unchanged_columns: vec![],
+        key_columns: vec!["id".to_string()],
+        lsn: None,
+        transaction: None,
+        xid: None,
+    });
+    window.apply(&delete(16384, "3"));
+    window.apply(&delete(16385, "2"));
+    assert_eq!(window.len(), 2);
+
+    let rows = window.close(Lsn(0x16B2D50));
+    assert!(window.is_empty());
+    assert!(!window.is_open());
+    let ids: Vec<_> = rows
+        .iter()
+        .map(|row| match row {
+            Change::Snapshot { new_tuple, lsn, incremental: true, .. } => {
+                assert_eq!(*lsn, Lsn(0x16B2D50));
+                new_tuple["id"].clone().unwrap().to_string()
+            }
+            other => panic!("Expected an incremental snapshot row, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(ids, vec!["1", "2"]);
+}
+
+/// Tests that streamed transaction keys are only applied while the window is open.
+#[test]
+fn test_chunk_window_forget_keys() {
+    let mut window = ChunkWindow::new(16384, vec![column("id", 23)]);
+    window.insert(chunk_row("1")).unwrap();
+    window.insert(chunk_row("2")).unwrap();
+
+    let keys = window.change_keys(&delete(16384, "2"));
+    window.forget(&keys);
+    assert_eq!(window.len(), 2);
+
+    window.open();
+    window.forget(&keys);
+    assert_eq!(window.len(), 1);
+}
+
+/// Tests a streamed transaction whose changes are sent before the low watermark and
+/// which commits inside the window.
+/// Verifies its rows are remembered without a window and supersede the chunk rows at
+/// its commit, while aborted subtransactions are left out.
+#[test]
+fn test_streamed_rows_commit_inside_window() {
+    let streamed_delete = |id: &str, subxid: u32| {
+        let mut change = delete(16384, id);
+        if let Change::Delete { xid, .. } = &mut change {
+            *xid = Some(subxid);
+        }
+        change
+    };
+
+    // Streamed before the chunk is read, while no window exists
+    let mut streamed = StreamedRows::default();
+    streamed.start(900);
+    streamed.record(900, &streamed_delete("2", 900));
+    streamed.record(901, &streamed_delete("1", 901));
+    streamed.stop();
+    streamed.abort(900, 901);
+    assert_eq!(streamed.len(), 1);
+
+    // The chunk is read and its low watermark arrives
+    let mut window = ChunkWindow::new(16384, vec![column("id", 23)]);
+    for id in ["1", "2", "3"] {
+        window.insert(chunk_row(id)).unwrap();
+    }
+    window.open();
+
+    // The transaction commits between the watermarks
+    streamed.commit(900, Some(&mut window));
+    assert!(streamed.is_empty());
+    let ids: Vec<_> = window
+        .close(Lsn(0x16B2D50))
+        .iter()
+        .map(|row| match row {
+            Change::Snapshot { new_tuple, .. } => new_tuple["id"].clone().unwrap().to_string(),
+            other => panic!("Expected a snapshot row, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(ids, vec!["1", "3"]);
+}
+
+/// Tests the keyset query reading incremental snapshot chunks.
+/// Verifies the row filter, a composite key compared as a row and the chunk limit.
+#[test]
+fn test_build_chunk_query() {
+    let plan = TablePlan {
+        table: PublishedTable {
+            relation_id: 16384,
+            schema: "public".to_string(),
+            table: "Order Lines".to_string(),
+            replica_identity: ReplicaIdentity::Default,
+            partitioned: false,
+            column_list: None,
+            row_filter: Some("qty > 0".to_string()),
+        },
+        columns: vec![column("order_id", 20), column("line", 23), column("qty", 23)],
+        chunk_key: None,
+        chunks: vec![],
+    };
+    let key = vec![column("order_id", 20), column("line", 23)];
+
+    assert_eq!(
+        build_chunk_query(&plan, &key, None, 1024),
+        "SELECT \"order_id\", \"line\", \"qty\" FROM ONLY \"public\".\"Order Lines\" \
+         WHERE (qty > 0) ORDER BY \"order_id\", \"line\" LIMIT 1024"
+    );
+    assert_eq!(
+        build_chunk_query(&plan, &key, Some(&["7".to_string(), "it's".to_string()]), 10),
+        "SELECT \"order_id\", \"line\", \"qty\" FROM ONLY \"public\".\"Order Lines\" \
+         WHERE (qty > 0) AND (\"order_id\", \"line\") > ('7', 'it''s') ORDER BY \"order_id\", \"line\" LIMIT 10"
+    );
+}
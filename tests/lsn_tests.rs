@@ -0,0 +1,71 @@
+use pgoutput_stream::lsn::Lsn;
+
+/// Tests parsing of LSNs written in PostgreSQL's X/Y notation.
+/// Verifies round-tripping through Display and rejection of malformed input.
+#[test]
+fn test_parse_lsn() {
+    assert_eq!("0/16B2D50".parse::<Lsn>().unwrap(), Lsn(0x16B2D50));
+    assert_eq!("1/0".parse::<Lsn>().unwrap(), Lsn(0x100000000));
+    assert_eq!("ffffffff/ffffffff".parse::<Lsn>().unwrap(), Lsn(u64::MAX));
+    assert_eq!("A/1234ABCD".parse::<Lsn>().unwrap().to_string(), "A/1234ABCD");
+    assert_eq!(Lsn::ZERO.to_string(), "0/0");
+
+    assert!("".parse::<Lsn>().is_err());
+    assert!("16B2D50".parse::<Lsn>().is_err());
+    assert!("0/XYZ".parse::<Lsn>().is_err());
+    assert!("100000000/0".parse::<Lsn>().is_err());
+}
+
+/// Tests that positions order by WAL position rather than by their text.
+#[test]
+fn test_lsn_ordering() {
+    let a: Lsn = "0/FFFFFFFF".parse().unwrap();
+    let b: Lsn = "1/0".parse().unwrap();
+    let c: Lsn = "0/9".parse().unwrap();
+    let d: Lsn = "0/10".parse().unwrap();
+
+    assert!(a < b);
+    assert!(c < d);
+    assert_eq!(std::cmp::max(a, b), b);
+
+    let mut positions = vec![b, d, a, c];
+    positions.sort();
+    assert_eq!(positions, vec![c, d, a, b]);
+}
+
+/// Tests byte distances between positions.
+/// Verifies subtraction across the X/Y boundary and moving a position by a byte count.
+#[test]
+fn test_lsn_arithmetic() {
+    let flushed: Lsn = "0/FFFFFF00".parse().unwrap();
+    let current: Lsn = "1/100".parse().unwrap();
+
+    assert_eq!(current - flushed, 0x200);
+    assert_eq!(current.bytes_since(flushed), 0x200);
+    assert_eq!(flushed.bytes_since(current), 0);
+    assert_eq!(flushed + 0x200, current);
+    assert_eq!(current - 0x200u64, flushed);
+}
+
+/// Tests byte distances when positions arrive out of order.
+/// Verifies that a stale position yields zero or `None` instead of panicking.
+#[test]
+fn test_lsn_arithmetic_out_of_order() {
+    let flushed: Lsn = "0/FFFFFF00".parse().unwrap();
+    let current: Lsn = "1/100".parse().unwrap();
+
+    assert_eq!(flushed - current, 0);
+    assert_eq!(current.checked_sub(flushed), Some(0x200));
+    assert_eq!(flushed.checked_sub(current), None);
+    assert_eq!(Lsn(0x10) - 0x20u64, Lsn::ZERO);
+}
+
+/// Tests that LSNs serialize as "X/Y" strings, keeping the JSON shape of events.
+#[test]
+fn test_lsn_serde() {
+    let lsn = Lsn(0x1_016B_2D50);
+    assert_eq!(serde_json::to_string(&lsn).unwrap(), "\"1/16B2D50\"");
+    assert_eq!(serde_json::from_str::<Lsn>("\"1/16B2D50\"").unwrap(), lsn);
+    assert!(serde_json::from_str::<Lsn>("\"bogus\"").is_err());
+    assert!(serde_json::from_str::<Lsn>("42").is_err());
+}
@@ -1,46 +1,61 @@
 use pgoutput_stream::decoder::*;
-use std::collections::HashMap;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::value::PgValue;
 
 // Helper function to create test changes
 fn create_insert_change(schema: &str, table: &str) -> Change {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Test".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Test".to_string())));
     
     Change::Insert {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
 fn create_update_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Updated".to_string())));
     
     Change::Update {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
 fn create_delete_change(schema: &str, table: &str) -> Change {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     Change::Delete {
         relation_id: 16384,
         schema: schema.to_string(),
         table: table.to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     }
 }
 
@@ -103,9 +118,10 @@ fn test_nats_subject_format_delete() {
 #[test]
 fn test_nats_subject_format_transaction_begin() {
     let change = Change::Begin {
-        lsn: "0/16B2D50".to_string(),
+        final_lsn: Lsn(0x16B2D50),
         timestamp: 730826470123456,
         xid: 1000,
+        origin: None,
     };
     
     match change {
@@ -121,8 +137,11 @@ fn test_nats_subject_format_transaction_begin() {
 #[test]
 fn test_nats_subject_format_transaction_commit() {
     let change = Change::Commit {
-        lsn: "0/16B2E20".to_string(),
+        flags: 0,
+        lsn: Lsn(0x16B2E20),
+        end_lsn: Lsn(0x16B2E20),
         timestamp: 730826470123457,
+        xid: None,
     };
     
     match change {
@@ -142,6 +161,11 @@ fn test_nats_subject_format_relation() {
             name: "id".to_string(),
             type_id: 23,
             flags: 1,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
     ];
     
@@ -149,6 +173,7 @@ fn test_nats_subject_format_relation() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns,
     };
     
@@ -229,16 +254,20 @@ fn test_change_serialization_for_nats() {
 fn test_change_serialization_roundtrip() {
     let changes = vec![
         Change::Begin {
-            lsn: "0/123".to_string(),
+            final_lsn: Lsn(0x123),
             timestamp: 12345,
             xid: 100,
+            origin: None,
         },
         create_insert_change("public", "users"),
         create_update_change("public", "orders"),
         create_delete_change("public", "products"),
         Change::Commit {
-            lsn: "0/456".to_string(),
+            flags: 0,
+            lsn: Lsn(0x456),
+            end_lsn: Lsn(0x456),
             timestamp: 12346,
+            xid: None,
         },
     ];
     
@@ -263,11 +292,11 @@ fn test_change_serialization_roundtrip() {
 #[test]
 fn test_nats_payload_size_reasonable() {
     // Verify that serialized payloads are reasonable sizes
-    let mut large_tuple = HashMap::new();
+    let mut large_tuple = Tuple::new();
     for i in 0..100 {
         large_tuple.insert(
             format!("column_{}", i),
-            Some(format!("value_{}", i)),
+            Some(PgValue::Text(format!("value_{}", i))),
         );
     }
     
@@ -276,6 +305,10 @@ fn test_nats_payload_size_reasonable() {
         schema: "public".to_string(),
         table: "wide_table".to_string(),
         new_tuple: large_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_vec(&change).unwrap();
@@ -348,7 +381,11 @@ fn test_empty_tuple_serialization() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
-        new_tuple: HashMap::new(),
+        new_tuple: Tuple::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     // Should serialize even with empty tuple
@@ -361,9 +398,10 @@ fn test_empty_tuple_serialization() {
 #[test]
 fn test_large_lsn_values() {
     let change = Change::Begin {
-        lsn: "FFFFFFFF/FFFFFFFF".to_string(),
+        final_lsn: Lsn(0xFFFFFFFFFFFFFFFF),
         timestamp: i64::MAX,
         xid: u32::MAX,
+        origin: None,
     };
     
     // Should serialize large values correctly
@@ -371,8 +409,8 @@ fn test_large_lsn_values() {
     let deserialized: Change = serde_json::from_slice(&json).unwrap();
     
     match deserialized {
-        Change::Begin { lsn, timestamp, xid } => {
-            assert_eq!(lsn, "FFFFFFFF/FFFFFFFF");
+        Change::Begin { final_lsn, timestamp, xid, .. } => {
+            assert_eq!(final_lsn, Lsn(0xFFFFFFFFFFFFFFFF));
             assert_eq!(timestamp, i64::MAX);
             assert_eq!(xid, u32::MAX);
         }
@@ -384,15 +422,19 @@ fn test_large_lsn_values() {
 /// Verifies that international characters (Chinese, Norwegian, German) are preserved through serialization.
 #[test]
 fn test_unicode_in_table_data() {
-    let mut tuple = HashMap::new();
-    tuple.insert("name".to_string(), Some("测试用户".to_string()));
-    tuple.insert("description".to_string(), Some("Tëst Üsér".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("name".to_string(), Some(PgValue::Text("测试用户".to_string())));
+    tuple.insert("description".to_string(), Some(PgValue::Text("Tëst Üsér".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     // Unicode should serialize correctly
@@ -401,8 +443,8 @@ fn test_unicode_in_table_data() {
     
     match deserialized {
         Change::Insert { new_tuple, .. } => {
-            assert_eq!(new_tuple.get("name").unwrap().as_ref().unwrap(), "测试用户");
-            assert_eq!(new_tuple.get("description").unwrap().as_ref().unwrap(), "Tëst Üsér");
+            assert_eq!(new_tuple.get("name").unwrap().as_ref().and_then(PgValue::as_str).unwrap(), "测试用户");
+            assert_eq!(new_tuple.get("description").unwrap().as_ref().and_then(PgValue::as_str).unwrap(), "Tëst Üsér");
         }
         _ => panic!("Expected Insert variant"),
     }
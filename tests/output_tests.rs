@@ -1,6 +1,7 @@
 use pgoutput_stream::output::*;
 use pgoutput_stream::decoder::*;
-use std::collections::HashMap;
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::value::PgValue;
 
 /// Tests parsing of 'json' output format string.
 /// Verifies that OutputFormat::from_str correctly recognizes and returns the Json variant.
@@ -49,9 +50,10 @@ fn test_output_format_from_str_invalid() {
 #[test]
 fn test_json_serialization_begin() {
     let change = Change::Begin {
-        lsn: "0/123456".to_string(),
+        final_lsn: Lsn(0x123456),
         timestamp: 123456789,
         xid: 999,
+        origin: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -69,8 +71,11 @@ fn test_json_serialization_begin() {
 #[test]
 fn test_json_serialization_commit() {
     let change = Change::Commit {
-        lsn: "0/789ABC".to_string(),
+        flags: 0,
+        lsn: Lsn(0x789ABC),
+        end_lsn: Lsn(0x789ABC),
         timestamp: 987654321,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -83,15 +88,19 @@ fn test_json_serialization_commit() {
 /// Verifies that relation ID, schema, table name, and tuple data are correctly represented in JSON.
 #[test]
 fn test_json_serialization_insert() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -108,8 +117,8 @@ fn test_json_serialization_insert() {
 /// Verifies that SQL NULL is properly represented as JSON null.
 #[test]
 fn test_json_serialization_insert_with_null() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     new_tuple.insert("email".to_string(), None);
     
     let change = Change::Insert {
@@ -117,6 +126,10 @@ fn test_json_serialization_insert_with_null() {
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -132,18 +145,24 @@ fn test_json_serialization_insert_with_null() {
 /// Verifies that both old and new values are included when REPLICA IDENTITY FULL is used.
 #[test]
 fn test_json_serialization_update_with_old_tuple() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("name".to_string(), Some("Bob".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Bob".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("name".to_string(), Some("Robert".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Robert".to_string())));
     
     let change = Change::Update {
         relation_id: 200,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -156,15 +175,21 @@ fn test_json_serialization_update_with_old_tuple() {
 /// Verifies proper handling when only new values are available (REPLICA IDENTITY DEFAULT).
 #[test]
 fn test_json_serialization_update_without_old_tuple() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("name".to_string(), Some("Carol".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Carol".to_string())));
     
     let change = Change::Update {
         relation_id: 200,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: None,
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -180,14 +205,18 @@ fn test_json_serialization_update_without_old_tuple() {
 /// Verifies that deleted row data (old tuple) is correctly serialized to JSON.
 #[test]
 fn test_json_serialization_delete() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("42".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("42".to_string())));
     
     let change = Change::Delete {
         relation_id: 300,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -204,11 +233,21 @@ fn test_json_serialization_relation() {
             name: "id".to_string(),
             type_id: 23,
             flags: 1,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
         ColumnInfo {
             name: "name".to_string(),
             type_id: 1043,
             flags: 0,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
     ];
     
@@ -216,6 +255,7 @@ fn test_json_serialization_relation() {
         relation_id: 12345,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns,
     };
     
@@ -233,9 +273,10 @@ fn test_json_serialization_relation() {
 #[test]
 fn test_json_pretty_format() {
     let change = Change::Begin {
-        lsn: "0/123456".to_string(),
+        final_lsn: Lsn(0x123456),
         timestamp: 123456789,
         xid: 999,
+        origin: None,
     };
     
     let json_pretty = serde_json::to_string_pretty(&change).unwrap();
@@ -249,14 +290,18 @@ fn test_json_pretty_format() {
 /// Verifies that quotes, backslashes, and other special characters are properly escaped.
 #[test]
 fn test_json_special_characters() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("description".to_string(), Some("Test \"quotes\" and \\backslash".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("description".to_string(), Some(PgValue::Text("Test \"quotes\" and \\backslash".to_string())));
     
     let change = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "items".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -273,14 +318,18 @@ fn test_json_special_characters() {
 /// Verifies that international characters (Norwegian, German, Chinese) are preserved correctly in JSON.
 #[test]
 fn test_json_unicode() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("name".to_string(), Some("Håkon Müller 李明".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Håkon Müller 李明".to_string())));
     
     let change = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -295,14 +344,18 @@ fn test_json_unicode() {
 /// Verifies that empty string values are correctly represented as "" in JSON output.
 #[test]
 fn test_json_empty_string() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("description".to_string(), Some("".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("description".to_string(), Some(PgValue::Text("".to_string())));
     
     let change = Change::Insert {
         relation_id: 100,
         schema: "public".to_string(),
         table: "items".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let json = serde_json::to_string(&change).unwrap();
@@ -321,15 +374,19 @@ fn test_json_empty_string() {
 async fn test_stdout_output_insert() {
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     // Should not panic
@@ -342,20 +399,26 @@ async fn test_stdout_output_insert() {
 async fn test_stdout_output_update() {
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice Updated".to_string())));
     
     let change = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     output.write_change(&change).await.unwrap();
@@ -367,15 +430,19 @@ async fn test_stdout_output_update() {
 async fn test_stdout_output_delete() {
     let output = StdoutOutput::new(OutputFormat::JsonPretty);
     
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Delete {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     output.write_change(&change).await.unwrap();
@@ -388,15 +455,19 @@ async fn test_stdout_output_transaction_events() {
     let output = StdoutOutput::new(OutputFormat::Text);
     
     let begin = Change::Begin {
-        lsn: "0/16B2D50".to_string(),
+        final_lsn: Lsn(0x16B2D50),
         timestamp: 730826470123456,
         xid: 1000,
+        origin: None,
     };
     output.write_change(&begin).await.unwrap();
     
     let commit = Change::Commit {
-        lsn: "0/16B2E20".to_string(),
+        flags: 0,
+        lsn: Lsn(0x16B2E20),
+        end_lsn: Lsn(0x16B2E20),
         timestamp: 730826470123457,
+        xid: None,
     };
     output.write_change(&commit).await.unwrap();
 }
@@ -412,11 +483,21 @@ async fn test_stdout_output_relation() {
             name: "id".to_string(),
             type_id: 23,
             flags: 1,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
         ColumnInfo {
             name: "name".to_string(),
             type_id: 1043,
             flags: 0,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
     ];
     
@@ -424,6 +505,7 @@ async fn test_stdout_output_relation() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns,
     };
     
@@ -439,14 +521,18 @@ async fn test_composite_output_with_single_target() {
     let stdout = StdoutOutput::new(OutputFormat::Json);
     let composite = CompositeOutput::new(vec![Arc::new(stdout)]);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     composite.write_change(&change).await.unwrap();
@@ -465,14 +551,18 @@ async fn test_composite_output_with_multiple_targets() {
         Arc::new(stdout2),
     ]);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     composite.write_change(&change).await.unwrap();
@@ -484,20 +574,67 @@ async fn test_composite_output_with_multiple_targets() {
 async fn test_composite_output_empty_targets() {
     let composite = CompositeOutput::new(vec![]);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     // Should not panic with no targets
     composite.write_change(&change).await.unwrap();
 }
 
+/// Output target that records flushes and can be told to fail them
+struct FlushRecorder {
+    flushes: std::sync::atomic::AtomicUsize,
+    fail: bool,
+}
+
+#[async_trait::async_trait]
+impl OutputTarget for FlushRecorder {
+    async fn write_change(&self, _change: &Change) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn flush(&self) -> anyhow::Result<()> {
+        self.flushes.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if self.fail {
+            anyhow::bail!("sink unavailable");
+        }
+        Ok(())
+    }
+}
+
+/// Tests that CompositeOutput flushes every target before a commit is confirmed.
+/// Verifies that a failing target surfaces as an error so the slot is not advanced.
+#[tokio::test]
+async fn test_composite_output_flush() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let healthy = Arc::new(FlushRecorder { flushes: AtomicUsize::new(0), fail: false });
+    let composite = CompositeOutput::new(vec![
+        Arc::new(StdoutOutput::new(OutputFormat::Json)),
+        healthy.clone(),
+    ]);
+    composite.flush().await.unwrap();
+    assert_eq!(healthy.flushes.load(Ordering::SeqCst), 1);
+
+    let failing = Arc::new(FlushRecorder { flushes: AtomicUsize::new(0), fail: true });
+    let composite = CompositeOutput::new(vec![healthy.clone(), failing.clone()]);
+    assert!(composite.flush().await.is_err());
+    assert_eq!(healthy.flushes.load(Ordering::SeqCst), 2);
+    assert_eq!(failing.flushes.load(Ordering::SeqCst), 1);
+}
+
 /// Tests complete transaction flow through OutputTarget.
 /// Verifies proper handling of Begin, Relation, INSERT, UPDATE, DELETE, and Commit in sequence.
 #[tokio::test]
@@ -506,9 +643,10 @@ async fn test_full_transaction_flow_through_output() {
     
     // Begin transaction
     let begin = Change::Begin {
-        lsn: "0/100".to_string(),
+        final_lsn: Lsn(0x100),
         timestamp: 1234567890,
         xid: 500,
+        origin: None,
     };
     output.write_change(&begin).await.unwrap();
     
@@ -518,56 +656,79 @@ async fn test_full_transaction_flow_through_output() {
             name: "id".to_string(),
             type_id: 23,
             flags: 1,
+            type_modifier: -1,
+            type_name: None,
+            length: None,
+            precision: None,
+            scale: None,
         },
     ];
     let relation = Change::Relation {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "test_table".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns,
     };
     output.write_change(&relation).await.unwrap();
     
     // Insert
-    let mut insert_tuple = HashMap::new();
-    insert_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut insert_tuple = Tuple::new();
+    insert_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     let insert = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "test_table".to_string(),
         new_tuple: insert_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     output.write_change(&insert).await.unwrap();
     
     // Update
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("1".to_string()));
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("2".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("2".to_string())));
     let update = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "test_table".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     output.write_change(&update).await.unwrap();
     
     // Delete
-    let mut delete_tuple = HashMap::new();
-    delete_tuple.insert("id".to_string(), Some("2".to_string()));
+    let mut delete_tuple = Tuple::new();
+    delete_tuple.insert("id".to_string(), Some(PgValue::Text("2".to_string())));
     let delete = Change::Delete {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "test_table".to_string(),
         old_tuple: delete_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     output.write_change(&delete).await.unwrap();
     
     // Commit transaction
     let commit = Change::Commit {
-        lsn: "0/200".to_string(),
+        flags: 0,
+        lsn: Lsn(0x200),
+        end_lsn: Lsn(0x200),
         timestamp: 1234567900,
+        xid: None,
     };
     output.write_change(&commit).await.unwrap();
 }
@@ -578,16 +739,20 @@ async fn test_full_transaction_flow_through_output() {
 async fn test_stdout_output_with_null_values() {
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     new_tuple.insert("email".to_string(), None);
-    new_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     output.write_change(&change).await.unwrap();
@@ -599,14 +764,18 @@ async fn test_stdout_output_with_null_values() {
 async fn test_stdout_output_with_special_schema_names() {
     let output = StdoutOutput::new(OutputFormat::Json);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "my-custom_schema".to_string(),
         table: "test_table".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     output.write_change(&change).await.unwrap();
@@ -618,15 +787,19 @@ async fn test_stdout_output_with_special_schema_names() {
 async fn test_stdout_output_text_format() {
     let output = StdoutOutput::new(OutputFormat::Text);
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("1".to_string()));
-    new_tuple.insert("name".to_string(), Some("Test User".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Test User".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     // Text format should not panic
@@ -647,24 +820,32 @@ async fn test_multiple_inserts_through_composite() {
     ]);
     
     // First insert
-    let mut tuple1 = HashMap::new();
-    tuple1.insert("id".to_string(), Some("1".to_string()));
+    let mut tuple1 = Tuple::new();
+    tuple1.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     let change1 = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple1,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     composite.write_change(&change1).await.unwrap();
     
     // Second insert
-    let mut tuple2 = HashMap::new();
-    tuple2.insert("id".to_string(), Some("2".to_string()));
+    let mut tuple2 = Tuple::new();
+    tuple2.insert("id".to_string(), Some(PgValue::Text("2".to_string())));
     let change2 = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "orders".to_string(),
         new_tuple: tuple2,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     composite.write_change(&change2).await.unwrap();
 }
@@ -681,15 +862,19 @@ fn test_output_format_from_str_debezium() {
 /// Verifies that the envelope contains correct op='c', after data, and source metadata.
 #[test]
 fn test_debezium_insert() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("123".to_string()));
-    tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -706,20 +891,26 @@ fn test_debezium_insert() {
 /// Verifies that the envelope contains op='u', both before and after data, and source metadata.
 #[test]
 fn test_debezium_update() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("123".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("123".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice Updated".to_string())));
     
     let change = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -735,15 +926,19 @@ fn test_debezium_update() {
 /// Verifies that the envelope contains op='d', before data only, and no after data.
 #[test]
 fn test_debezium_delete() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("123".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Delete {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -760,9 +955,10 @@ fn test_debezium_delete() {
 #[test]
 fn test_debezium_begin_skipped() {
     let change = Change::Begin {
-        lsn: "0/16B9188".to_string(),
+        final_lsn: Lsn(0x16B9188),
         timestamp: 1705320000000,
         xid: 1234,
+        origin: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change);
@@ -774,8 +970,11 @@ fn test_debezium_begin_skipped() {
 #[test]
 fn test_debezium_commit_skipped() {
     let change = Change::Commit {
-        lsn: "0/16B91B8".to_string(),
+        flags: 0,
+        lsn: Lsn(0x16B91B8),
+        end_lsn: Lsn(0x16B91B8),
         timestamp: 1705320001000,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change);
@@ -790,6 +989,7 @@ fn test_debezium_relation_skipped() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns: vec![],
     };
     
@@ -801,8 +1001,8 @@ fn test_debezium_relation_skipped() {
 /// Verifies that NULL values are correctly represented in the envelope.
 #[test]
 fn test_debezium_null_handling() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("123".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
     tuple.insert("email".to_string(), None); // NULL value
     
     let change = Change::Insert {
@@ -810,6 +1010,10 @@ fn test_debezium_null_handling() {
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -820,17 +1024,22 @@ fn test_debezium_null_handling() {
 }
 
 /// Tests Debezium source metadata fields.
-/// Verifies that source contains all required Debezium fields with correct values.
+/// Verifies that source contains all required Debezium fields with correct values,
+/// including the change's LSN, transaction id and commit time.
 #[test]
 fn test_debezium_source_metadata() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "test_schema".to_string(),
         table: "test_table".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: Some(Lsn(0x16B2D50)),
+        transaction: Some(TransactionInfo { xid: 742, commit_timestamp: Some(1_000_000) }),
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -841,21 +1050,31 @@ fn test_debezium_source_metadata() {
     assert_eq!(envelope.source.db, "postgres");
     assert_eq!(envelope.source.schema, "test_schema");
     assert_eq!(envelope.source.table, "test_table");
-    assert!(!envelope.source.lsn.is_empty());
+    assert_eq!(envelope.source.lsn, "0/16B2D50");
+    assert_eq!(envelope.source.tx_id, Some(742));
+    // One second after 2000-01-01
+    assert_eq!(envelope.source.ts_ms, 946_684_801_000);
+
+    let json = serde_json::to_value(&envelope).unwrap();
+    assert_eq!(json["source"]["txId"], 742);
 }
 
 /// Tests Debezium timestamp field presence.
 /// Verifies that ts_ms is populated and is a reasonable Unix timestamp.
 #[test]
 fn test_debezium_timestamp() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("1".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
     let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
@@ -884,18 +1103,22 @@ fn test_output_format_from_str_insert_delete() {
 /// Verifies that the event contains an insert key with the record data.
 #[test]
 fn test_feldera_insert() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("123".to_string()));
-    tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     
     assert_eq!(events.len(), 1);
     let event = &events[0];
@@ -912,23 +1135,29 @@ fn test_feldera_insert() {
 /// Verifies that updates are encoded as delete (old) + insert (new) pairs.
 #[test]
 fn test_feldera_update() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("123".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("123".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice Updated".to_string())));
     
     let change = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: Some(old_tuple),
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     
     // Update should produce two events: delete + insert
     assert_eq!(events.len(), 2);
@@ -956,19 +1185,25 @@ fn test_feldera_update() {
 /// Verifies that when old_tuple is None, only insert event is produced.
 #[test]
 fn test_feldera_update_without_old_tuple() {
-    let mut new_tuple = HashMap::new();
-    new_tuple.insert("id".to_string(), Some("123".to_string()));
-    new_tuple.insert("name".to_string(), Some("Alice Updated".to_string()));
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice Updated".to_string())));
     
     let change = Change::Update {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple: None,
+        old_key_only: false,
         new_tuple,
+        unchanged_columns: Vec::new(),
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     
     // Without old_tuple, only insert is produced
     assert_eq!(events.len(), 1);
@@ -977,22 +1212,267 @@ fn test_feldera_update_without_old_tuple() {
     assert!(event.delete.is_none());
 }
 
+/// Tests Feldera format UPDATE with unchanged TOAST columns.
+/// Verifies the value is taken from the old tuple, and that the update is
+/// rejected when no before-image is available instead of inserting NULL.
+#[test]
+fn test_feldera_update_unchanged_toast_column() {
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    old_tuple.insert("bio".to_string(), Some(PgValue::Text("long text".to_string())));
+
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("124".to_string())));
+
+    let mut change = Change::Update {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "users".to_string(),
+        old_tuple: Some(old_tuple),
+        old_key_only: false,
+        new_tuple,
+        unchanged_columns: vec!["bio".to_string()],
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
+    };
+
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
+    assert_eq!(events.len(), 2);
+    let insert_data = events[1].insert.as_ref().unwrap();
+    assert_eq!(insert_data["id"], "124");
+    assert_eq!(insert_data["bio"], "long text");
+
+    if let Change::Update { old_tuple, .. } = &mut change {
+        *old_tuple = None;
+    }
+    let err = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap_err();
+    assert!(err.to_string().contains("'bio'"));
+}
+
+/// Tests Feldera conversion of a decoded UPDATE whose old tuple is a key-only ('K') image.
+/// Verifies the unchanged TOAST column is not repaired from the NULL in the key image.
+#[test]
+fn test_feldera_update_unchanged_toast_column_key_image() {
+    let mut decoder = Decoder::new();
+    let mut relation_data = vec![b'R'];
+    relation_data.extend_from_slice(&16384u32.to_be_bytes());
+    relation_data.extend_from_slice(b"public\0");
+    relation_data.extend_from_slice(b"users\0");
+    relation_data.push(b'd');
+    relation_data.extend_from_slice(&2u16.to_be_bytes());
+    for (flags, name, type_id) in [(1u8, &b"id\0"[..], 23u32), (0, &b"bio\0"[..], 25)] {
+        relation_data.push(flags);
+        relation_data.extend_from_slice(name);
+        relation_data.extend_from_slice(&type_id.to_be_bytes());
+        relation_data.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+    decoder.decode_pgoutput_message(&relation_data).unwrap();
+
+    // UPDATE with key image (id = '123', bio NULL) and new tuple (id = '124', bio unchanged)
+    let mut data = vec![b'U'];
+    data.extend_from_slice(&16384u32.to_be_bytes());
+    data.push(b'K');
+    data.extend_from_slice(&2u16.to_be_bytes());
+    data.push(b't');
+    data.extend_from_slice(&3u32.to_be_bytes());
+    data.extend_from_slice(b"123");
+    data.push(b'n');
+    data.push(b'N');
+    data.extend_from_slice(&2u16.to_be_bytes());
+    data.push(b't');
+    data.extend_from_slice(&3u32.to_be_bytes());
+    data.extend_from_slice(b"124");
+    data.push(b'u');
+
+    let change = decoder.decode_pgoutput_message(&data).unwrap().unwrap();
+    assert!(matches!(change, Change::Update { old_key_only: true, .. }));
+
+    let err = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap_err();
+    assert!(err.to_string().contains("'bio'"));
+    assert!(err.to_string().contains("REPLICA IDENTITY FULL"));
+}
+
+/// Tests Debezium format UPDATE with unchanged TOAST columns.
+/// Verifies they are marked with Debezium's unavailable-value placeholder.
+#[test]
+fn test_debezium_update_unchanged_toast_column() {
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+
+    let change = Change::Update {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "users".to_string(),
+        old_tuple: None,
+        old_key_only: false,
+        new_tuple,
+        unchanged_columns: vec!["bio".to_string()],
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
+    };
+
+    let envelope = pgoutput_stream::output::convert_to_debezium_test(&change).unwrap();
+    let after = envelope.after.unwrap();
+    assert_eq!(after["id"], "123");
+    assert_eq!(after["bio"], pgoutput_stream::output::DEBEZIUM_UNAVAILABLE_VALUE);
+}
+
+/// Tests the Debezium schema block and typed rows written with `--debezium-schema`.
+/// Verifies Connect types, key optionality and the source type, length and scale parameters.
+#[test]
+fn test_debezium_schema_block() {
+    let columns = vec![
+        ColumnInfo {
+            name: "id".to_string(),
+            type_id: 23,
+            flags: 1,
+            type_modifier: -1,
+            type_name: Some("integer".to_string()),
+            length: None,
+            precision: None,
+            scale: None,
+        },
+        ColumnInfo {
+            name: "amount".to_string(),
+            type_id: 1700,
+            flags: 0,
+            type_modifier: ((12 << 16) | 2) + 4,
+            type_name: Some("numeric".to_string()),
+            length: None,
+            precision: Some(12),
+            scale: Some(2),
+        },
+        ColumnInfo {
+            name: "code".to_string(),
+            type_id: 1043,
+            flags: 0,
+            type_modifier: 68,
+            type_name: Some("character varying".to_string()),
+            length: Some(64),
+            precision: None,
+            scale: None,
+        },
+    ];
+
+    let schema = debezium_schema("public", "invoices", &columns);
+    assert_eq!(schema["name"], "pgoutput-stream.public.invoices.Envelope");
+    let fields = schema["fields"].as_array().unwrap();
+    assert_eq!(fields[0]["field"], "before");
+    assert_eq!(fields[1]["field"], "after");
+    assert_eq!(fields[2]["field"], "source");
+
+    let row = fields[1]["fields"].as_array().unwrap();
+    assert_eq!(row[0]["type"], "int32");
+    assert_eq!(row[0]["optional"], false);
+    assert_eq!(row[1]["type"], "string");
+    assert_eq!(row[1]["optional"], true);
+    assert_eq!(row[1]["parameters"]["__debezium.source.column.type"], "NUMERIC");
+    assert_eq!(row[1]["parameters"]["__debezium.source.column.length"], "12");
+    assert_eq!(row[1]["parameters"]["__debezium.source.column.scale"], "2");
+    assert_eq!(row[2]["type"], "string");
+    assert_eq!(row[2]["parameters"]["__debezium.source.column.length"], "64");
+
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("7".to_string())));
+    new_tuple.insert("amount".to_string(), Some(PgValue::Text("19.99".to_string())));
+    new_tuple.insert("code".to_string(), Some(PgValue::Text("INV-7".to_string())));
+    let change = Change::Insert {
+        relation_id: 111,
+        schema: "public".to_string(),
+        table: "invoices".to_string(),
+        new_tuple,
+        key_columns: vec!["id".to_string()],
+        lsn: None,
+        transaction: None,
+        xid: None,
+    };
+
+    let envelope = convert_to_debezium_typed_test(&change, &columns).unwrap();
+    let after = envelope.after.as_ref().unwrap();
+    assert_eq!(after["id"], 7);
+    assert_eq!(after["amount"], "19.99");
+    assert_eq!(after["code"], "INV-7");
+
+    let record = serde_json::to_value(DebeziumRecord { schema, payload: envelope }).unwrap();
+    assert_eq!(record["payload"]["op"], "c");
+    assert_eq!(record["schema"]["type"], "struct");
+}
+
+/// Tests that typed rows keep text-format values lossless.
+/// Verifies exact numerics, nested json, parsed arrays, base64 bytea and RFC 3339 timestamps.
+#[test]
+fn test_debezium_typed_row_values() {
+    let column = |name: &str, type_id: u32| ColumnInfo {
+        name: name.to_string(),
+        type_id,
+        flags: 0,
+        type_modifier: -1,
+        type_name: None,
+        length: None,
+        precision: None,
+        scale: None,
+    };
+    let columns = vec![
+        column("amount", 1700),
+        column("attrs", 3802),
+        column("tags", 1009),
+        column("matrix", 1007),
+        column("payload", 17),
+        column("created_at", 1184),
+    ];
+
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("amount".to_string(), Some(PgValue::Text("9007199254740993.01".to_string())));
+    new_tuple.insert("attrs".to_string(), Some(PgValue::Text(r#"{"vip": true}"#.to_string())));
+    new_tuple.insert("tags".to_string(), Some(PgValue::Text(r#"{a,"b c",NULL}"#.to_string())));
+    new_tuple.insert("matrix".to_string(), Some(PgValue::Text("{{1,2},{3,NULL}}".to_string())));
+    new_tuple.insert("payload".to_string(), Some(PgValue::Text("\\x0001ff".to_string())));
+    new_tuple.insert("created_at".to_string(), Some(PgValue::Text("2024-01-25 10:30:00.25+01".to_string())));
+    let change = Change::Insert {
+        relation_id: 112,
+        schema: "public".to_string(),
+        table: "orders".to_string(),
+        new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
+    };
+
+    let envelope = convert_to_debezium_typed_test(&change, &columns).unwrap();
+    let after = envelope.after.unwrap();
+    assert_eq!(after["amount"], "9007199254740993.01");
+    assert_eq!(after["attrs"], serde_json::json!({"vip": true}));
+    assert_eq!(after["tags"], serde_json::json!(["a", "b c", null]));
+    assert_eq!(after["matrix"], serde_json::json!([[1, 2], [3, null]]));
+    assert_eq!(after["payload"], "AAH/");
+    assert_eq!(after["created_at"], "2024-01-25T09:30:00.25Z");
+}
+
 /// Tests Feldera format DELETE event structure.
 /// Verifies that the event contains a delete key with the old record data.
 #[test]
 fn test_feldera_delete() {
-    let mut old_tuple = HashMap::new();
-    old_tuple.insert("id".to_string(), Some("123".to_string()));
-    old_tuple.insert("name".to_string(), Some("Alice".to_string()));
+    let mut old_tuple = Tuple::new();
+    old_tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
+    old_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
     
     let change = Change::Delete {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         old_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     
     assert_eq!(events.len(), 1);
     let event = &events[0];
@@ -1010,12 +1490,13 @@ fn test_feldera_delete() {
 #[test]
 fn test_feldera_begin_skipped() {
     let change = Change::Begin {
-        lsn: "0/16B9188".to_string(),
+        final_lsn: Lsn(0x16B9188),
         timestamp: 1705320000000,
         xid: 1234,
+        origin: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert!(events.is_empty());
 }
 
@@ -1024,11 +1505,14 @@ fn test_feldera_begin_skipped() {
 #[test]
 fn test_feldera_commit_skipped() {
     let change = Change::Commit {
-        lsn: "0/16B91B8".to_string(),
+        flags: 0,
+        lsn: Lsn(0x16B91B8),
+        end_lsn: Lsn(0x16B91B8),
         timestamp: 1705320001000,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert!(events.is_empty());
 }
 
@@ -1040,10 +1524,11 @@ fn test_feldera_relation_skipped() {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
+        replica_identity: ReplicaIdentity::Default,
         columns: vec![],
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert!(events.is_empty());
 }
 
@@ -1051,8 +1536,8 @@ fn test_feldera_relation_skipped() {
 /// Verifies that NULL values are correctly represented.
 #[test]
 fn test_feldera_null_handling() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("123".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("123".to_string())));
     tuple.insert("email".to_string(), None); // NULL value
     
     let change = Change::Insert {
@@ -1060,9 +1545,13 @@ fn test_feldera_null_handling() {
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     
     assert_eq!(events.len(), 1);
     let event = &events[0];
@@ -1075,18 +1564,22 @@ fn test_feldera_null_handling() {
 /// Verifies that the format can be serialized and deserialized correctly.
 #[test]
 fn test_feldera_serialization() {
-    let mut tuple = HashMap::new();
-    tuple.insert("id".to_string(), Some("1".to_string()));
-    tuple.insert("name".to_string(), Some("Test".to_string()));
+    let mut tuple = Tuple::new();
+    tuple.insert("id".to_string(), Some(PgValue::Text("1".to_string())));
+    tuple.insert("name".to_string(), Some(PgValue::Text("Test".to_string())));
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert_eq!(events.len(), 1);
     
     // Serialize to JSON
@@ -1102,16 +1595,463 @@ fn test_feldera_serialization() {
 /// Verifies handling of records with no data fields.
 #[test]
 fn test_feldera_empty_tuple() {
-    let tuple = HashMap::new();
+    let tuple = Tuple::new();
     
     let change = Change::Insert {
         relation_id: 16384,
         schema: "public".to_string(),
         table: "users".to_string(),
         new_tuple: tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: None,
+        xid: None,
     };
     
-    let events = pgoutput_stream::output::convert_to_feldera_test(&change);
+    let events = pgoutput_stream::output::convert_to_feldera_test(&change).unwrap();
     assert_eq!(events.len(), 1);
     assert!(events[0].insert.is_some());
 }
+
+/// Output target that records every change it receives
+#[derive(Default)]
+struct ChangeRecorder {
+    changes: std::sync::Mutex<Vec<Change>>,
+}
+
+#[async_trait::async_trait]
+impl OutputTarget for ChangeRecorder {
+    async fn write_change(&self, change: &Change) -> anyhow::Result<()> {
+        self.changes.lock().unwrap().push(change.clone());
+        Ok(())
+    }
+}
+
+fn streamed_insert(id: &str, xid: u32) -> Change {
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text(id.to_string())));
+    Change::Insert {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "users".to_string(),
+        new_tuple,
+        key_columns: Vec::new(),
+        lsn: None,
+        transaction: Some(TransactionInfo { xid, commit_timestamp: None }),
+        xid: Some(xid),
+    }
+}
+
+/// Tests StreamedTransactionMode parsing.
+#[test]
+fn test_streamed_transaction_mode_from_str() {
+    assert_eq!(StreamedTransactionMode::from_str("reassemble").unwrap(), StreamedTransactionMode::Reassemble);
+    assert_eq!(StreamedTransactionMode::from_str("Passthrough").unwrap(), StreamedTransactionMode::Passthrough);
+    assert!(StreamedTransactionMode::from_str("buffer").is_err());
+}
+
+/// Tests ReassemblingOutput with a committed streamed transaction.
+/// Verifies chunks are held back until STREAM COMMIT and then released as
+/// BEGIN / changes / COMMIT, while unrelated changes pass straight through.
+#[tokio::test]
+async fn test_reassembling_output_commit() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = ReassemblingOutput::new(recorder.clone());
+
+    output.write_change(&Change::StreamStart { xid: 500, first_segment: true }).await.unwrap();
+    output.write_change(&streamed_insert("1", 500)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+
+    // A regular transaction interleaved between streamed blocks
+    output.write_change(&Change::Begin { final_lsn: Lsn(0x100), timestamp: 1, xid: 501, origin: None }).await.unwrap();
+    output.write_change(&Change::Commit { flags: 0, lsn: Lsn(0x100), end_lsn: Lsn(0x100), timestamp: 1, xid: None }).await.unwrap();
+    assert_eq!(recorder.changes.lock().unwrap().len(), 2);
+
+    output.write_change(&Change::StreamStart { xid: 500, first_segment: false }).await.unwrap();
+    output.write_change(&streamed_insert("2", 500)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+    output.write_change(&Change::StreamCommit { xid: 500, lsn: Lsn(0x200), end_lsn: Lsn(0x200), timestamp: 2 }).await.unwrap();
+
+    let changes = recorder.changes.lock().unwrap();
+    assert_eq!(changes.len(), 6);
+    assert!(matches!(&changes[2], Change::Begin { xid: 500, final_lsn, .. } if *final_lsn == Lsn(0x200)));
+    assert!(matches!(&changes[3], Change::Insert { new_tuple, .. } if new_tuple["id"].as_ref().and_then(PgValue::as_str) == Some("1")));
+    assert!(matches!(&changes[4], Change::Insert { new_tuple, .. } if new_tuple["id"].as_ref().and_then(PgValue::as_str) == Some("2")));
+    assert!(matches!(&changes[5], Change::Commit { lsn, xid: Some(500), .. } if *lsn == Lsn(0x200)));
+    // Released changes get the commit timestamp from STREAM COMMIT
+    assert_eq!(changes[3].transaction().and_then(|t| t.commit_timestamp), Some(2));
+}
+
+/// Tests ReassemblingOutput with aborted streamed transactions.
+/// Verifies a subtransaction abort drops only its changes and a top-level abort drops everything.
+#[tokio::test]
+async fn test_reassembling_output_abort() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = ReassemblingOutput::new(recorder.clone());
+
+    output.write_change(&Change::StreamStart { xid: 600, first_segment: true }).await.unwrap();
+    output.write_change(&streamed_insert("kept", 600)).await.unwrap();
+    output.write_change(&streamed_insert("rolled back", 601)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+    output.write_change(&Change::StreamAbort { xid: 600, subxid: 601 }).await.unwrap();
+    output.write_change(&Change::StreamCommit { xid: 600, lsn: Lsn(0x300), end_lsn: Lsn(0x300), timestamp: 3 }).await.unwrap();
+
+    {
+        let changes = recorder.changes.lock().unwrap();
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(&changes[1], Change::Insert { new_tuple, .. } if new_tuple["id"].as_ref().and_then(PgValue::as_str) == Some("kept")));
+    }
+
+    output.write_change(&Change::StreamStart { xid: 700, first_segment: true }).await.unwrap();
+    output.write_change(&streamed_insert("discarded", 700)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+    output.write_change(&Change::StreamAbort { xid: 700, subxid: 700 }).await.unwrap();
+
+    assert_eq!(recorder.changes.lock().unwrap().len(), 3);
+}
+
+/// Tests Debezium transaction metadata for two-phase commit events.
+/// Verifies the status, id and GID, and that data-less events have no envelope.
+#[test]
+fn test_debezium_two_phase_transaction_events() {
+    let prepare = Change::Prepare {
+        lsn: Lsn(0x1000000),
+        timestamp: 0,
+        xid: 900,
+        gid: "tx-900".to_string(),
+    };
+    assert!(pgoutput_stream::output::convert_to_debezium_test(&prepare).is_none());
+
+    let event = pgoutput_stream::output::convert_to_debezium_transaction_test(&prepare).unwrap();
+    assert_eq!(event.status, "PREPARE");
+    assert_eq!(event.id, "900:0/1000000");
+    assert_eq!(event.gid, "tx-900");
+    assert_eq!(event.ts_ms, 946_684_800_000);
+
+    let rollback = Change::RollbackPrepared {
+        lsn: Lsn(0x3000000),
+        timestamp: 0,
+        prepare_end_lsn: Lsn(0x1000100),
+        prepare_timestamp: 0,
+        xid: 900,
+        gid: "tx-900".to_string(),
+    };
+    let event = pgoutput_stream::output::convert_to_debezium_transaction_test(&rollback).unwrap();
+    assert_eq!(event.status, "ROLLBACK");
+
+    let commit = Change::Commit { flags: 0, lsn: Lsn(0x100), end_lsn: Lsn(0x100), timestamp: 0, xid: None };
+    assert!(pgoutput_stream::output::convert_to_debezium_transaction_test(&commit).is_none());
+}
+
+/// Tests ReassemblingOutput with a streamed transaction that is prepared.
+/// Verifies the buffered changes are released as BEGIN PREPARE / changes / PREPARE.
+#[tokio::test]
+async fn test_reassembling_output_stream_prepare() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = ReassemblingOutput::new(recorder.clone());
+
+    output.write_change(&Change::StreamStart { xid: 800, first_segment: true }).await.unwrap();
+    output.write_change(&streamed_insert("1", 800)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+    output.write_change(&Change::StreamPrepare {
+        lsn: Lsn(0x400),
+        timestamp: 4,
+        xid: 800,
+        gid: "tx-800".to_string(),
+    }).await.unwrap();
+
+    let changes = recorder.changes.lock().unwrap();
+    assert_eq!(changes.len(), 3);
+    assert!(matches!(&changes[0], Change::BeginPrepare { xid: 800, gid, .. } if gid == "tx-800"));
+    assert!(matches!(&changes[1], Change::Insert { .. }));
+    assert!(matches!(&changes[2], Change::Prepare { xid: 800, gid, .. } if gid == "tx-800"));
+}
+
+/// Tests Debezium conversion of TRUNCATE.
+/// Verifies one `op: "t"` event per truncated table with no before/after image.
+#[test]
+fn test_debezium_truncate_events() {
+    let change = Change::Truncate {
+        relations: vec![
+            TruncatedRelation { relation_id: 1, schema: "public".to_string(), table: "orders".to_string() },
+            TruncatedRelation { relation_id: 2, schema: "sales".to_string(), table: "items".to_string() },
+        ],
+        cascade: true,
+        restart_identity: false,
+        lsn: None,
+        transaction: None,
+        xid: None,
+    };
+
+    assert!(pgoutput_stream::output::convert_to_debezium_test(&change).is_none());
+    let events = pgoutput_stream::output::convert_to_debezium_truncate_test(&change);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].op, "t");
+    assert!(events[0].before.is_none() && events[0].after.is_none());
+    assert_eq!(events[0].source.table, "orders");
+    assert_eq!(events[1].source.schema, "sales");
+
+    let json = serde_json::to_value(&change).unwrap();
+    assert_eq!(json["Truncate"]["relations"][1]["table"], "items");
+    assert_eq!(json["Truncate"]["cascade"], true);
+}
+
+/// Tests JSON serialization of the transaction origin on BEGIN.
+/// Verifies the field is present when set and omitted otherwise.
+#[test]
+fn test_begin_origin_serialization() {
+    let with_origin = Change::Begin {
+        final_lsn: Lsn(0x100),
+        timestamp: 1,
+        xid: 7,
+        origin: Some("pg_16390".to_string()),
+    };
+    let json = serde_json::to_value(&with_origin).unwrap();
+    assert_eq!(json["Begin"]["origin"], "pg_16390");
+
+    let without_origin = Change::Begin {
+        final_lsn: Lsn(0x100),
+        timestamp: 1,
+        xid: 7,
+        origin: None,
+    };
+    let json = serde_json::to_value(&without_origin).unwrap();
+    assert!(json["Begin"].get("origin").is_none());
+
+    // Changes serialized before the field existed still deserialize
+    let parsed: Change = serde_json::from_str(r#"{"Begin":{"lsn":"0/100","timestamp":1,"xid":7}}"#).unwrap();
+    assert!(matches!(parsed, Change::Begin { origin: None, .. }));
+}
+
+/// Tests Debezium conversion of logical decoding messages.
+/// Verifies the `op: "m"` event carries the prefix, content and position.
+#[test]
+fn test_debezium_message_event() {
+    let change = Change::Message {
+        transactional: true,
+        prefix: "audit".to_string(),
+        content: "hello".to_string(),
+        lsn: Lsn(0xABCDEF),
+        xid: None,
+    };
+
+    assert!(pgoutput_stream::output::convert_to_debezium_test(&change).is_none());
+    let event = pgoutput_stream::output::convert_to_debezium_message_test(&change).unwrap();
+    assert_eq!(event.op, "m");
+    assert_eq!(event.message.prefix, "audit");
+    assert_eq!(event.message.content, "hello");
+    assert_eq!(event.source.lsn, Lsn(0xABCDEF));
+}
+
+/// Tests ReassemblingOutput with logical decoding messages inside a streamed block.
+/// Verifies non-transactional messages pass straight through while transactional
+/// ones wait for the transaction's commit.
+#[tokio::test]
+async fn test_reassembling_output_messages() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = ReassemblingOutput::new(recorder.clone());
+
+    let message = |transactional: bool| Change::Message {
+        transactional,
+        prefix: "audit".to_string(),
+        content: "event".to_string(),
+        lsn: Lsn(0x500),
+        xid: Some(900),
+    };
+
+    output.write_change(&Change::StreamStart { xid: 900, first_segment: true }).await.unwrap();
+    output.write_change(&message(true)).await.unwrap();
+    output.write_change(&message(false)).await.unwrap();
+    output.write_change(&Change::StreamStop).await.unwrap();
+    {
+        let changes = recorder.changes.lock().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], Change::Message { transactional: false, .. }));
+    }
+
+    output.write_change(&Change::StreamCommit { xid: 900, lsn: Lsn(0x600), end_lsn: Lsn(0x600), timestamp: 6 }).await.unwrap();
+    let changes = recorder.changes.lock().unwrap();
+    assert_eq!(changes.len(), 4);
+    assert!(matches!(&changes[2], Change::Message { transactional: true, .. }));
+}
+
+/// Tests TransactionGroupingOutput with a committed transaction.
+/// Verifies BEGIN / changes / COMMIT are released as one Transaction event carrying
+/// the commit metadata, and that a non-transactional message passes straight through.
+#[tokio::test]
+async fn test_transaction_grouping_output() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = TransactionGroupingOutput::new(recorder.clone(), 100);
+
+    output.write_change(&Change::Begin { final_lsn: Lsn(0x300), timestamp: 7, xid: 700, origin: None }).await.unwrap();
+    output.write_change(&streamed_insert("1", 700)).await.unwrap();
+    output
+        .write_change(&Change::Message {
+            transactional: false,
+            prefix: "audit".to_string(),
+            content: "event".to_string(),
+            lsn: Lsn(0x2F0),
+            xid: None,
+        })
+        .await
+        .unwrap();
+    output.write_change(&streamed_insert("2", 700)).await.unwrap();
+    {
+        let changes = recorder.changes.lock().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], Change::Message { transactional: false, .. }));
+    }
+
+    output.write_change(&Change::Commit { flags: 0, lsn: Lsn(0x300), end_lsn: Lsn(0x330), timestamp: 7, xid: None }).await.unwrap();
+    let changes = recorder.changes.lock().unwrap();
+    assert_eq!(changes.len(), 2);
+    match &changes[1] {
+        Change::Transaction { xid, commit_lsn, end_lsn, commit_ts, changes, .. } => {
+            assert_eq!(*xid, 700);
+            assert_eq!(*commit_lsn, Lsn(0x300));
+            assert_eq!(*end_lsn, Lsn(0x330));
+            assert_eq!(*commit_ts, 7);
+            assert_eq!(changes.len(), 2);
+        }
+        other => panic!("Expected Transaction, got {:?}", other),
+    }
+    assert_eq!(changes[1].get_lsn(), Some(Lsn(0x300)));
+}
+
+/// Tests TransactionGroupingOutput with a transaction larger than the change limit.
+/// Verifies it falls back to per-change output instead of buffering without bound.
+#[tokio::test]
+async fn test_transaction_grouping_output_overflow() {
+    use std::sync::Arc;
+
+    let recorder = Arc::new(ChangeRecorder::default());
+    let output = TransactionGroupingOutput::new(recorder.clone(), 2);
+
+    output.write_change(&Change::Begin { final_lsn: Lsn(0x400), timestamp: 8, xid: 800, origin: None }).await.unwrap();
+    output.write_change(&streamed_insert("1", 800)).await.unwrap();
+    output.write_change(&streamed_insert("2", 800)).await.unwrap();
+    assert!(recorder.changes.lock().unwrap().is_empty());
+
+    output.write_change(&streamed_insert("3", 800)).await.unwrap();
+    assert_eq!(recorder.changes.lock().unwrap().len(), 4);
+
+    output.write_change(&Change::Commit { flags: 0, lsn: Lsn(0x400), end_lsn: Lsn(0x430), timestamp: 8, xid: None }).await.unwrap();
+    {
+        let changes = recorder.changes.lock().unwrap();
+        assert_eq!(changes.len(), 5);
+        assert!(matches!(&changes[0], Change::Begin { xid: 800, .. }));
+        assert!(matches!(&changes[4], Change::Commit { .. }));
+    }
+
+    // The next transaction is grouped again
+    output.write_change(&Change::Begin { final_lsn: Lsn(0x500), timestamp: 9, xid: 801, origin: None }).await.unwrap();
+    output.write_change(&Change::Commit { flags: 0, lsn: Lsn(0x500), end_lsn: Lsn(0x530), timestamp: 9, xid: None }).await.unwrap();
+    let changes = recorder.changes.lock().unwrap();
+    assert_eq!(changes.len(), 6);
+    assert!(matches!(&changes[5], Change::Transaction { xid: 801, .. }));
+}
+
+/// Tests the JSON shape of a grouped transaction and its expansion back into
+/// BEGIN / changes / COMMIT for targets that write changes one at a time.
+#[test]
+fn test_transaction_json_and_expand() {
+    let transaction = Change::Transaction {
+        xid: 900,
+        commit_lsn: Lsn(0x16B2D50),
+        end_lsn: Lsn(0x16B2D80),
+        commit_ts: 10,
+        origin: None,
+        changes: vec![streamed_insert("1", 900)],
+    };
+
+    let json = serde_json::to_value(&transaction).unwrap();
+    assert_eq!(json["Transaction"]["xid"], 900);
+    assert_eq!(json["Transaction"]["commit_lsn"], "0/16B2D50");
+    assert_eq!(json["Transaction"]["end_lsn"], "0/16B2D80");
+    assert_eq!(json["Transaction"]["commit_ts"], 10);
+    assert!(json["Transaction"].get("origin").is_none());
+    assert_eq!(json["Transaction"]["changes"][0]["Insert"]["table"], "users");
+
+    let expanded = transaction.expand_transaction().unwrap();
+    assert_eq!(expanded.len(), 3);
+    assert!(matches!(&expanded[0], Change::Begin { xid: 900, final_lsn, timestamp: 10, .. } if *final_lsn == Lsn(0x16B2D50)));
+    assert!(matches!(&expanded[1], Change::Insert { .. }));
+    assert!(matches!(&expanded[2], Change::Commit { xid: Some(900), end_lsn, .. } if *end_lsn == Lsn(0x16B2D80)));
+    assert!(streamed_insert("1", 900).expand_transaction().is_none());
+}
+
+fn snapshot_row() -> Change {
+    let mut new_tuple = Tuple::new();
+    new_tuple.insert("id".to_string(), Some(PgValue::Text("7".to_string())));
+    new_tuple.insert("name".to_string(), Some(PgValue::Text("Alice".to_string())));
+    Change::Snapshot {
+        relation_id: 16384,
+        schema: "public".to_string(),
+        table: "users".to_string(),
+        new_tuple,
+        key_columns: vec!["id".to_string()],
+        lsn: Lsn(0x16B2D50),
+        incremental: false,
+    }
+}
+
+/// Tests conversion of snapshot rows to Debezium read events.
+/// Verifies `op: "r"`, the snapshot flag and the consistent point as the source LSN.
+#[test]
+fn test_debezium_snapshot_read_event() {
+    let envelope = convert_to_debezium_test(&snapshot_row()).unwrap();
+    assert_eq!(envelope.op, "r");
+    assert!(envelope.before.is_none());
+    assert_eq!(envelope.after.as_ref().unwrap()["name"], "Alice");
+    assert_eq!(envelope.source.snapshot.as_deref(), Some("true"));
+    assert_eq!(envelope.source.lsn, "0/16B2D50");
+    assert!(envelope.source.tx_id.is_none());
+
+    // Streamed changes leave the snapshot flag out
+    let json = serde_json::to_value(convert_to_debezium_test(&streamed_insert("1", 1)).unwrap()).unwrap();
+    assert!(json["source"].get("snapshot").is_none());
+}
+
+/// Tests that rows of an incremental snapshot are marked as such.
+/// Verifies the Debezium snapshot flag and that the field is only serialized when set.
+#[test]
+fn test_incremental_snapshot_row() {
+    let mut change = snapshot_row();
+    assert!(serde_json::to_value(&change).unwrap()["Snapshot"].get("incremental").is_none());
+
+    if let Change::Snapshot { incremental, .. } = &mut change {
+        *incremental = true;
+    }
+    assert_eq!(serde_json::to_value(&change).unwrap()["Snapshot"]["incremental"], true);
+
+    let envelope = convert_to_debezium_test(&change).unwrap();
+    assert_eq!(envelope.op, "r");
+    assert_eq!(envelope.source.snapshot.as_deref(), Some("incremental"));
+}
+
+/// Tests that snapshot rows serialize with their key and convert to Feldera inserts.
+#[test]
+fn test_snapshot_row_json_and_feldera() {
+    let change = snapshot_row();
+    let json = serde_json::to_value(&change).unwrap();
+    assert_eq!(json["Snapshot"]["table"], "users");
+    assert_eq!(json["Snapshot"]["lsn"], "0/16B2D50");
+    assert_eq!(json["Snapshot"]["key_columns"][0], "id");
+    assert_eq!(change.get_lsn(), Some(Lsn(0x16B2D50)));
+    assert_eq!(change.key().unwrap()["id"], Some(PgValue::Text("7".to_string())));
+
+    let events = convert_to_feldera_test(&change).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].insert.as_ref().unwrap()["id"], "7");
+    assert!(events[0].delete.is_none());
+}
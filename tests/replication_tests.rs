@@ -0,0 +1,271 @@
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::protocol::*;
+use pgoutput_stream::replication::*;
+
+/// Tests parsing of XLogData ('w') frames from the walsender.
+/// Verifies WAL positions, server clock and the embedded pgoutput payload.
+#[test]
+fn test_parse_xlog_data() {
+    // XLogData format: 'w' + wal_start(8) + wal_end(8) + server_clock(8) + data
+    let mut data = vec![b'w'];
+    data.extend_from_slice(&0x0000000001234567u64.to_be_bytes()); // wal_start
+    data.extend_from_slice(&0x0000000001234600u64.to_be_bytes()); // wal_end
+    data.extend_from_slice(&777i64.to_be_bytes());                 // server clock
+    data.extend_from_slice(b"B payload");                          // pgoutput message
+
+    match parse_replication_message(&data).unwrap() {
+        ReplicationMessage::XLogData { wal_start, wal_end, server_clock, data } => {
+            assert_eq!(wal_start, Lsn(0x1234567));
+            assert_eq!(wal_end, Lsn(0x1234600));
+            assert_eq!(server_clock, 777);
+            assert_eq!(&data[..], b"B payload");
+        }
+        other => panic!("Expected XLogData, got {:?}", other),
+    }
+}
+
+/// Tests parsing of primary keepalive ('k') frames.
+/// Verifies the reply-requested flag is decoded.
+#[test]
+fn test_parse_primary_keepalive() {
+    // Keepalive format: 'k' + wal_end(8) + server_clock(8) + reply(1)
+    let mut data = vec![b'k'];
+    data.extend_from_slice(&0x0000000100000000u64.to_be_bytes());
+    data.extend_from_slice(&42i64.to_be_bytes());
+    data.push(1);
+
+    assert_eq!(
+        parse_replication_message(&data).unwrap(),
+        ReplicationMessage::PrimaryKeepalive {
+            wal_end: Lsn(0x100000000),
+            server_clock: 42,
+            reply_requested: true,
+        }
+    );
+}
+
+/// Tests that truncated frames are rejected instead of panicking.
+#[test]
+fn test_parse_truncated_frames() {
+    assert!(parse_replication_message(&[]).is_err());
+    assert!(parse_replication_message(&[b'w', 0, 0, 0]).is_err());
+    assert!(parse_replication_message(&[b'k', 0, 0]).is_err());
+}
+
+/// Tests splitting backend messages off the read buffer.
+/// Verifies partial frames wait for more data and negative or too small lengths are
+/// rejected instead of allocating or panicking.
+#[test]
+fn test_split_message() {
+    let mut buf = bytes::BytesMut::from(&[b'Z', 0, 0, 0, 5, b'I', b'd', 0][..]);
+    let (tag, body) = split_message(&mut buf).unwrap().unwrap();
+    assert_eq!(tag, b'Z');
+    assert_eq!(&body[..], b"I");
+    // The next frame is still incomplete
+    assert!(split_message(&mut buf).unwrap().is_none());
+    assert_eq!(&buf[..], &[b'd', 0][..]);
+
+    let mut buf = bytes::BytesMut::from(&[b'd', 0xFF, 0xFF, 0xFF, 0xF0, 0][..]);
+    assert!(split_message(&mut buf).is_err());
+    let mut buf = bytes::BytesMut::from(&[b'd', 0, 0, 0, 3, 0][..]);
+    assert!(split_message(&mut buf).is_err());
+}
+
+/// Tests parsing of DataRow messages returned by replication commands.
+/// Verifies NULLs and values, and that truncated rows are errors.
+#[test]
+fn test_parse_data_row() {
+    let mut body = vec![0, 2];
+    body.extend_from_slice(&2i32.to_be_bytes());
+    body.extend_from_slice(b"ok");
+    body.extend_from_slice(&(-1i32).to_be_bytes());
+    assert_eq!(
+        parse_data_row(bytes::Bytes::from(body)).unwrap(),
+        vec![Some("ok".to_string()), None]
+    );
+
+    let mut body = vec![0, 1];
+    body.extend_from_slice(&100i32.to_be_bytes());
+    body.extend_from_slice(b"short");
+    assert!(parse_data_row(bytes::Bytes::from(body)).is_err());
+    assert!(parse_data_row(bytes::Bytes::from_static(&[0, 1, 0])).is_err());
+    assert!(parse_data_row(bytes::Bytes::new()).is_err());
+}
+
+/// Tests that unknown frame types are reported as errors.
+#[test]
+fn test_parse_unknown_frame_type() {
+    assert!(parse_replication_message(&[b'x', 0, 0, 0, 0]).is_err());
+}
+
+/// Tests parsing of replication mode names from the CLI.
+#[test]
+fn test_replication_mode_from_str() {
+    assert_eq!(ReplicationMode::from_str("streaming").unwrap(), ReplicationMode::Streaming);
+    assert_eq!(ReplicationMode::from_str("POLLING").unwrap(), ReplicationMode::Polling);
+    assert!(ReplicationMode::from_str("bogus").is_err());
+}
+
+/// Tests construction of the START_REPLICATION command.
+/// Verifies identifiers are quoted and the LSN is formatted as X/Y.
+#[test]
+fn test_build_start_replication_command() {
+    let plugin_options = build_plugin_options("my_pub", &ReplicationOptions::default()).unwrap();
+    let command = build_start_replication_command("my_slot", Lsn(0x0000_0001_016B_2D50), &plugin_options);
+    assert_eq!(
+        command,
+        "START_REPLICATION SLOT \"my_slot\" LOGICAL 1/16B2D50 (proto_version '1', publication_names '\"my_pub\"')"
+    );
+}
+
+/// Tests parsing of delivery mode names from the CLI.
+#[test]
+fn test_delivery_mode_from_str() {
+    assert_eq!(DeliveryMode::from_str("at-least-once").unwrap(), DeliveryMode::AtLeastOnce);
+    assert_eq!(DeliveryMode::from_str("AT_MOST_ONCE").unwrap(), DeliveryMode::AtMostOnce);
+    assert!(DeliveryMode::from_str("exactly-once").is_err());
+}
+
+/// Tests encoding of Standby Status Update ('r') frames.
+/// Verifies the write/flush/apply positions, clock and reply flag layout.
+#[test]
+fn test_encode_standby_status_update() {
+    let data = encode_standby_status_update(Lsn(0x30), Lsn(0x20), Lsn(0x10), 99, true);
+
+    assert_eq!(data.len(), 34);
+    assert_eq!(data[0], b'r');
+    assert_eq!(u64::from_be_bytes(data[1..9].try_into().unwrap()), 0x30);
+    assert_eq!(u64::from_be_bytes(data[9..17].try_into().unwrap()), 0x20);
+    assert_eq!(u64::from_be_bytes(data[17..25].try_into().unwrap()), 0x10);
+    assert_eq!(i64::from_be_bytes(data[25..33].try_into().unwrap()), 99);
+    assert_eq!(data[33], 1);
+}
+
+/// Tests the default replication options.
+/// Verifies streaming with at-least-once delivery and a 10 second status interval.
+#[test]
+fn test_replication_options_default() {
+    let options = ReplicationOptions::default();
+
+    assert!(!options.create_slot);
+    assert!(options.start_lsn.is_none());
+    assert_eq!(options.mode, ReplicationMode::Streaming);
+    assert_eq!(options.delivery, DeliveryMode::AtLeastOnce);
+    assert_eq!(options.status_interval, std::time::Duration::from_secs(10));
+}
+
+/// Tests pgoutput plugin options for streamed transactions.
+/// Verifies protocol v2 with streaming enabled and rejection of streaming on v1.
+#[test]
+fn test_build_plugin_options_streaming() {
+    let options = ReplicationOptions {
+        protocol_version: 2,
+        streaming: true,
+        ..Default::default()
+    };
+    let plugin_options = build_plugin_options("my_pub", &options).unwrap();
+    assert_eq!(
+        plugin_options,
+        vec![
+            ("proto_version", "2".to_string()),
+            ("publication_names", "\"my_pub\"".to_string()),
+            ("streaming", "on".to_string()),
+        ]
+    );
+
+    let invalid = ReplicationOptions {
+        streaming: true,
+        ..Default::default()
+    };
+    assert!(build_plugin_options("my_pub", &invalid).is_err());
+}
+
+/// Tests pgoutput plugin options for several publications.
+/// Verifies each name of a comma-separated list is quoted on its own and empty names are rejected.
+#[test]
+fn test_build_plugin_options_publications() {
+    let options = ReplicationOptions::default();
+    let plugin_options = build_plugin_options("pub_a, Pub_B", &options).unwrap();
+    assert_eq!(plugin_options[1], ("publication_names", "\"pub_a\",\"Pub_B\"".to_string()));
+
+    assert!(build_plugin_options("pub_a,", &options).is_err());
+}
+
+/// Tests pgoutput plugin options for two-phase commit decoding.
+/// Verifies protocol v3 with two_phase enabled and rejection on older protocol versions.
+#[test]
+fn test_build_plugin_options_two_phase() {
+    let options = ReplicationOptions {
+        protocol_version: 3,
+        two_phase: true,
+        ..Default::default()
+    };
+    let plugin_options = build_plugin_options("my_pub", &options).unwrap();
+    assert_eq!(
+        plugin_options,
+        vec![
+            ("proto_version", "3".to_string()),
+            ("publication_names", "\"my_pub\"".to_string()),
+            ("two_phase", "on".to_string()),
+        ]
+    );
+
+    let invalid = ReplicationOptions {
+        protocol_version: 2,
+        two_phase: true,
+        ..Default::default()
+    };
+    assert!(build_plugin_options("my_pub", &invalid).is_err());
+}
+
+/// Tests that binary mode adds the pgoutput `binary` option.
+#[test]
+fn test_build_plugin_options_binary() {
+    let options = ReplicationOptions {
+        binary: true,
+        ..Default::default()
+    };
+    let plugin_options = build_plugin_options("my_pub", &options).unwrap();
+    assert!(plugin_options.contains(&("binary", "true".to_string())));
+}
+
+/// Tests that requesting logical decoding messages adds the pgoutput `messages` option.
+#[test]
+fn test_build_plugin_options_messages() {
+    let options = ReplicationOptions {
+        messages: true,
+        message_prefixes: vec!["audit".to_string()],
+        ..Default::default()
+    };
+    let plugin_options = build_plugin_options("my_pub", &options).unwrap();
+    assert!(plugin_options.contains(&("messages", "true".to_string())));
+
+    let plugin_options = build_plugin_options("my_pub", &ReplicationOptions::default()).unwrap();
+    assert!(!plugin_options.iter().any(|(name, _)| *name == "messages"));
+}
+
+/// Tests construction of the CREATE_REPLICATION_SLOT command used for snapshots.
+/// Verifies the snapshot is exported and two-phase slots use the option list syntax.
+#[test]
+fn test_build_create_slot_command() {
+    assert_eq!(
+        build_create_slot_command("my_slot", false),
+        "CREATE_REPLICATION_SLOT \"my_slot\" LOGICAL pgoutput EXPORT_SNAPSHOT"
+    );
+    assert_eq!(
+        build_create_slot_command("my_slot", true),
+        "CREATE_REPLICATION_SLOT \"my_slot\" LOGICAL pgoutput (TWO_PHASE true, SNAPSHOT 'export')"
+    );
+}
+
+/// Tests that a snapshot cannot be combined with a start position.
+#[tokio::test]
+async fn test_snapshot_rejects_start_lsn() {
+    let options = ReplicationOptions {
+        snapshot: Some(pgoutput_stream::snapshot::SnapshotOptions::default()),
+        start_lsn: Some(Lsn(0x100)),
+        ..Default::default()
+    };
+    let error = ReplicationStream::new("host=localhost", "my_slot", "my_pub", options).await.err().unwrap();
+    assert!(error.to_string().contains("start LSN"));
+}
@@ -0,0 +1,162 @@
+use pgoutput_stream::decoder::{ColumnInfo, ReplicaIdentity};
+use pgoutput_stream::lsn::Lsn;
+use pgoutput_stream::snapshot::*;
+
+fn column(name: &str, type_id: u32) -> ColumnInfo {
+    ColumnInfo {
+        name: name.to_string(),
+        type_id,
+        flags: 0,
+        type_modifier: -1,
+        type_name: None,
+        length: None,
+        precision: None,
+        scale: None,
+    }
+}
+
+fn table_plan(partitioned: bool, row_filter: Option<&str>, chunk_key: Option<&str>) -> TablePlan {
+    TablePlan {
+        table: PublishedTable {
+            relation_id: 16384,
+            schema: "public".to_string(),
+            table: "Users".to_string(),
+            replica_identity: ReplicaIdentity::Default,
+            partitioned,
+            column_list: None,
+            row_filter: row_filter.map(str::to_string),
+        },
+        columns: vec![column("id", 23), column("Name", 25)],
+        chunk_key: chunk_key.map(str::to_string),
+        chunks: vec![Chunk { start: None, end: None, done: false }],
+    }
+}
+
+fn chunk(start: Option<i64>, end: Option<i64>) -> Chunk {
+    Chunk { start, end, done: false }
+}
+
+/// Tests splitting COPY text rows into values.
+/// Verifies `\N` becomes NULL while an escaped backslash followed by N stays text.
+#[test]
+fn test_parse_copy_line_nulls() {
+    assert_eq!(
+        parse_copy_line(b"1\t\\N\t\t\\\\N"),
+        vec![Some("1".to_string()), None, Some(String::new()), Some("\\N".to_string())]
+    );
+}
+
+/// Tests unescaping of COPY's text format.
+/// Verifies control character, octal and hex escapes and UTF-8 passthrough.
+#[test]
+fn test_parse_copy_line_escapes() {
+    assert_eq!(
+        parse_copy_line(b"a\\tb\\nc\\\\d\tx\\101\\x42y\tcaf\xc3\xa9"),
+        vec![
+            Some("a\tb\nc\\d".to_string()),
+            Some("xABy".to_string()),
+            Some("café".to_string()),
+        ]
+    );
+}
+
+/// Tests the COPY statement for an unchunked table.
+/// Verifies identifiers are quoted and inheritance children are excluded.
+#[test]
+fn test_build_copy_query() {
+    assert_eq!(
+        build_copy_query(&table_plan(false, None, None), &chunk(None, None)),
+        "COPY (SELECT \"id\", \"Name\" FROM ONLY \"public\".\"Users\") TO STDOUT"
+    );
+}
+
+/// Tests the COPY statement for partitioned tables and publication row filters.
+/// Verifies partitions are read through their root and the filter is applied as a whole.
+#[test]
+fn test_build_copy_query_partitioned_with_row_filter() {
+    assert_eq!(
+        build_copy_query(&table_plan(true, Some("a OR b"), None), &chunk(None, None)),
+        "COPY (SELECT \"id\", \"Name\" FROM \"public\".\"Users\" WHERE (a OR b)) TO STDOUT"
+    );
+}
+
+/// Tests the COPY statement for key range chunks.
+/// Verifies the bounds are half-open and combined with the row filter.
+#[test]
+fn test_build_copy_query_chunk() {
+    let plan = table_plan(false, Some("a OR b"), Some("id"));
+    assert_eq!(
+        build_copy_query(&plan, &chunk(Some(100), Some(200))),
+        "COPY (SELECT \"id\", \"Name\" FROM ONLY \"public\".\"Users\" WHERE (a OR b) AND \"id\" >= 100 AND \"id\" < 200) TO STDOUT"
+    );
+    assert_eq!(
+        build_copy_query(&plan, &chunk(None, Some(100))),
+        "COPY (SELECT \"id\", \"Name\" FROM ONLY \"public\".\"Users\" WHERE (a OR b) AND \"id\" < 100) TO STDOUT"
+    );
+}
+
+/// Tests splitting a key range into chunks at boundary keys.
+/// Verifies contiguous ranges with open first and last chunks.
+#[test]
+fn test_chunk_ranges() {
+    assert_eq!(
+        chunk_ranges(&[101, 201]),
+        vec![chunk(None, Some(101)), chunk(Some(101), Some(201)), chunk(Some(201), None)]
+    );
+    // Small and empty tables get a single chunk
+    assert_eq!(chunk_ranges(&[]), vec![chunk(None, None)]);
+}
+
+/// Tests the number of chunks planned for a table.
+/// Verifies it follows the row count, not the key range, and is capped.
+#[test]
+fn test_chunk_count() {
+    assert_eq!(chunk_count(250, 100), 3);
+    assert_eq!(chunk_count(100, 100), 1);
+    assert_eq!(chunk_count(0, 100), 1);
+    // Sparse keys (e.g. ids 1 and 9e18) with a small chunk size stay a single chunk
+    assert_eq!(chunk_count(2, 1000), 1);
+    assert_eq!(chunk_ranges(&[9_000_000_000_000_000_000]).len(), 2);
+    // Huge tables are capped, and a zero chunk size does not divide by zero
+    assert_eq!(chunk_count(i64::MAX, 1), MAX_CHUNKS_PER_TABLE);
+    assert_eq!(chunk_count(5, 0), 5);
+}
+
+/// Tests picking chunk boundaries from a sorted sample of keys.
+/// Verifies equal-sized parts, sparse keys and duplicate or missing sample values.
+#[test]
+fn test_sample_boundaries() {
+    let sample: Vec<i64> = (1..=12).collect();
+    assert_eq!(sample_boundaries(&sample, 3), vec![5, 9]);
+    // Sparse keys split where the rows are, not where the key range is
+    let sparse = [1, 2, 3, 9_000_000_000_000_000_000, 9_000_000_000_000_000_001, 9_000_000_000_000_000_002];
+    assert_eq!(sample_boundaries(&sparse, 2), vec![9_000_000_000_000_000_000]);
+    assert_eq!(sample_boundaries(&[7, 7, 7, 7], 4), vec![7]);
+    assert!(sample_boundaries(&[], 4).is_empty());
+}
+
+/// Tests persisting snapshot progress.
+/// Verifies the state file round-trips and only undelivered chunks are pending.
+#[test]
+fn test_snapshot_state_roundtrip() {
+    let mut plan = table_plan(false, None, Some("id"));
+    plan.chunks = vec![
+        Chunk { start: None, end: Some(101), done: true },
+        chunk(Some(101), None),
+    ];
+    let state = SnapshotState {
+        slot: "my_slot".to_string(),
+        publication: "my_pub".to_string(),
+        consistent_point: Lsn(0x16B2D50),
+        tables: vec![plan],
+        complete: false,
+    };
+    assert_eq!(state.pending_chunks(), vec![(0, 1)]);
+
+    let path = std::env::temp_dir().join(format!("pgoutput-stream-{}.snapshot.json", std::process::id()));
+    assert!(SnapshotState::load(&path).unwrap().is_none());
+    state.save(&path).unwrap();
+    let loaded = SnapshotState::load(&path).unwrap().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, state);
+}
@@ -0,0 +1,261 @@
+use pgoutput_stream::value::*;
+
+/// Tests binary decoding of boolean and integer types.
+#[test]
+fn test_decode_binary_scalars() {
+    assert_eq!(decode_binary(16, &[1]).unwrap(), PgValue::Bool(true));
+    assert_eq!(decode_binary(21, &(-7i16).to_be_bytes()).unwrap(), PgValue::Int(-7));
+    assert_eq!(decode_binary(23, &42i32.to_be_bytes()).unwrap(), PgValue::Int(42));
+    assert_eq!(decode_binary(20, &i64::MAX.to_be_bytes()).unwrap(), PgValue::Int(i64::MAX));
+    assert_eq!(decode_binary(25, b"hello").unwrap(), PgValue::Text("hello".to_string()));
+}
+
+/// Tests binary decoding of float4 and float8.
+/// Verifies float4 keeps its shortest decimal form and special values print like PostgreSQL.
+#[test]
+fn test_decode_binary_floats() {
+    assert_eq!(decode_binary(700, &0.1f32.to_be_bytes()).unwrap(), PgValue::Float(0.1));
+    assert_eq!(decode_binary(701, &2.5f64.to_be_bytes()).unwrap(), PgValue::Float(2.5));
+    assert_eq!(decode_binary(701, &f64::INFINITY.to_be_bytes()).unwrap().to_string(), "Infinity");
+}
+
+/// Tests that fixed-width values with the wrong length are rejected.
+#[test]
+fn test_decode_binary_wrong_length() {
+    assert!(decode_binary(23, &[0, 1]).is_err());
+    assert!(decode_binary(2950, &[0; 4]).is_err());
+}
+
+/// Tests binary decoding of numeric values into exact decimal strings.
+#[test]
+fn test_decode_binary_numeric() {
+    // numeric format: ndigits(2) + weight(2) + sign(2) + dscale(2) + base-10000 digits
+    fn numeric(weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(digits.len() as u16).to_be_bytes());
+        data.extend_from_slice(&weight.to_be_bytes());
+        data.extend_from_slice(&sign.to_be_bytes());
+        data.extend_from_slice(&dscale.to_be_bytes());
+        for d in digits {
+            data.extend_from_slice(&d.to_be_bytes());
+        }
+        data
+    }
+
+    // 12345678.90
+    let value = decode_binary(1700, &numeric(1, 0, 2, &[1234, 5678, 9000])).unwrap();
+    assert_eq!(value, PgValue::Numeric("12345678.90".to_string()));
+    // -0.0012
+    let value = decode_binary(1700, &numeric(-1, 0x4000, 4, &[12])).unwrap();
+    assert_eq!(value, PgValue::Numeric("-0.0012".to_string()));
+    // 0
+    let value = decode_binary(1700, &numeric(0, 0, 0, &[])).unwrap();
+    assert_eq!(value, PgValue::Numeric("0".to_string()));
+    // NaN
+    let value = decode_binary(1700, &numeric(0, 0xC000, 0, &[])).unwrap();
+    assert_eq!(value, PgValue::Numeric("NaN".to_string()));
+}
+
+/// Tests binary decoding of date, time, timestamp, timestamptz and interval.
+/// Verifies values are rendered in PostgreSQL's text output format.
+#[test]
+fn test_decode_binary_temporal() {
+    // 2024-03-15 is 8840 days after 2000-01-01
+    assert_eq!(decode_binary(1082, &8840i32.to_be_bytes()).unwrap().to_string(), "2024-03-15");
+    assert_eq!(decode_binary(1082, &i32::MAX.to_be_bytes()).unwrap().to_string(), "infinity");
+
+    let time = 13 * 3_600_000_000i64 + 30 * 60_000_000 + 500_000;
+    assert_eq!(decode_binary(1083, &time.to_be_bytes()).unwrap().to_string(), "13:30:00.5");
+
+    let ts = 8840 * 86_400_000_000i64 + time;
+    assert_eq!(decode_binary(1114, &ts.to_be_bytes()).unwrap().to_string(), "2024-03-15 13:30:00.5");
+    assert_eq!(decode_binary(1184, &ts.to_be_bytes()).unwrap().to_string(), "2024-03-15 13:30:00.5+00");
+
+    // interval format: microseconds(8) + days(4) + months(4)
+    let mut interval = Vec::new();
+    interval.extend_from_slice(&3_723_000_000i64.to_be_bytes());
+    interval.extend_from_slice(&3i32.to_be_bytes());
+    interval.extend_from_slice(&14i32.to_be_bytes());
+    assert_eq!(
+        decode_binary(1186, &interval).unwrap().to_string(),
+        "1 year 2 mons 3 days 01:02:03"
+    );
+}
+
+/// Tests binary decoding of bytea, uuid, json and jsonb.
+#[test]
+fn test_decode_binary_bytea_uuid_json() {
+    assert_eq!(decode_binary(17, &[0xde, 0xad]).unwrap().to_string(), "\\xdead");
+
+    let uuid: Vec<u8> = (0u8..16).collect();
+    assert_eq!(
+        decode_binary(2950, &uuid).unwrap().to_string(),
+        "00010203-0405-0607-0809-0a0b0c0d0e0f"
+    );
+
+    let json = decode_binary(114, br#"{"a": 1}"#).unwrap();
+    assert_eq!(json.to_json(), serde_json::json!({"a": 1}));
+
+    // jsonb is prefixed with a version byte
+    let jsonb = decode_binary(3802, b"\x01[1, 2]").unwrap();
+    assert_eq!(jsonb, PgValue::Json(serde_json::json!([1, 2])));
+}
+
+/// Tests binary decoding of arrays, including NULL elements and two dimensions.
+#[test]
+fn test_decode_binary_arrays() {
+    // array format: ndim(4) + has_nulls(4) + element_oid(4) + [size(4) + lbound(4)] * ndim + elements
+    fn int4_array(dims: &[i32], elements: &[Option<i32>]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(dims.len() as i32).to_be_bytes());
+        data.extend_from_slice(&1i32.to_be_bytes());
+        data.extend_from_slice(&23u32.to_be_bytes());
+        for size in dims {
+            data.extend_from_slice(&size.to_be_bytes());
+            data.extend_from_slice(&1i32.to_be_bytes());
+        }
+        for element in elements {
+            match element {
+                Some(v) => {
+                    data.extend_from_slice(&4i32.to_be_bytes());
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                None => data.extend_from_slice(&(-1i32).to_be_bytes()),
+            }
+        }
+        data
+    }
+
+    let value = decode_binary(1007, &int4_array(&[3], &[Some(1), None, Some(3)])).unwrap();
+    assert_eq!(value.to_string(), "{1,NULL,3}");
+    assert_eq!(value.to_json(), serde_json::json!([1, null, 3]));
+
+    let value = decode_binary(1007, &int4_array(&[2, 2], &[Some(1), Some(2), Some(3), Some(4)])).unwrap();
+    assert_eq!(value.to_json(), serde_json::json!([[1, 2], [3, 4]]));
+
+    let empty = decode_binary(1007, &int4_array(&[], &[])).unwrap();
+    assert_eq!(empty.to_string(), "{}");
+}
+
+/// Tests the fallback for types without a binary decoder.
+/// Verifies UTF-8 payloads (e.g. enum labels) become text and others raw bytes.
+#[test]
+fn test_decode_binary_unknown_type() {
+    assert_eq!(decode_binary(99999, b"active").unwrap(), PgValue::Text("active".to_string()));
+    assert_eq!(decode_binary(99999, &[0xff, 0xfe]).unwrap(), PgValue::Bytea(vec![0xff, 0xfe]));
+}
+
+/// Tests JSON serialization of typed values inside a change.
+/// Verifies numbers and booleans keep their type while text stays a string.
+#[test]
+fn test_pg_value_serialization() {
+    assert_eq!(serde_json::to_string(&PgValue::Int(5)).unwrap(), "5");
+    assert_eq!(serde_json::to_string(&PgValue::Bool(false)).unwrap(), "false");
+    assert_eq!(serde_json::to_string(&PgValue::Text("5".to_string())).unwrap(), "\"5\"");
+    assert_eq!(serde_json::to_string(&PgValue::Numeric("1.50".to_string())).unwrap(), "\"1.50\"");
+
+    let value: PgValue = serde_json::from_str("[1, null]").unwrap();
+    assert_eq!(value, PgValue::Array(vec![Some(PgValue::Int(1)), None]));
+}
+
+/// Tests JSON rendering of values that have no native JSON type.
+/// Verifies bytea as base64, RFC 3339 timestamptz, offset-free timestamp and ISO 8601 intervals.
+#[test]
+fn test_pg_value_lossless_json() {
+    assert_eq!(PgValue::Bytea(vec![0xde, 0xad, 0xbe, 0xef]).to_json(), "3q2+7w==");
+
+    let ts = decode_binary(1114, &(8840 * 86_400_000_000i64 + 500_000).to_be_bytes()).unwrap();
+    assert_eq!(ts.to_json(), "2024-03-15T00:00:00.5");
+    let tstz = decode_binary(1184, &(8840 * 86_400_000_000i64).to_be_bytes()).unwrap();
+    assert_eq!(tstz.to_json(), "2024-03-15T00:00:00Z");
+
+    let interval = |months, days, microseconds| PgValue::Interval { months, days, microseconds };
+    assert_eq!(interval(14, 3, 3_723_500_000).to_json(), "P1Y2M3DT1H2M3.5S");
+    assert_eq!(interval(0, -1, 0).to_json(), "P-1D");
+    assert_eq!(interval(0, 0, -90_000_000).to_json(), "PT-1M-30S");
+    assert_eq!(interval(0, 0, 0).to_json(), "PT0S");
+}
+
+/// Tests parsing of text-format values into typed values.
+/// Verifies exact numerics, json, bytea, uuid, temporal types and the text fallback.
+#[test]
+fn test_decode_text_scalars() {
+    assert_eq!(decode_text(16, "t"), PgValue::Bool(true));
+    assert_eq!(decode_text(20, "-9223372036854775808"), PgValue::Int(i64::MIN));
+    assert_eq!(decode_text(701, "-Infinity"), PgValue::Float(f64::NEG_INFINITY));
+    assert_eq!(
+        decode_text(1700, "12345678901234567890.123456789").to_json(),
+        "12345678901234567890.123456789"
+    );
+    assert_eq!(decode_text(3802, r#"{"a": [1, null]}"#).to_json(), serde_json::json!({"a": [1, null]}));
+    assert_eq!(decode_text(17, "\\xdeadbeef"), PgValue::Bytea(vec![0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(
+        decode_text(2950, "00010203-0405-0607-0809-0a0b0c0d0e0f"),
+        PgValue::Uuid((0u8..16).collect::<Vec<_>>().try_into().unwrap())
+    );
+
+    assert_eq!(decode_text(1082, "2024-03-15").to_string(), "2024-03-15");
+    assert_eq!(decode_text(1114, "2024-03-15 13:30:00.5").to_json(), "2024-03-15T13:30:00.5");
+    assert_eq!(decode_text(1184, "2024-03-15 13:30:00+05:30").to_json(), "2024-03-15T08:00:00Z");
+    assert_eq!(decode_text(1184, "2024-03-15 13:30:00-08").to_json(), "2024-03-15T21:30:00Z");
+    assert_eq!(
+        decode_text(1186, "1 year 2 mons -3 days -01:02:03.25"),
+        PgValue::Interval { months: 14, days: -3, microseconds: -3_723_250_000 }
+    );
+
+    // Values that don't parse as their type stay text
+    assert_eq!(decode_text(1184, "infinity"), PgValue::Text("infinity".to_string()));
+    assert_eq!(decode_text(1082, "0044-03-15 BC"), PgValue::Text("0044-03-15 BC".to_string()));
+    assert_eq!(decode_text(1186, "P1Y"), PgValue::Text("P1Y".to_string()));
+    assert_eq!(decode_text(23, "abc"), PgValue::Text("abc".to_string()));
+    assert_eq!(decode_text(99999, "active"), PgValue::Text("active".to_string()));
+}
+
+/// Tests parsing of text-format array literals.
+/// Verifies nesting, NULL elements, quoting and escapes, and typed elements.
+#[test]
+fn test_decode_text_arrays() {
+    assert_eq!(decode_text(1007, "{1,NULL,3}").to_json(), serde_json::json!([1, null, 3]));
+    assert_eq!(decode_text(1007, "{{1,2},{3,4}}").to_json(), serde_json::json!([[1, 2], [3, 4]]));
+    assert_eq!(decode_text(1007, "[0:1]={5,6}").to_json(), serde_json::json!([5, 6]));
+    assert_eq!(decode_text(1007, "{}").to_json(), serde_json::json!([]));
+    assert_eq!(
+        decode_text(1009, r#"{plain,"with space","NULL","quote \" and \\",NULL,""}"#).to_json(),
+        serde_json::json!(["plain", "with space", "NULL", "quote \" and \\", null, ""])
+    );
+    assert_eq!(decode_text(1231, "{1.10,NaN}").to_json(), serde_json::json!(["1.10", "NaN"]));
+    assert_eq!(
+        decode_text(3807, r#"{"{\"a\": 1}",null}"#).to_json(),
+        serde_json::json!([{"a": 1}, null])
+    );
+    assert_eq!(decode_text(1001, r#"{"\\x0102"}"#).to_json(), serde_json::json!(["AQI="]));
+
+    // Array literals round-trip through the text output format
+    let value = decode_text(1009, r#"{{a,"b c"},{NULL,"d\"e"}}"#);
+    assert_eq!(value.to_string(), r#"{{a,"b c"},{NULL,"d\"e"}}"#);
+
+    // Malformed literals and arrays deeper than PostgreSQL allows stay text
+    assert_eq!(decode_text(1007, "{1,2"), PgValue::Text("{1,2".to_string()));
+    assert_eq!(decode_text(1007, "{1}x"), PgValue::Text("{1}x".to_string()));
+    assert_eq!(decode_text(1007, "{{{{{{{1}}}}}}}"), PgValue::Text("{{{{{{{1}}}}}}}".to_string()));
+}
+
+/// Tests decoding of type modifiers and naming of built-in types.
+/// Verifies negative numeric scales, bit lengths, interval precision and unknown OIDs.
+#[test]
+fn test_type_modifier_decode() {
+    let numeric = TypeModifier::decode(1700, ((5 << 16) | (-2i32 & 0x7ff)) + 4);
+    assert_eq!((numeric.precision, numeric.scale), (Some(5), Some(-2)));
+
+    assert_eq!(TypeModifier::decode(1042, 1 + 4).length, Some(1));
+    assert_eq!(TypeModifier::decode(1560, 8).length, Some(8));
+    assert_eq!(TypeModifier::decode(1186, 6).precision, Some(6));
+    // INTERVAL DAY TO SECOND without a precision
+    assert_eq!(TypeModifier::decode(1186, (0x0c00 << 16) | 0xffff), TypeModifier::default());
+    assert_eq!(TypeModifier::decode(1043, -1), TypeModifier::default());
+    assert_eq!(TypeModifier::decode(70001, 42), TypeModifier::default());
+
+    assert_eq!(builtin_type_name(1184), Some("timestamp with time zone"));
+    assert_eq!(builtin_type_name(1231), Some("numeric[]"));
+    assert_eq!(builtin_type_name(70001), None);
+}